@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, UserAccount};
+
+// Length of the airdrop program in weeks
+pub const AIRDROP_WEEKS: usize = 36;
+
+const SECONDS_PER_WEEK: i64 = 7 * 24 * 60 * 60;
+
+// DONUT distributed among the matrices completed in each airdrop week
+pub const WEEKLY_DISTRIBUTIONS: [u64; AIRDROP_WEEKS] = [
+    240_081,    259_617,    279_997,    301_268,    323_478,    346_675,
+    370_908,    396_224,    422_672,    450_303,    479_169,    509_323,
+    540_819,    573_712,    608_059,    643_919,    681_351,    720_417,
+    761_179,    803_704,    848_057,    894_308,    942_525,    992_783,
+    1_045_139,  1_099_731,  1_156_576,  1_215_747,  1_317_311,  1_391_342,
+    1_467_912,  1_547_090,  1_628_943,  1_713_547,  1_800_978,  1_891_317,
+];
+
+pub fn week_distribution(week: u8) -> u64 {
+    if week == 0 || week as usize > AIRDROP_WEEKS {
+        return 0;
+    }
+    WEEKLY_DISTRIBUTIONS[(week - 1) as usize]
+}
+
+// Airdrop week (1..=36) at `now`, or 0 before the start and after the last week
+pub fn week_at(start_timestamp: i64, now: i64) -> u8 {
+    if now < start_timestamp {
+        return 0;
+    }
+
+    let elapsed_weeks = (now - start_timestamp) / SECONDS_PER_WEEK + 1;
+    if elapsed_weeks > AIRDROP_WEEKS as i64 {
+        return 0;
+    }
+    elapsed_weeks as u8
+}
+
+// Result of a closed week, used to pay users for the matrices they completed in it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WeekSnapshot {
+    pub week_number: u8,
+    pub total_matrices: u64,
+    pub donut_distributed: u64,
+    pub donut_per_matrix: u64,
+    pub week_end_timestamp: i64,
+}
+
+impl WeekSnapshot {
+    pub const SIZE: usize = 1 + 8 + 8 + 8 + 8;
+}
+
+// Matrices a user completed in one airdrop week
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UserWeekData {
+    pub week_number: u8,
+    pub matrices_completed: u64,
+}
+
+impl UserWeekData {
+    pub const SIZE: usize = 1 + 8;
+}
+
+// Weekly DONUT airdrop to users completing matrices, stored in the `airdrop_state` PDA
+#[account]
+#[derive(Default, Debug)]
+pub struct AirdropState {
+    pub active: bool,
+    pub program_start_timestamp: i64,
+    pub current_week: u8,
+    pub total_matrices_this_week: u64,
+    pub closed_weeks: Vec<WeekSnapshot>,
+}
+
+impl AirdropState {
+    pub const SIZE: usize = 1 + 8 + 1 + 8 + 4 + (AIRDROP_WEEKS * WeekSnapshot::SIZE);
+
+    pub fn start(&mut self, now: i64) {
+        self.active = true;
+        self.program_start_timestamp = now;
+        self.current_week = 1;
+        self.total_matrices_this_week = 0;
+        self.closed_weeks = Vec::new();
+    }
+
+    // Move to the week containing `now`, closing the current one.
+    // Returns the snapshot of the closed week when it had any matrices.
+    pub fn advance_week(&mut self, now: i64) -> Result<Option<WeekSnapshot>> {
+        if !self.active {
+            return Ok(None);
+        }
+
+        let new_week = week_at(self.program_start_timestamp, now);
+        if new_week == self.current_week {
+            return Ok(None);
+        }
+
+        let mut closed = None;
+        if self.current_week > 0 && self.total_matrices_this_week > 0 && self.closed_weeks.len() < AIRDROP_WEEKS {
+            let donut_distributed = week_distribution(self.current_week);
+            let snapshot = WeekSnapshot {
+                week_number: self.current_week,
+                total_matrices: self.total_matrices_this_week,
+                donut_distributed,
+                donut_per_matrix: donut_distributed / self.total_matrices_this_week,
+                week_end_timestamp: now,
+            };
+            self.closed_weeks.push(snapshot);
+            closed = Some(snapshot);
+        }
+
+        self.total_matrices_this_week = 0;
+        self.current_week = new_week;
+
+        // Past the last week
+        if new_week == 0 {
+            self.active = false;
+        }
+
+        Ok(closed)
+    }
+
+    // Count a completed matrix for `user` in the current week.
+    // Returns the week it was counted in, or None when no airdrop week is running.
+    pub fn record_matrix_completion(&mut self, user: &mut UserAccount) -> Result<Option<u8>> {
+        if !self.active || self.current_week == 0 {
+            return Ok(None);
+        }
+
+        self.total_matrices_this_week = self.total_matrices_this_week
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        user.completed_matrices_total = user.completed_matrices_total
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        let week_number = self.current_week;
+        if let Some(existing) = user.weekly_matrices.iter_mut().find(|w| w.week_number == week_number) {
+            existing.matrices_completed = existing.matrices_completed
+                .checked_add(1)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        } else if user.weekly_matrices.len() < AIRDROP_WEEKS {
+            user.weekly_matrices.push(UserWeekData { week_number, matrices_completed: 1 });
+        }
+
+        Ok(Some(week_number))
+    }
+
+    // Add the user's share of every week closed since the last call to their earned DONUT
+    pub fn settle_user(&self, user: &mut UserAccount) -> Result<()> {
+        let mut total_earned = 0u64;
+
+        for week in self.closed_weeks.iter().filter(|w| w.week_number > user.last_processed_week) {
+            let user_matrices = user.weekly_matrices.iter()
+                .find(|w| w.week_number == week.week_number)
+                .map(|w| w.matrices_completed)
+                .unwrap_or(0);
+
+            let earned = user_matrices
+                .checked_mul(week.donut_per_matrix)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            total_earned = total_earned
+                .checked_add(earned)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+
+        user.total_donut_earned = user.total_donut_earned
+            .checked_add(total_earned)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        // Only closed weeks are settled, the running week is picked up once it closes
+        if let Some(last_closed) = self.closed_weeks.last() {
+            user.last_processed_week = user.last_processed_week.max(last_closed.week_number);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Smallest burn vault balance the crank swaps (0.01 SOL)
+pub const MIN_BUYBACK_AMOUNT: u64 = 10_000_000;
+
+// How slot burns are carried out
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BurnMode {
+    #[default]
+    Immediate, // Swapped and burned inside the registration
+    Batched,   // Queued in the burn vault for `execute_buyback_burn`
+}
+
+impl BurnMode {
+    pub const SIZE: usize = 1;
+}
+
+// Totals of the batched buyback, stored in the `buyback_state` PDA
+#[account]
+#[derive(Default, Debug)]
+pub struct BuybackState {
+    pub total_sol_spent: u64,
+    pub total_donut_burned: u64,
+    pub executions: u64,
+    pub last_execution: i64,
+}
+
+impl BuybackState {
+    pub const SIZE: usize = 8 + 8 + 8 + 8;
+
+    pub fn record(&mut self, sol_spent: u64, donut_burned: u64, now: i64) -> Result<()> {
+        self.total_sol_spent = self.total_sol_spent
+            .checked_add(sol_spent)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.total_donut_burned = self.total_donut_burned
+            .checked_add(donut_burned)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.executions = self.executions
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.last_execution = now;
+        Ok(())
+    }
+}
+
+// SOL queued in the burn vault, leaving what keeps the vault rent exempt
+pub fn queued_amount(vault_lamports: u64, rent_exempt_minimum: u64) -> u64 {
+    vault_lamports.saturating_sub(rent_exempt_minimum)
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, MAX_UPLINE_DEPTH};
+
+// Uplines a single registration or `continue_cascade` walks before saving the rest
+pub const MAX_CASCADE_STEPS: usize = 3;
+
+// How far a deposit got up the uplines it was handed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CascadeProgress {
+    Used,      // An upline's slot used the deposit
+    Stopped,   // An upline's matrix stayed open without using it
+    Forwarded, // Every upline completed its matrix and passed the deposit on
+}
+
+// Uplines processed in the registration itself, the rest are left to `continue_cascade`
+pub fn inline_steps(upline_count: usize) -> usize {
+    upline_count.min(MAX_CASCADE_STEPS)
+}
+
+// Recursion saved when it outgrows one transaction, stored in the registrant's `pending_cascade` PDA.
+// The PDA is created when the cascade is saved and closed back to the registrant when it completes.
+// The uplines it still has to reach are locked until it does, so registrations touching them wait
+// and every slot fills in single-transaction order; the rest of the tree keeps registering.
+#[account]
+#[derive(Default, Debug)]
+pub struct PendingCascade {
+    pub active: bool,
+    pub registrant: Pubkey,   // Wallet whose registration started the cascade
+    pub current_user: Pubkey, // Fills the next upline's slot
+    pub deposit: u64,         // Lamports held in the cascade vault
+    pub uplines: Vec<Pubkey>, // Upline PDAs in processing order, nearest first
+    pub next_upline_index: u8,
+    pub started_at: i64,
+}
+
+impl PendingCascade {
+    pub const SIZE: usize = 1 + 32 + 32 + 8 + 4 + (MAX_UPLINE_DEPTH * 32) + 1 + 8;
+
+    pub fn ensure_idle(&self) -> Result<()> {
+        if self.active {
+            msg!("Cascade of {} is pending at upline {}", self.registrant, self.next_upline_index);
+            return Err(error!(ErrorCode::CascadePending));
+        }
+        Ok(())
+    }
+
+    // Save a cascade that still has `uplines[next_upline_index..]` to walk
+    pub fn save(
+        &mut self,
+        registrant: Pubkey,
+        current_user: Pubkey,
+        deposit: u64,
+        uplines: Vec<Pubkey>,
+        next_upline_index: usize,
+        now: i64,
+    ) -> Result<()> {
+        self.ensure_idle()?;
+        if uplines.len() > MAX_UPLINE_DEPTH || next_upline_index >= uplines.len() {
+            return Err(error!(ErrorCode::InvalidUplineAccount));
+        }
+
+        *self = Self {
+            active: true,
+            registrant,
+            current_user,
+            deposit,
+            uplines,
+            next_upline_index: next_upline_index as u8,
+            started_at: now,
+        };
+        Ok(())
+    }
+
+    pub fn remaining_uplines(&self) -> &[Pubkey] {
+        self.uplines.get(self.next_upline_index as usize..).unwrap_or_default()
+    }
+
+    // Uplines the next `continue_cascade` walks
+    pub fn next_uplines(&self) -> &[Pubkey] {
+        let remaining = self.remaining_uplines();
+        &remaining[..inline_steps(remaining.len())]
+    }
+
+    // Move past `steps` uplines that all forwarded the deposit, the last one to `current_user`
+    pub fn advance(&mut self, steps: usize, current_user: Pubkey) -> Result<()> {
+        if steps > self.remaining_uplines().len() {
+            return Err(error!(ErrorCode::InvalidUplineAccount));
+        }
+        self.next_upline_index += steps as u8;
+        self.current_user = current_user;
+        Ok(())
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining_uplines().is_empty()
+    }
+
+    pub fn finish(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use chainlink_solana::v2::{read_feed_v2, ReadError};
+
+use crate::ErrorCode;
+
+// Discriminator, store header and one live transmission. The SDK splits the
+// account at the header without checking its length, so guard that here.
+const MIN_FEED_LEN: usize = 8 + 192 + 48;
+
+// Latest round reported by a Chainlink feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundData {
+    pub round_id: u32,
+    pub answer: i128,
+    pub timestamp: i64,
+    pub decimals: u8,
+}
+
+fn map_read_error(err: ReadError) -> Error {
+    msg!("Price feed read failed: {}", err);
+    match err {
+        ReadError::InvalidOwner | ReadError::InvalidDiscriminator | ReadError::InvalidAccount => {
+            error!(ErrorCode::InvalidPriceFeed)
+        }
+        _ => error!(ErrorCode::PriceFeedReadFailed),
+    }
+}
+
+// Read the latest round from a feed account owned by the Chainlink store program.
+// The account layout is decoded by the official `chainlink_solana` SDK.
+pub fn read_latest_round(feed: &AccountInfo, chainlink_program: &Pubkey) -> Result<RoundData> {
+    if feed.owner != chainlink_program {
+        msg!("Price feed owner mismatch: owner={}, expected={}", feed.owner, chainlink_program);
+        return Err(error!(ErrorCode::InvalidPriceFeed));
+    }
+
+    if feed.data_len() < MIN_FEED_LEN {
+        msg!("Price feed account too small: {}", feed.data_len());
+        return Err(error!(ErrorCode::PriceFeedReadFailed));
+    }
+
+    let feed_data = read_feed_v2(feed.try_borrow_data()?, feed.owner.to_bytes()).map_err(map_read_error)?;
+    let round = feed_data.latest_round_data().ok_or_else(|| {
+        msg!("Price feed has no rounds yet");
+        error!(ErrorCode::PriceFeedReadFailed)
+    })?;
+
+    if round.answer <= 0 {
+        msg!("Price feed returned a non-positive answer: {}", round.answer);
+        return Err(error!(ErrorCode::PriceFeedReadFailed));
+    }
+
+    Ok(RoundData {
+        round_id: round.round_id,
+        answer: round.answer,
+        timestamp: i64::from(round.timestamp),
+        decimals: feed_data.decimals(),
+    })
+}
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+
+use crate::buyback::BurnMode;
+use crate::dex::SwapVenue;
+use crate::matrix::{SlotAction, MAX_MATRIX_SLOTS};
+use crate::pending_burn::SwapFailurePolicy;
+use crate::sink::TerminalSinkPolicy;
+use crate::{matrix, price_guard, slippage, ErrorCode, MAX_UPLINE_DEPTH};
+
+// Addresses of the pool, vaults, mints and programs the protocol interacts with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct ProtocolAddresses {
+    // Pool address
+    pub pool: Pubkey,
+
+    // Vault A addresses (DONUT token vault)
+    pub a_vault: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub a_vault_lp_mint: Pubkey,
+    pub a_token_vault: Pubkey,
+
+    // Vault B addresses (SOL token vault)
+    pub b_vault: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub b_token_vault: Pubkey,
+    pub b_vault_lp_mint: Pubkey,
+
+    // Token addresses
+    pub token_mint: Pubkey,
+    pub wsol_mint: Pubkey,
+
+    // Meteora programs
+    pub meteora_vault_program: Pubkey,
+    pub meteora_amm_program: Pubkey, // Program of the configured swap venue
+
+    // Protocol fee accounts
+    pub protocol_token_a_fee: Pubkey,
+    pub protocol_token_b_fee: Pubkey,
+}
+
+impl ProtocolAddresses {
+    pub const SIZE: usize = 15 * 32;
+}
+
+// Deployment specific addresses and limits, stored in the `protocol_config` PDA
+#[account]
+#[derive(Default, Debug)]
+pub struct ProtocolConfig {
+    pub addresses: ProtocolAddresses,
+    pub minimum_usd_deposit: u64,   // USD with 8 decimals
+    pub max_price_feed_age: i64,    // Seconds
+    pub max_upline_depth: u8,
+    pub slot_actions: Vec<SlotAction>, // One action per matrix slot, the length is the matrix width
+    pub max_slippage_bps: u16,      // Ceiling on the slippage a registration may request
+    pub max_price_deviation_bps: u16, // Pool price move from its EMA that holds burns instead (0 = off)
+    pub swap_venue: SwapVenue,      // Pool the burn swap routes through
+    pub burn_mode: BurnMode,        // Burn inside registrations or batch them for the crank
+    pub swap_failure_policy: SwapFailurePolicy, // Revert or escrow a burn the pool cannot swap
+    pub terminal_sink_policy: TerminalSinkPolicy, // Burn, pay to the treasury or reserve a deposit no slot used
+}
+
+impl ProtocolConfig {
+    pub const SIZE: usize = ProtocolAddresses::SIZE + 8 + 8 + 1 + (4 + MAX_MATRIX_SLOTS) + 2 + 2 + SwapVenue::SIZE + BurnMode::SIZE + SwapFailurePolicy::SIZE + TerminalSinkPolicy::SIZE;
+
+    // Check the limits before they are written to the config account
+    pub fn validate_params(
+        minimum_usd_deposit: u64,
+        max_price_feed_age: i64,
+        max_upline_depth: u8,
+    ) -> Result<()> {
+        if minimum_usd_deposit == 0 {
+            msg!("Minimum USD deposit must be positive");
+            return Err(error!(ErrorCode::InvalidProtocolConfig));
+        }
+
+        if max_price_feed_age <= 0 {
+            msg!("Max price feed age must be positive: {}", max_price_feed_age);
+            return Err(error!(ErrorCode::InvalidProtocolConfig));
+        }
+
+        // User accounts are sized for MAX_UPLINE_DEPTH entries
+        if max_upline_depth == 0 || max_upline_depth as usize > MAX_UPLINE_DEPTH {
+            msg!("Max upline depth must be between 1 and {}: {}", MAX_UPLINE_DEPTH, max_upline_depth);
+            return Err(error!(ErrorCode::InvalidProtocolConfig));
+        }
+
+        Ok(())
+    }
+
+    // Check every limit of a whole config before it is written to the config account
+    pub fn validate(&self) -> Result<()> {
+        Self::validate_params(self.minimum_usd_deposit, self.max_price_feed_age, self.max_upline_depth)?;
+        matrix::validate_slot_actions(&self.slot_actions)?;
+        slippage::validate_max_slippage_bps(self.max_slippage_bps)?;
+        price_guard::validate_max_price_deviation_bps(self.max_price_deviation_bps)
+    }
+
+    pub fn max_upline_depth(&self) -> usize {
+        self.max_upline_depth as usize
+    }
+
+    pub fn matrix_width(&self) -> usize {
+        self.slot_actions.len()
+    }
+}
@@ -0,0 +1,85 @@
+// Fixed-point decimal used for price display and for the USD to lamports conversion.
+// A `Decimal` represents `value * 10^-decimals`.
+
+// Number of decimals of a lamport amount expressed in SOL
+pub const LAMPORTS_DECIMALS: u32 = 9;
+
+// Direction in which a division result is rounded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Decimal {
+    pub value: i128,
+    pub decimals: u32,
+}
+
+fn pow10(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+impl Decimal {
+    pub fn new(value: i128, decimals: u32) -> Self {
+        Decimal { value, decimals }
+    }
+
+    // Build a decimal from an oracle style (mantissa, exponent) pair
+    pub fn from_price(price: i128, expo: i32) -> Option<Self> {
+        if expo <= 0 {
+            Some(Decimal::new(price, expo.unsigned_abs()))
+        } else {
+            Some(Decimal::new(price.checked_mul(pow10(expo as u32)?)?, 0))
+        }
+    }
+
+    // Divide by `other`, returning the quotient with `decimals` decimals.
+    // Only defined for a non-negative dividend and a positive divisor.
+    pub fn checked_div(&self, other: &Decimal, decimals: u32, rounding: Rounding) -> Option<Decimal> {
+        if self.value < 0 || other.value <= 0 {
+            return None;
+        }
+
+        // self / other = (self.value * 10^(decimals + other.decimals - self.decimals)) / other.value / 10^decimals
+        let shift = i64::from(decimals) + i64::from(other.decimals) - i64::from(self.decimals);
+        let (numerator, denominator) = if shift >= 0 {
+            let factor = pow10(u32::try_from(shift).ok()?)?;
+            (self.value.checked_mul(factor)?, other.value)
+        } else {
+            let factor = pow10(u32::try_from(-shift).ok()?)?;
+            (self.value, other.value.checked_mul(factor)?)
+        };
+
+        let mut quotient = numerator / denominator;
+        if rounding == Rounding::Up && numerator % denominator != 0 {
+            quotient = quotient.checked_add(1)?;
+        }
+
+        Some(Decimal::new(quotient, decimals))
+    }
+}
+
+// Convert a USD amount into lamports at the given SOL/USD price.
+// The result is rounded up so a USD minimum is never undershot.
+pub fn usd_to_lamports(usd_amount: &Decimal, sol_price: &Decimal) -> Option<u64> {
+    let lamports = usd_amount.checked_div(sol_price, LAMPORTS_DECIMALS, Rounding::Up)?;
+    u64::try_from(lamports.value).ok()
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut scaled_val = self.value.to_string();
+        if scaled_val.len() <= self.decimals as usize {
+            scaled_val.insert_str(
+                0,
+                &vec!["0"; self.decimals as usize - scaled_val.len()].join(""),
+            );
+            scaled_val.insert_str(0, "0.");
+        } else {
+            scaled_val.insert(scaled_val.len() - self.decimals as usize, '.');
+        }
+        f.write_str(&scaled_val)
+    }
+}
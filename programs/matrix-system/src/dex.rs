@@ -0,0 +1,392 @@
+// Venues the burn swap can route through.
+// Registrations pass the same account slots whatever the venue; each adapter
+// documents how it reads them, so moving liquidity only takes a config change.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    self,
+    instruction::{AccountMeta, Instruction},
+};
+use anchor_spl::token::spl_token;
+
+use crate::price_tracker::PriceTracker;
+use crate::{get_vault_token_amount, layout, price_guard, quote, slippage, ErrorCode};
+
+// Meteora `swap` discriminator: sha256("global:swap")[0..8]
+pub const METEORA_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+// SPL token-swap `Swap` instruction tag
+pub const TOKEN_SWAP_SWAP_TAG: u8 = 1;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SwapVenue {
+    #[default]
+    MeteoraDynamicAmm,
+    ConstantProduct, // SPL token-swap pool on the constant-product curve
+}
+
+impl SwapVenue {
+    pub const SIZE: usize = 1;
+}
+
+// Pool, vault and program accounts passed for the swap, named after the Meteora pool
+#[derive(Clone)]
+pub struct VenueAccounts<'info> {
+    pub pool: AccountInfo<'info>,
+    pub a_vault: AccountInfo<'info>,
+    pub a_vault_lp: AccountInfo<'info>,
+    pub a_vault_lp_mint: AccountInfo<'info>,
+    pub a_token_vault: AccountInfo<'info>,
+    pub b_vault: AccountInfo<'info>,
+    pub b_vault_lp: AccountInfo<'info>,
+    pub b_vault_lp_mint: AccountInfo<'info>,
+    pub b_token_vault: AccountInfo<'info>,
+    pub protocol_token_fee: AccountInfo<'info>,
+    pub vault_program: AccountInfo<'info>,
+    pub amm_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+// A pool the burn can swap WSOL for DONUT on
+pub trait DexAdapter<'info> {
+    // DONUT and SOL backing the pool
+    fn reserves(&self, current_time: i64) -> Result<(u64, u64)>;
+
+    // DONUT received for `amount_in` WSOL against the given reserves, with the pool's curve and fees
+    fn quote_donut_out_at(&self, amount_in: u64, donut_reserve: u64, sol_reserve: u64) -> Result<u64>;
+
+    // DONUT received for `amount_in` WSOL after the pool's fees
+    fn quote_donut_out(&self, amount_in: u64, current_time: i64) -> Result<u64> {
+        let (donut_reserve, sol_reserve) = self.reserves(current_time)?;
+        let amount_out = self.quote_donut_out_at(amount_in, donut_reserve, sol_reserve)?;
+        msg!("Expected output: {} DONUT", amount_out);
+        Ok(amount_out)
+    }
+
+    // Swap `amount_in` WSOL from `source` into the DONUT account `destination`, both owned by
+    // `authority`; `signer_seeds` sign for an authority that is a program address
+    fn swap(
+        &self,
+        authority: &AccountInfo<'info>,
+        source: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()>;
+}
+
+// Adapter for the configured venue, swapping into `donut_mint`
+pub fn adapter<'a, 'info>(
+    venue: SwapVenue,
+    accounts: &'a VenueAccounts<'info>,
+    donut_mint: Pubkey,
+) -> Box<dyn DexAdapter<'info> + 'a> {
+    match venue {
+        SwapVenue::MeteoraDynamicAmm => Box::new(MeteoraDynamicAmm { accounts }),
+        SwapVenue::ConstantProduct => Box::new(ConstantProductPool { accounts, donut_mint }),
+    }
+}
+
+// DONUT the pool would return for `amount_in` WSOL with its liquidity moved to the tracked TWAP,
+// quoted on the same curve and fees as a live swap. None without a TWAP or readable reserves.
+pub fn fair_donut_out<'info>(
+    dex: &dyn DexAdapter<'info>,
+    price_tracker: &PriceTracker,
+    amount_in: u64,
+    current_time: i64,
+) -> Option<u64> {
+    let twap = price_tracker.twap(current_time)?;
+    let (donut_reserve, sol_reserve) = dex.reserves(current_time).ok()?;
+    let (donut_reserve, sol_reserve) = price_guard::reserves_at_price(donut_reserve, sol_reserve, twap)?;
+    dex.quote_donut_out_at(amount_in, donut_reserve, sol_reserve).ok()
+}
+
+// Swap through `dex` and return the DONUT that reached `destination`,
+// failing with a slippage error when it is below `minimum_amount_out`
+pub fn swap_for_donut<'info>(
+    dex: &dyn DexAdapter<'info>,
+    authority: &AccountInfo<'info>,
+    source: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let balance_before = layout::read_token_amount(destination)?;
+    dex.swap(authority, source, destination, amount_in, minimum_amount_out, signer_seeds)?;
+    let received = layout::read_token_amount(destination)?.saturating_sub(balance_before);
+
+    // Enforce the bound here as well, so a short swap fails with a slippage error
+    slippage::check_amount_out(received, minimum_amount_out)?;
+    Ok(received)
+}
+
+fn invoke_swap(instruction: &Instruction, account_infos: &[AccountInfo], signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    solana_program::program::invoke_signed(instruction, account_infos, signer_seeds).map_err(|e| {
+        msg!("Swap failed: {:?}", e);
+        error!(ErrorCode::SwapFailed)
+    })
+}
+
+fn venue_account_mismatch(name: &str, key: &Pubkey) -> Error {
+    msg!("Swap venue {} mismatch: {}", name, key);
+    error!(ErrorCode::InvalidSwapVenueAccount)
+}
+
+// ===== METEORA DYNAMIC AMM =====
+
+// Meteora dynamic AMM pool with DONUT in vault A and SOL in vault B
+pub struct MeteoraDynamicAmm<'a, 'info> {
+    pub accounts: &'a VenueAccounts<'info>,
+}
+
+impl<'a, 'info> MeteoraDynamicAmm<'a, 'info> {
+    pub fn swap_instruction(
+        &self,
+        authority: &Pubkey,
+        source: &Pubkey,
+        destination: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Instruction {
+        let accounts = self.accounts;
+        let mut data = METEORA_SWAP_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+        Instruction {
+            program_id: accounts.amm_program.key(),
+            accounts: vec![
+                AccountMeta::new(accounts.pool.key(), false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new(accounts.a_vault.key(), false),
+                AccountMeta::new(accounts.b_vault.key(), false),
+                AccountMeta::new(accounts.a_token_vault.key(), false),
+                AccountMeta::new(accounts.b_token_vault.key(), false),
+                AccountMeta::new(accounts.a_vault_lp_mint.key(), false),
+                AccountMeta::new(accounts.b_vault_lp_mint.key(), false),
+                AccountMeta::new(accounts.a_vault_lp.key(), false),
+                AccountMeta::new(accounts.b_vault_lp.key(), false),
+                AccountMeta::new(accounts.protocol_token_fee.key(), false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(accounts.vault_program.key(), false),
+                AccountMeta::new_readonly(accounts.token_program.key(), false),
+            ],
+            data,
+        }
+    }
+}
+
+impl<'a, 'info> DexAdapter<'info> for MeteoraDynamicAmm<'a, 'info> {
+    fn reserves(&self, current_time: i64) -> Result<(u64, u64)> {
+        let accounts = self.accounts;
+        let vault_program = accounts.vault_program.key;
+        let donut_amount = get_vault_token_amount(&accounts.a_vault, &accounts.a_vault_lp, &accounts.a_vault_lp_mint, vault_program, current_time)?;
+        let sol_amount = get_vault_token_amount(&accounts.b_vault, &accounts.b_vault_lp, &accounts.b_vault_lp_mint, vault_program, current_time)?;
+        Ok((donut_amount, sol_amount))
+    }
+
+    fn quote_donut_out_at(&self, amount_in: u64, donut_reserve: u64, sol_reserve: u64) -> Result<u64> {
+        let accounts = self.accounts;
+        layout::check_owner(&accounts.pool, accounts.amm_program.key)?;
+        let pool_data = accounts.pool.try_borrow_data()?;
+        let pool_view = layout::PoolView::decode(&pool_data)?;
+
+        if !pool_view.enabled() {
+            msg!("Pool is disabled");
+            return Err(error!(ErrorCode::PriceMeteoraReadFailed));
+        }
+        if donut_reserve == 0 || sol_reserve == 0 {
+            return Err(error!(ErrorCode::PriceMeteoraReadFailed));
+        }
+
+        // WSOL is token B, DONUT is token A
+        let swap_quote = quote::quote_swap(
+            &pool_view.fees(),
+            &pool_view.curve_type()?,
+            sol_reserve,
+            donut_reserve,
+            amount_in,
+            quote::TradeDirection::BtoA,
+        )
+        .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
+        Ok(swap_quote.amount_out)
+    }
+
+    fn swap(
+        &self,
+        authority: &AccountInfo<'info>,
+        source: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let accounts = self.accounts;
+        let instruction = self.swap_instruction(authority.key, source.key, destination.key, amount_in, minimum_amount_out);
+        invoke_swap(
+            &instruction,
+            &[
+                accounts.pool.clone(),
+                source.clone(),
+                destination.clone(),
+                accounts.a_vault.clone(),
+                accounts.b_vault.clone(),
+                accounts.a_token_vault.clone(),
+                accounts.b_token_vault.clone(),
+                accounts.a_vault_lp_mint.clone(),
+                accounts.b_vault_lp_mint.clone(),
+                accounts.a_vault_lp.clone(),
+                accounts.b_vault_lp.clone(),
+                accounts.protocol_token_fee.clone(),
+                authority.clone(),
+                accounts.vault_program.clone(),
+                accounts.token_program.clone(),
+            ],
+            signer_seeds,
+        )
+    }
+}
+
+// ===== CONSTANT PRODUCT =====
+
+// SPL token-swap pool on the constant-product curve. The slots hold:
+//   pool               swap state
+//   a_vault            swap authority, the program address of the swap state
+//   a_token_vault      DONUT reserve, token A of the swap state
+//   b_token_vault      WSOL reserve, token B of the swap state
+//   a_vault_lp_mint    pool token mint
+//   protocol_token_fee pool fee account
+//   amm_program        token-swap program
+// The other vault slots are not used.
+pub struct ConstantProductPool<'a, 'info> {
+    pub accounts: &'a VenueAccounts<'info>,
+    pub donut_mint: Pubkey,
+}
+
+impl<'a, 'info> ConstantProductPool<'a, 'info> {
+    // Fees of the swap state, once it is checked against the slot accounts
+    fn load_fees(&self) -> Result<crate::quote::TokenSwapFees> {
+        let accounts = self.accounts;
+        layout::check_owner(&accounts.pool, accounts.amm_program.key)?;
+        let data = accounts.pool.try_borrow_data()?;
+        let swap_state = layout::TokenSwapView::decode(&data)?;
+
+        if swap_state.curve_type() != layout::TOKEN_SWAP_CONSTANT_PRODUCT_CURVE {
+            msg!("Token-swap pool is not on the constant-product curve: {}", swap_state.curve_type());
+            return Err(error!(ErrorCode::InvalidSwapVenueAccount));
+        }
+
+        let donut_reserve = accounts.a_token_vault.key();
+        let sol_reserve = accounts.b_token_vault.key();
+        if (swap_state.token_a(), swap_state.token_b()) != (donut_reserve, sol_reserve) {
+            return Err(venue_account_mismatch("reserves", &donut_reserve));
+        }
+        // Another pool's swap state could hold any pair, so pin both sides to DONUT and WSOL
+        let donut_mints = (swap_state.token_a_mint(), layout::read_token_mint(&accounts.a_token_vault)?);
+        if donut_mints != (self.donut_mint, self.donut_mint) {
+            return Err(venue_account_mismatch("DONUT mint", &donut_mints.0));
+        }
+        let sol_mints = (swap_state.token_b_mint(), layout::read_token_mint(&accounts.b_token_vault)?);
+        if sol_mints != (spl_token::native_mint::ID, spl_token::native_mint::ID) {
+            return Err(venue_account_mismatch("WSOL mint", &sol_mints.0));
+        }
+        if swap_state.pool_mint() != accounts.a_vault_lp_mint.key() {
+            return Err(venue_account_mismatch("pool mint", accounts.a_vault_lp_mint.key));
+        }
+        if swap_state.pool_fee_account() != accounts.protocol_token_fee.key() {
+            return Err(venue_account_mismatch("fee account", accounts.protocol_token_fee.key));
+        }
+
+        let authority = Pubkey::create_program_address(
+            &[accounts.pool.key.as_ref(), &[swap_state.bump_seed()]],
+            accounts.amm_program.key,
+        )
+        .map_err(|_| venue_account_mismatch("authority", accounts.a_vault.key))?;
+        if authority != accounts.a_vault.key() {
+            return Err(venue_account_mismatch("authority", accounts.a_vault.key));
+        }
+
+        Ok(swap_state.fees())
+    }
+
+    pub fn swap_instruction(
+        &self,
+        authority: &Pubkey,
+        source: &Pubkey,
+        destination: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Instruction {
+        let accounts = self.accounts;
+        let mut data = vec![TOKEN_SWAP_SWAP_TAG];
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+        Instruction {
+            program_id: accounts.amm_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(accounts.pool.key(), false),
+                AccountMeta::new_readonly(accounts.a_vault.key(), false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(*source, false),
+                AccountMeta::new(accounts.b_token_vault.key(), false),
+                AccountMeta::new(accounts.a_token_vault.key(), false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new(accounts.a_vault_lp_mint.key(), false),
+                AccountMeta::new(accounts.protocol_token_fee.key(), false),
+                AccountMeta::new_readonly(accounts.token_program.key(), false),
+            ],
+            data,
+        }
+    }
+}
+
+impl<'a, 'info> DexAdapter<'info> for ConstantProductPool<'a, 'info> {
+    fn reserves(&self, _current_time: i64) -> Result<(u64, u64)> {
+        self.load_fees()?;
+        let donut_amount = layout::read_token_amount(&self.accounts.a_token_vault)?;
+        let sol_amount = layout::read_token_amount(&self.accounts.b_token_vault)?;
+        Ok((donut_amount, sol_amount))
+    }
+
+    fn quote_donut_out_at(&self, amount_in: u64, donut_reserve: u64, sol_reserve: u64) -> Result<u64> {
+        let fees = self.load_fees()?;
+        let quote = fees
+            .quote(sol_reserve, donut_reserve, amount_in)
+            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
+        Ok(quote.amount_out)
+    }
+
+    fn swap(
+        &self,
+        authority: &AccountInfo<'info>,
+        source: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        self.load_fees()?;
+        let accounts = self.accounts;
+        let instruction = self.swap_instruction(authority.key, source.key, destination.key, amount_in, minimum_amount_out);
+        invoke_swap(
+            &instruction,
+            &[
+                accounts.pool.clone(),
+                accounts.a_vault.clone(),
+                authority.clone(),
+                source.clone(),
+                accounts.b_token_vault.clone(),
+                accounts.a_token_vault.clone(),
+                destination.clone(),
+                accounts.a_vault_lp_mint.clone(),
+                accounts.protocol_token_fee.clone(),
+                accounts.token_program.clone(),
+            ],
+            signer_seeds,
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+
+use crate::config::ProtocolConfig;
+use crate::oracle::{OracleConfig, OracleSource, StalePricePolicy};
+use crate::{ErrorCode, ProgramState, MAX_ADMIN_PRICE_DURATION};
+
+// Delay between proposing a governance change and being able to apply it (48 hours in seconds)
+pub const GOVERNANCE_DELAY: i64 = 48 * 3600;
+
+// Config change queued by the owner, applied only after the governance delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum ConfigChange {
+    // Replace the whole protocol config and the treasury
+    Protocol { config: Box<ProtocolConfig>, multisig_treasury: Pubkey },
+    // Replace the price sources (first is primary, rest are fallbacks)
+    Oracles { sources: Vec<OracleSource> },
+    // Choose what happens when the primary oracle is stale
+    StalePricePolicy { policy: StalePricePolicy },
+    // Fallback SOL price (8 decimals) used by the AdminPrice policy until it expires
+    AdminSolPrice { price: u64, expires_at: i64 },
+    // Move lamports held under the Reserve sink policy to the multisig treasury
+    ReserveWithdrawal { amount: u64 },
+}
+
+impl ConfigChange {
+    // Tag plus the largest variant
+    pub const SIZE: usize = 1 + ProtocolConfig::SIZE + 32;
+
+    // Check the change before it is queued for `eta`
+    pub fn validate(&self, eta: i64) -> Result<()> {
+        match self {
+            ConfigChange::Protocol { config, .. } => config.validate(),
+            ConfigChange::Oracles { sources } => OracleConfig::validate_sources(sources),
+            ConfigChange::StalePricePolicy { .. } => Ok(()),
+            ConfigChange::AdminSolPrice { price, expires_at } => validate_admin_price(*price, *expires_at, eta),
+            ConfigChange::ReserveWithdrawal { amount } => {
+                if *amount == 0 {
+                    msg!("Reserve withdrawal amount must be positive");
+                    return Err(error!(ErrorCode::InvalidReserveWithdrawal));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Write the change into the live accounts
+    pub fn apply(
+        &self,
+        state: &mut ProgramState,
+        protocol_config: &mut ProtocolConfig,
+        oracle_config: &mut OracleConfig,
+        now: i64,
+    ) -> Result<()> {
+        match self {
+            ConfigChange::Protocol { config, multisig_treasury } => {
+                *protocol_config = config.as_ref().clone();
+                state.multisig_treasury = *multisig_treasury;
+            }
+            ConfigChange::Oracles { sources } => {
+                oracle_config.sources = sources.clone();
+            }
+            ConfigChange::StalePricePolicy { policy } => {
+                state.stale_price_policy = *policy;
+            }
+            ConfigChange::AdminSolPrice { price, expires_at } => {
+                // The price must still be live when the timelock lets it through
+                validate_admin_price(*price, *expires_at, now)?;
+                state.admin_sol_price = *price;
+                state.admin_price_expires_at = *expires_at;
+            }
+            // The lamports are moved by `execute_config_change`, which holds the reserve account
+            ConfigChange::ReserveWithdrawal { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+// An admin price must be positive and expire within MAX_ADMIN_PRICE_DURATION of `from`
+pub fn validate_admin_price(price: u64, expires_at: i64, from: i64) -> Result<()> {
+    if price == 0 || expires_at <= from || expires_at - from > MAX_ADMIN_PRICE_DURATION {
+        msg!("Invalid admin price: price={}, expires_at={}, from={}", price, expires_at, from);
+        return Err(error!(ErrorCode::InvalidAdminPrice));
+    }
+    Ok(())
+}
+
+// Governance change waiting for its timelock, stored in the `pending_config_change` PDA
+#[account]
+#[derive(Debug)]
+pub struct PendingConfigChange {
+    pub change: ConfigChange,
+    pub eta: i64,                   // Earliest time the change can be executed
+}
+
+impl PendingConfigChange {
+    pub const SIZE: usize = ConfigChange::SIZE + 8;
+}
+
+// Earliest time a change proposed at `now` can take effect
+pub fn timelock_eta(now: i64) -> i64 {
+    now.saturating_add(GOVERNANCE_DELAY)
+}
+
+// Fail until the timelock of a proposed change has elapsed
+pub fn check_timelock(eta: i64, now: i64) -> Result<()> {
+    if now < eta {
+        msg!("Timelock not elapsed: now={}, eta={}", now, eta);
+        return Err(error!(ErrorCode::TimelockNotElapsed));
+    }
+    Ok(())
+}
@@ -0,0 +1,385 @@
+// Typed, borrowing views over Meteora, SPL token and SPL token-swap account data.
+// Each view checks the account size and discriminator once, then reads fields
+// straight from the account bytes; `read_*` helpers also check the owner.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_spl::token::spl_token;
+
+use crate::quote::{CurveType, PoolFees, TokenSwapFees};
+use crate::ErrorCode;
+
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+// Meteora dynamic AMM `Pool` layout (offsets include the discriminator)
+pub const POOL_LP_MINT_OFFSET: usize = 8;
+pub const POOL_TOKEN_A_MINT_OFFSET: usize = 8 + 32;
+pub const POOL_TOKEN_B_MINT_OFFSET: usize = 8 + 64;
+pub const POOL_A_VAULT_OFFSET: usize = 8 + 96;
+pub const POOL_B_VAULT_OFFSET: usize = 8 + 128;
+pub const POOL_A_VAULT_LP_OFFSET: usize = 8 + 160;
+pub const POOL_B_VAULT_LP_OFFSET: usize = 8 + 192;
+pub const POOL_ENABLED_OFFSET: usize = 8 + 225;
+pub const POOL_FEES_OFFSET: usize = 8 + 322;
+pub const POOL_CURVE_TYPE_OFFSET: usize = 8 + 866;
+
+// Meteora vault `Vault` layout (offsets include the discriminator)
+pub const VAULT_ENABLED_OFFSET: usize = 8;
+pub const VAULT_TOTAL_AMOUNT_OFFSET: usize = 8 + 3;
+pub const VAULT_TOKEN_VAULT_OFFSET: usize = 8 + 11;
+pub const VAULT_TOKEN_MINT_OFFSET: usize = 8 + 75;
+pub const VAULT_LP_MINT_OFFSET: usize = 8 + 107;
+pub const VAULT_LOCKED_PROFIT_TRACKER_OFFSET: usize = 8 + 1195;
+pub const VAULT_LEN: usize = 8 + 1219;
+
+// SPL token-swap `SwapV1` layout, behind a one byte version (no discriminator)
+pub const TOKEN_SWAP_LEN: usize = 1 + 323;
+pub const TOKEN_SWAP_VERSION: u8 = 1;
+pub const TOKEN_SWAP_CONSTANT_PRODUCT_CURVE: u8 = 0;
+const TOKEN_SWAP_IS_INITIALIZED_OFFSET: usize = 1;
+const TOKEN_SWAP_BUMP_SEED_OFFSET: usize = 2;
+const TOKEN_SWAP_TOKEN_A_OFFSET: usize = 35;
+const TOKEN_SWAP_TOKEN_B_OFFSET: usize = 67;
+const TOKEN_SWAP_POOL_MINT_OFFSET: usize = 99;
+const TOKEN_SWAP_TOKEN_A_MINT_OFFSET: usize = 131;
+const TOKEN_SWAP_TOKEN_B_MINT_OFFSET: usize = 163;
+const TOKEN_SWAP_POOL_FEE_ACCOUNT_OFFSET: usize = 195;
+const TOKEN_SWAP_FEES_OFFSET: usize = 227;
+const TOKEN_SWAP_CURVE_TYPE_OFFSET: usize = 291;
+
+// SPL token layouts (no discriminator)
+pub const MINT_LEN: usize = 82;
+const MINT_SUPPLY_OFFSET: usize = 36;
+const MINT_DECIMALS_OFFSET: usize = 44;
+const MINT_IS_INITIALIZED_OFFSET: usize = 45;
+
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+const TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+// Anchor discriminator of an account type named `name`
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("account:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+pub fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(bytes)
+}
+
+pub fn check_owner(account: &AccountInfo, expected_owner: &Pubkey) -> Result<()> {
+    if account.owner != expected_owner {
+        msg!("Account {} owner mismatch: owner={}, expected={}", account.key(), account.owner, expected_owner);
+        return Err(error!(ErrorCode::InvalidAccountOwner));
+    }
+    Ok(())
+}
+
+fn check_anchor_account(data: &[u8], name: &str, min_len: usize) -> Result<()> {
+    if data.len() < min_len {
+        msg!("{} account too small: {}", name, data.len());
+        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
+    }
+    if data[..DISCRIMINATOR_LEN] != account_discriminator(name) {
+        msg!("{} account has an invalid discriminator", name);
+        return Err(error!(ErrorCode::InvalidAccountDiscriminator));
+    }
+    Ok(())
+}
+
+// ===== METEORA POOL =====
+
+pub struct PoolView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PoolView<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        check_anchor_account(data, "Pool", POOL_CURVE_TYPE_OFFSET + 1)?;
+        Ok(PoolView { data })
+    }
+
+    pub fn lp_mint(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_LP_MINT_OFFSET)
+    }
+
+    pub fn token_a_mint(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_TOKEN_A_MINT_OFFSET)
+    }
+
+    pub fn token_b_mint(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_TOKEN_B_MINT_OFFSET)
+    }
+
+    pub fn a_vault(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_A_VAULT_OFFSET)
+    }
+
+    pub fn b_vault(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_B_VAULT_OFFSET)
+    }
+
+    pub fn a_vault_lp(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_A_VAULT_LP_OFFSET)
+    }
+
+    pub fn b_vault_lp(&self) -> Pubkey {
+        read_pubkey(self.data, POOL_B_VAULT_LP_OFFSET)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.data[POOL_ENABLED_OFFSET] != 0
+    }
+
+    pub fn fees(&self) -> PoolFees {
+        PoolFees {
+            trade_fee_numerator: read_u64(self.data, POOL_FEES_OFFSET),
+            trade_fee_denominator: read_u64(self.data, POOL_FEES_OFFSET + 8),
+            protocol_trade_fee_numerator: read_u64(self.data, POOL_FEES_OFFSET + 16),
+            protocol_trade_fee_denominator: read_u64(self.data, POOL_FEES_OFFSET + 24),
+        }
+    }
+
+    // The curve is the last, variable sized field
+    pub fn curve_type(&self) -> Result<CurveType> {
+        let mut curve_data = &self.data[POOL_CURVE_TYPE_OFFSET..];
+        CurveType::deserialize(&mut curve_data).map_err(|_| error!(ErrorCode::PriceMeteoraReadFailed))
+    }
+}
+
+// ===== METEORA VAULT =====
+
+// Denominator of `LockedProfitTracker::locked_profit_degradation`
+pub const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+// Profit from the last vault harvest, released linearly over time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockedProfitTracker {
+    pub last_updated_locked_profit: u64,
+    pub last_report: u64,
+    pub locked_profit_degradation: u64, // Fraction of the profit unlocked per second
+}
+
+impl LockedProfitTracker {
+    // Part of the last harvest still locked at `current_time`
+    pub fn calculate_locked_profit(&self, current_time: u64) -> Option<u64> {
+        let duration = u128::from(current_time.checked_sub(self.last_report)?);
+        let locked_fund_ratio = duration.checked_mul(u128::from(self.locked_profit_degradation))?;
+
+        if locked_fund_ratio > LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+            return Some(0);
+        }
+
+        let locked_profit = u128::from(self.last_updated_locked_profit)
+            .checked_mul(LOCKED_PROFIT_DEGRADATION_DENOMINATOR - locked_fund_ratio)?
+            .checked_div(LOCKED_PROFIT_DEGRADATION_DENOMINATOR)?;
+
+        u64::try_from(locked_profit).ok()
+    }
+}
+
+pub struct VaultView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> VaultView<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        check_anchor_account(data, "Vault", VAULT_LEN)?;
+        Ok(VaultView { data })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.data[VAULT_ENABLED_OFFSET] != 0
+    }
+
+    pub fn total_amount(&self) -> u64 {
+        read_u64(self.data, VAULT_TOTAL_AMOUNT_OFFSET)
+    }
+
+    pub fn token_vault(&self) -> Pubkey {
+        read_pubkey(self.data, VAULT_TOKEN_VAULT_OFFSET)
+    }
+
+    pub fn token_mint(&self) -> Pubkey {
+        read_pubkey(self.data, VAULT_TOKEN_MINT_OFFSET)
+    }
+
+    pub fn lp_mint(&self) -> Pubkey {
+        read_pubkey(self.data, VAULT_LP_MINT_OFFSET)
+    }
+
+    pub fn locked_profit_tracker(&self) -> LockedProfitTracker {
+        LockedProfitTracker {
+            last_updated_locked_profit: read_u64(self.data, VAULT_LOCKED_PROFIT_TRACKER_OFFSET),
+            last_report: read_u64(self.data, VAULT_LOCKED_PROFIT_TRACKER_OFFSET + 8),
+            locked_profit_degradation: read_u64(self.data, VAULT_LOCKED_PROFIT_TRACKER_OFFSET + 16),
+        }
+    }
+
+    // Vault amount without the profit still locked at `current_time`
+    pub fn get_unlocked_amount(&self, current_time: u64) -> Option<u64> {
+        self.total_amount()
+            .checked_sub(self.locked_profit_tracker().calculate_locked_profit(current_time)?)
+    }
+
+    // Value of `share` LP tokens out of `total_supply` at `current_time`
+    pub fn get_amount_by_share(&self, current_time: u64, share: u64, total_supply: u64) -> Option<u64> {
+        if total_supply == 0 {
+            return Some(0);
+        }
+
+        let total_amount = self.get_unlocked_amount(current_time)?;
+        u64::try_from(
+            u128::from(share)
+                .checked_mul(u128::from(total_amount))?
+                .checked_div(u128::from(total_supply))?,
+        )
+        .ok()
+    }
+}
+
+// ===== SPL TOKEN =====
+
+pub struct MintView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> MintView<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        if data.len() != MINT_LEN || data[MINT_IS_INITIALIZED_OFFSET] != 1 {
+            msg!("Not an initialized mint account");
+            return Err(error!(ErrorCode::InvalidTokenAccount));
+        }
+        Ok(MintView { data })
+    }
+
+    pub fn supply(&self) -> u64 {
+        read_u64(self.data, MINT_SUPPLY_OFFSET)
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.data[MINT_DECIMALS_OFFSET]
+    }
+}
+
+pub struct TokenAccountView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TokenAccountView<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        // State 0 is uninitialized, 1 initialized and 2 frozen
+        if data.len() != TOKEN_ACCOUNT_LEN || data[TOKEN_ACCOUNT_STATE_OFFSET] == 0 {
+            msg!("Not an initialized token account");
+            return Err(error!(ErrorCode::InvalidTokenAccount));
+        }
+        Ok(TokenAccountView { data })
+    }
+
+    pub fn mint(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_ACCOUNT_MINT_OFFSET)
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_ACCOUNT_OWNER_OFFSET)
+    }
+
+    pub fn amount(&self) -> u64 {
+        read_u64(self.data, TOKEN_ACCOUNT_AMOUNT_OFFSET)
+    }
+}
+
+// ===== SPL TOKEN SWAP =====
+
+pub struct TokenSwapView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TokenSwapView<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        if data.len() != TOKEN_SWAP_LEN || data[0] != TOKEN_SWAP_VERSION {
+            msg!("Not a token-swap account: len {}", data.len());
+            return Err(error!(ErrorCode::InvalidAccountDiscriminator));
+        }
+        if data[TOKEN_SWAP_IS_INITIALIZED_OFFSET] != 1 {
+            msg!("Token-swap account is not initialized");
+            return Err(error!(ErrorCode::InvalidAccountDiscriminator));
+        }
+        Ok(TokenSwapView { data })
+    }
+
+    pub fn bump_seed(&self) -> u8 {
+        self.data[TOKEN_SWAP_BUMP_SEED_OFFSET]
+    }
+
+    // Reserve token accounts
+    pub fn token_a(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_SWAP_TOKEN_A_OFFSET)
+    }
+
+    pub fn token_b(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_SWAP_TOKEN_B_OFFSET)
+    }
+
+    pub fn pool_mint(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_SWAP_POOL_MINT_OFFSET)
+    }
+
+    // Mints of the reserves
+    pub fn token_a_mint(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_SWAP_TOKEN_A_MINT_OFFSET)
+    }
+
+    pub fn token_b_mint(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_SWAP_TOKEN_B_MINT_OFFSET)
+    }
+
+    pub fn pool_fee_account(&self) -> Pubkey {
+        read_pubkey(self.data, TOKEN_SWAP_POOL_FEE_ACCOUNT_OFFSET)
+    }
+
+    pub fn fees(&self) -> TokenSwapFees {
+        TokenSwapFees {
+            trade_fee_numerator: read_u64(self.data, TOKEN_SWAP_FEES_OFFSET),
+            trade_fee_denominator: read_u64(self.data, TOKEN_SWAP_FEES_OFFSET + 8),
+            owner_trade_fee_numerator: read_u64(self.data, TOKEN_SWAP_FEES_OFFSET + 16),
+            owner_trade_fee_denominator: read_u64(self.data, TOKEN_SWAP_FEES_OFFSET + 24),
+        }
+    }
+
+    pub fn curve_type(&self) -> u8 {
+        self.data[TOKEN_SWAP_CURVE_TYPE_OFFSET]
+    }
+}
+
+// Supply of a mint owned by the token program
+pub fn read_mint_supply(mint: &AccountInfo) -> Result<u64> {
+    check_owner(mint, &spl_token::ID)?;
+    let data = mint.try_borrow_data()?;
+    Ok(MintView::decode(&data)?.supply())
+}
+
+// Mint of a token account owned by the token program
+pub fn read_token_mint(token_account: &AccountInfo) -> Result<Pubkey> {
+    check_owner(token_account, &spl_token::ID)?;
+    let data = token_account.try_borrow_data()?;
+    Ok(TokenAccountView::decode(&data)?.mint())
+}
+
+// Balance of a token account owned by the token program
+pub fn read_token_amount(token_account: &AccountInfo) -> Result<u64> {
+    check_owner(token_account, &spl_token::ID)?;
+    let data = token_account.try_borrow_data()?;
+    Ok(TokenAccountView::decode(&data)?.amount())
+}
@@ -3,11 +3,48 @@ use anchor_lang::solana_program::{self, clock::Clock};
 use anchor_lang::AnchorDeserialize;
 use anchor_lang::AnchorSerialize;
 use anchor_spl::token::{Token, TokenAccount};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::associated_token::{self, AssociatedToken};
 use anchor_spl::token::spl_token;
 #[cfg(not(feature = "no-entrypoint"))]
 use {solana_security_txt::security_txt};
 
+pub mod airdrop;
+pub mod buyback;
+pub mod cascade;
+pub mod chainlink;
+pub mod config;
+pub mod decimal;
+pub mod dex;
+pub mod governance;
+pub mod layout;
+pub mod matrix;
+pub mod oracle;
+pub mod pause;
+pub mod pending_burn;
+pub mod price_guard;
+pub mod price_tracker;
+pub mod quote;
+pub mod reentrancy;
+pub mod sink;
+pub mod slippage;
+pub mod wsol;
+
+use airdrop::{AirdropState, UserWeekData, AIRDROP_WEEKS};
+use buyback::{BurnMode, BuybackState};
+use cascade::{CascadeProgress, PendingCascade};
+use config::ProtocolConfig;
+use dex::{DexAdapter, SwapVenue, VenueAccounts};
+pub use decimal::Decimal;
+use governance::{ConfigChange, PendingConfigChange};
+use matrix::{SlotAction, MAX_MATRIX_SLOTS};
+use pause::{PauseFlag, PauseFlags};
+use pending_burn::SwapFailurePolicy;
+use price_tracker::{PriceSnapshot, PriceTracker};
+use reentrancy::ReentrancyGuard;
+use sink::{DepositLedger, TerminalSinkPolicy};
+use wsol::{SplWsolAccount, WsolSession};
+use oracle::{AdminPrice, OracleConfig, OraclePrice, PriceResolution, StalePricePolicy};
+
 declare_id!("7Ck62qPCsLc6cXykGdYtNirHHH2FJNNi6jDrT7NNndSg");
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -25,57 +62,17 @@ security_txt! {
     acknowledgements: "We thank all security researchers who contributed to the security of our protocol."
 }
 
-// Minimum deposit amount in USD (10 dollars in base units - 8 decimals)
-const MINIMUM_USD_DEPOSIT: u64 = 10_00000000; // 10 USD with 8 decimals (Chainlink format)
-
-// Maximum price feed staleness (24 hours in seconds)
-const MAX_PRICE_FEED_AGE: i64 = 86400;
-
-// Default SOL price in case of stale feed ($100 USD per SOL)
-const DEFAULT_SOL_PRICE: i128 = 100_00000000; // $100 with 8 decimals
+// Maximum lifetime of an admin-set fallback SOL price (7 days in seconds)
+const MAX_ADMIN_PRICE_DURATION: i64 = 7 * 86400;
 
-// Maximum number of upline accounts that can be processed in a single transaction
-const MAX_UPLINE_DEPTH: usize = 6;
+// Maximum number of upline entries a user account has room for
+pub const MAX_UPLINE_DEPTH: usize = 6;
 
 // Number of Vault A accounts in the remaining_accounts
 const VAULT_A_ACCOUNTS_COUNT: usize = 4; // a_vault + a_vault_lp + a_vault_lp_mint + a_token_vault
 
-// Constants for strict address verification
-pub mod verified_addresses {
-    use anchor_lang::prelude::Pubkey;
-    use anchor_lang::solana_program;
- 
-    // Pool address
-    pub static POOL_ADDRESS: Pubkey = solana_program::pubkey!("FrQ5KsAgjCe3FFg6ZENri8feDft54tgnATxyffcasuxU");
-    
-    // Vault A addresses (DONUT token vault)
-    pub static A_VAULT: Pubkey = solana_program::pubkey!("4ndfcH16GKY76bzDkKfyVwHMoF8oY75KES2VaAhUYksN");
-    pub static A_VAULT_LP: Pubkey = solana_program::pubkey!("CocstBGbeDVyTJWxbWs4docwWapVADAo1xXQSh9RfPMz");
-    pub static A_VAULT_LP_MINT: Pubkey = solana_program::pubkey!("6f2FVX5UT5uBtgknc8fDj119Z7DQoLJeKRmBq7j1zsVi");
-    pub static A_TOKEN_VAULT: Pubkey = solana_program::pubkey!("6m1wvYoPrwjAnbuGMqpMoodQaq4VnZXRjrzufXnPSjmj");
-    
-    // Meteora pool addresses
-    pub static B_VAULT_LP: Pubkey = solana_program::pubkey!("HJNs8hPTzs9i6AVFkRDDMFVEkrrUoV7H7LDZHdCWvxn7");
-    pub static B_VAULT: Pubkey = solana_program::pubkey!("FERjPVNEa7Udq8CEv68h6tPL46Tq7ieE49HrE2wea3XT");
-    pub static B_TOKEN_VAULT: Pubkey = solana_program::pubkey!("HZeLxbZ9uHtSpwZC3LBr4Nubd14iHwz7bRSghRZf5VCG");
-    pub static B_VAULT_LP_MINT: Pubkey = solana_program::pubkey!("BvoAjwEDhpLzs3jtu4H72j96ShKT5rvZE9RP1vgpfSM");
-    
-    // Token addresses
-    pub static TOKEN_MINT: Pubkey = solana_program::pubkey!("CCTG4ZmGa9Nk9NVxbd1FXBNyKjyHSapuF9aU6zgcA3xz");
-    pub static WSOL_MINT: Pubkey = solana_program::pubkey!("So11111111111111111111111111111111111111112");
-    
-    // Chainlink addresses (Devnet)
-    pub static CHAINLINK_PROGRAM: Pubkey = solana_program::pubkey!("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny");
-    pub static SOL_USD_FEED: Pubkey = solana_program::pubkey!("99B2bTijsU6f1GCT73HmdR7HCFFjGMBcPZY6jZ96ynrR");
-    
-    // CRITICAL SECURITY ADDRESSES 
-    pub static METEORA_VAULT_PROGRAM: Pubkey = solana_program::pubkey!("24Uqj9JCLxUeoC3hGfh5W3s9FM9uCHDS2SG3LYwBpyTi");
-    pub static METEORA_AMM_PROGRAM: Pubkey = solana_program::pubkey!("Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB");
-    
-    // Protocol fee accounts
-    pub static PROTOCOL_TOKEN_A_FEE: Pubkey = solana_program::pubkey!("2B6tLDfiQAMSPAKuHqRMvhuQ5dRKDWkYF6m7ggtzmCY5");
-    pub static PROTOCOL_TOKEN_B_FEE: Pubkey = solana_program::pubkey!("88fLv3iEY7ubFCjwCzfzA7FsPG8xSBFicSPS8T8fX4Kq");
-}
+// Both registrations start their remaining_accounts with the pool followed by the vault A accounts
+const POOL_AND_VAULT_A_ACCOUNTS_COUNT: usize = 1 + VAULT_A_ACCOUNTS_COUNT;
 
 // Admin account addresses
 pub mod admin_addresses {
@@ -88,18 +85,28 @@ pub mod admin_addresses {
 
 // ===== PROGRAM STRUCTURES =====
 
-// Program state structure
+// Program state structure.
+// ProgramState and UserAccount grew new fields and are not migrated in place:
+// the program must be deployed fresh (new program id and `initialize`). Accounts
+// left over from the old layout are rejected instead of being misread.
 #[account]
 pub struct ProgramState {
     pub owner: Pubkey,
     pub multisig_treasury: Pubkey,
     pub next_upline_id: u32,
     pub next_chain_id: u32,
-    pub is_locked: bool,
+    pub is_locked: bool,                // Reentrancy lock, only set while an instruction runs (see reentrancy.rs)
+    pub stale_price_policy: StalePricePolicy,
+    pub admin_sol_price: u64,           // USD per SOL with 8 decimals
+    pub admin_price_expires_at: i64,
+    pub pending_owner: Option<Pubkey>,
+    pub ownership_transfer_eta: i64,
+    pub pause_flags: PauseFlags,
+    pub last_mint_amount: u64,          // Last DONUT amount minted by a Mint slot, bounds the next one
 }
 
 impl ProgramState {
-    pub const SIZE: usize = 32 + 32 + 4 + 4 + 1;
+    pub const SIZE: usize = 32 + 32 + 4 + 4 + 1 + 1 + 8 + 8 + (1 + 32) + 8 + PauseFlags::SIZE + 8;
 }
 
 // Structure to store complete information for each upline
@@ -121,10 +128,41 @@ pub struct ReferralUpline {
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct ReferralChain {
     pub id: u32,
-    pub slots: [Option<Pubkey>; 3],
+    pub slots: [Option<Pubkey>; MAX_MATRIX_SLOTS],
     pub filled_slots: u8,
 }
 
+impl ReferralChain {
+    pub fn new(id: u32) -> Self {
+        ReferralChain {
+            id,
+            slots: [None; MAX_MATRIX_SLOTS],
+            filled_slots: 0,
+        }
+    }
+
+    // Put `user` in the next free slot of a matrix `width` slots wide.
+    // Returns the filled slot index and whether it completed the matrix, in which case
+    // the matrix restarts empty under `next_chain_id`.
+    pub fn fill_next_slot(&mut self, user: Pubkey, width: usize, next_chain_id: u32) -> Result<(usize, bool)> {
+        let slot_idx = self.filled_slots as usize;
+        if slot_idx >= MAX_MATRIX_SLOTS {
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+
+        self.slots[slot_idx] = Some(user);
+        self.filled_slots += 1;
+
+        // A matrix left wider than the table by a config change completes on its next fill
+        if self.filled_slots as usize >= width {
+            *self = ReferralChain::new(next_chain_id);
+            return Ok((slot_idx, true));
+        }
+
+        Ok((slot_idx, false))
+    }
+}
+
 // User account structure
 #[account]
 #[derive(Default)]
@@ -135,6 +173,14 @@ pub struct UserAccount {
     pub upline: ReferralUpline,
     pub chain: ReferralChain,
     pub reserved_sol: u64,
+    pub reserved_tokens: u64,           // DONUT minted by a Mint slot, released by PayReserved
+    pub completed_matrices_total: u64,
+    pub weekly_matrices: Vec<UserWeekData>,
+    pub total_donut_earned: u64,        // Airdrop shares and released reserved tokens
+    pub total_donut_claimed: u64,
+    pub last_processed_week: u8,
+    pub claimable_sol: u64,             // Reserved SOL released by PayReserved, paid out by `claim_rewards`
+    pub cascade_lock: Option<Pubkey>,   // Registrant whose saved cascade still has to reach this matrix
 }
 
 impl UserAccount {
@@ -142,8 +188,83 @@ impl UserAccount {
                            1 + 32 + // Option<Pubkey> (1 for is_some + 32 for Pubkey)
                            32 + // owner_wallet
                            4 + 1 + 4 + (MAX_UPLINE_DEPTH * (32 + 32)) + // ReferralUpline
-                           4 + (3 * (1 + 32)) + 1 + // ReferralChain
-                           8; // reserved_sol
+                           4 + (MAX_MATRIX_SLOTS * (1 + 32)) + 1 + // ReferralChain
+                           8 + // reserved_sol
+                           8 + // reserved_tokens
+                           8 + // completed_matrices_total
+                           4 + (AIRDROP_WEEKS * UserWeekData::SIZE) + // weekly_matrices
+                           8 + 8 + 1 + // total_donut_earned, total_donut_claimed, last_processed_week
+                           8 + // claimable_sol
+                           1 + 32; // cascade_lock
+
+    // Reject user accounts written with a previous layout
+    pub fn check_layout(info: &AccountInfo) -> Result<()> {
+        if info.data_len() != 8 + Self::SIZE {
+            msg!("User account {} has an outdated layout: {} bytes", info.key, info.data_len());
+            return Err(error!(ErrorCode::OutdatedAccountLayout));
+        }
+        Ok(())
+    }
+
+    // Fail while the saved cascade of a registrant other than `registrant` still has to fill
+    // a slot here, so slots fill in the order one transaction per registration would give
+    pub fn check_cascade_lock(&self, registrant: &Pubkey) -> Result<()> {
+        match self.cascade_lock {
+            Some(holder) if holder != *registrant => {
+                msg!("Matrix is held for the saved cascade of {}", holder);
+                Err(error!(ErrorCode::CascadePending))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Hold the matrix for the saved cascade of `registrant`
+    pub fn lock_for_cascade(&mut self, registrant: Pubkey) -> Result<()> {
+        self.check_cascade_lock(&registrant)?;
+        self.cascade_lock = Some(registrant);
+        Ok(())
+    }
+
+    // Drop the hold of `registrant`'s cascade once it reached this matrix or ended below it
+    pub fn release_cascade_lock(&mut self, registrant: &Pubkey) {
+        if self.cascade_lock == Some(*registrant) {
+            self.cascade_lock = None;
+        }
+    }
+
+    pub fn claimable_donut(&self) -> u64 {
+        self.total_donut_earned.saturating_sub(self.total_donut_claimed)
+    }
+
+    // Make the SOL reserved by earlier slots claimable by the owner
+    pub fn release_reserved_sol(&mut self) -> Result<u64> {
+        let released = self.reserved_sol;
+        self.claimable_sol = self.claimable_sol
+            .checked_add(released)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.reserved_sol = 0;
+        Ok(released)
+    }
+
+    // Take the whole claimable SOL balance for a payout
+    pub fn take_claimable_sol(&mut self) -> Result<u64> {
+        let amount = self.claimable_sol;
+        if amount == 0 {
+            return Err(error!(ErrorCode::NothingToClaim));
+        }
+        self.claimable_sol = 0;
+        Ok(amount)
+    }
+
+    // Make the DONUT reserved by a Mint slot claimable by the owner
+    pub fn release_reserved_tokens(&mut self) -> Result<u64> {
+        let released = self.reserved_tokens;
+        self.total_donut_earned = self.total_donut_earned
+            .checked_add(released)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.reserved_tokens = 0;
+        Ok(released)
+    }
 }
 
 // Error codes
@@ -254,7 +375,7 @@ pub enum ErrorCode {
     #[msg("Invalid AMM program")]
     InvalidAmmProgram,
     
-    #[msg("Slot 3 registration requires upline accounts for recursion")]
+    #[msg("Matrix completing registration requires upline accounts for recursion")]
     Slot3RequiresUplineAccounts,
     
     #[msg("Deposit was not fully processed - registration aborted")]
@@ -271,6 +392,114 @@ pub enum ErrorCode {
     
     #[msg("Invalid protocol fee account")]
     InvalidProtocolFeeAccount,
+
+    #[msg("Invalid oracle configuration")]
+    InvalidOracleConfig,
+
+    #[msg("Missing price oracle accounts")]
+    MissingOracleAccounts,
+
+    #[msg("Invalid admin price or expiry")]
+    InvalidAdminPrice,
+
+    #[msg("Deposit amount calculation overflow")]
+    DepositCalculationOverflow,
+
+    #[msg("Invalid protocol configuration")]
+    InvalidProtocolConfig,
+
+    #[msg("Governance timelock has not elapsed yet")]
+    TimelockNotElapsed,
+
+    #[msg("No ownership transfer is pending")]
+    NoPendingOwnershipTransfer,
+
+    #[msg("Registrations are paused")]
+    RegistrationPaused,
+
+    #[msg("Swap and burn is paused")]
+    SwapAndBurnPaused,
+
+    #[msg("Reserve payouts are paused")]
+    ReservePayoutsPaused,
+
+    #[msg("Airdrop claims are paused")]
+    AirdropClaimsPaused,
+
+    #[msg("Invalid matrix slot action table")]
+    InvalidSlotActions,
+
+    #[msg("Invalid multisig treasury account")]
+    InvalidTreasuryAccount,
+
+    #[msg("Failed to deposit to pool")]
+    DepositToPoolFailed,
+
+    #[msg("Failed to pay treasury")]
+    TreasuryPaymentFailed,
+
+    #[msg("Arithmetic overflow detected")]
+    ArithmeticOverflow,
+
+    #[msg("Failed to mint tokens")]
+    TokenMintFailed,
+
+    #[msg("Invalid program token vault address")]
+    InvalidProgramTokenVault,
+
+    #[msg("Nothing to claim - no DONUT available")]
+    NothingToClaim,
+
+    #[msg("Airdrop has already been started")]
+    AirdropAlreadyStarted,
+
+    #[msg("Requested slippage exceeds the protocol maximum")]
+    SlippageAboveMaximum,
+
+    #[msg("Swap output below the slippage bound")]
+    SlippageExceeded,
+
+    #[msg("Account is not owned by the expected program")]
+    InvalidAccountOwner,
+
+    #[msg("Account discriminator does not match the expected account type")]
+    InvalidAccountDiscriminator,
+
+    #[msg("Price tracker has no observations yet")]
+    PriceTrackerNotInitialized,
+
+    #[msg("Account does not match the configured swap venue")]
+    InvalidSwapVenueAccount,
+
+    #[msg("Burn vault holds less than the minimum buyback amount")]
+    NothingToBuyBack,
+
+    #[msg("Pending burn vault holds nothing to burn")]
+    NoPendingBurn,
+
+    #[msg("Holding vault holds no deposits to release")]
+    NoHeldDeposits,
+
+    #[msg("Registration changed the WSOL the user held before")]
+    UserWsolBalanceChanged,
+
+    #[msg("Pool price deviates too far from its EMA")]
+    PriceDeviationTooHigh,
+
+    #[msg("A saved cascade must be continued before this matrix takes new slots")]
+    CascadePending,
+
+    #[msg("No cascade is waiting to be continued")]
+    NoPendingCascade,
+
+    #[msg("Deposit lamports were left unaccounted for")]
+    DepositUnaccounted,
+
+    #[msg("Account was created with an older layout; the program must be freshly deployed")]
+    OutdatedAccountLayout,
+
+    #[msg("Reserve withdrawal must be positive and leave the reserve rent exempt")]
+    InvalidReserveWithdrawal,
 }
 
 // Event structure for slot filling
@@ -282,33 +511,214 @@ pub struct SlotFilled {
     pub owner: Pubkey,
 }
 
-// Decimal handling for price display
-#[derive(Default)]
-pub struct Decimal {
-    pub value: i128,
-    pub decimals: u32,
+// Event emitted when the airdrop weeks start
+#[event]
+pub struct AirdropStarted {
+    pub start_timestamp: i64,
 }
 
-impl Decimal {
-    pub fn new(value: i128, decimals: u32) -> Self {
-        Decimal { value, decimals }
-    }
+// Event emitted when an airdrop week closes with completed matrices
+#[event]
+pub struct WeekClosed {
+    pub week_number: u8,
+    pub total_matrices: u64,
+    pub donut_distributed: u64,
+    pub donut_per_matrix: u64,
 }
 
-impl std::fmt::Display for Decimal {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut scaled_val = self.value.to_string();
-        if scaled_val.len() <= self.decimals as usize {
-            scaled_val.insert_str(
-                0,
-                &vec!["0"; self.decimals as usize - scaled_val.len()].join(""),
-            );
-            scaled_val.insert_str(0, "0.");
-        } else {
-            scaled_val.insert(scaled_val.len() - self.decimals as usize, '.');
-        }
-        f.write_str(&scaled_val)
-    }
+// Event emitted when a completed matrix is counted for the airdrop
+#[event]
+pub struct MatrixCompleted {
+    pub user: Pubkey,
+    pub week_number: u8,
+    pub total_matrices: u64,
+}
+
+// Event emitted when a user claims their earned DONUT
+#[event]
+pub struct DonutClaimed {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_earned: u64,
+    pub total_claimed: u64,
+}
+
+// Event emitted when a user claims the SOL released to them by PayReserved slots
+#[event]
+pub struct SolClaimed {
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+// Event emitted when the buyback crank swaps the burn vault and burns the DONUT
+#[event]
+pub struct DonutBurned {
+    pub sol_amount: u64,
+    pub donut_burned: u64,
+    pub total_sol_spent: u64,
+    pub total_donut_burned: u64,
+    pub executions: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a burn the pool cannot swap is escrowed in the pending burn vault
+#[event]
+pub struct BurnDeferred {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_pending: u64, // Pending burn vault lamports after the deposit
+    pub timestamp: i64,
+}
+
+// Event emitted when `retry_pending_burn` swaps the escrowed SOL and burns the DONUT
+#[event]
+pub struct PendingBurnRetried {
+    pub sol_amount: u64,
+    pub donut_burned: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when the deposits held off a suspect pool price are swapped and burned
+#[event]
+pub struct HeldDepositsReleased {
+    pub sol_amount: u64,
+    pub donut_burned: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a timelocked withdrawal moves lamports out of the protocol reserve
+#[event]
+pub struct ReserveWithdrawn {
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when a cascade is saved with uplines left for `continue_cascade`
+#[event]
+pub struct CascadeSaved {
+    pub registrant: Pubkey,
+    pub current_user: Pubkey,
+    pub deposit: u64,
+    pub next_upline_index: u8,
+    pub remaining_uplines: u8,
+    pub timestamp: i64,
+}
+
+// Event emitted when `continue_cascade` settles the deposit of a saved cascade
+#[event]
+pub struct CascadeCompleted {
+    pub registrant: Pubkey,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a deposit no slot used is handed to the terminal sink policy
+#[event]
+pub struct DepositSunk {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub policy: TerminalSinkPolicy,
+    pub timestamp: i64,
+}
+
+// Event emitted when a burn is skipped because the pool price moved too far from its EMA
+#[event]
+pub struct DepositHeld {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub pool_price: u128,
+    pub ema_price: u128,
+    pub deviation_bps: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when the primary oracle is stale and the stale price policy supplies a price
+#[event]
+pub struct StalePriceHandled {
+    pub policy: StalePricePolicy,
+    pub primary_publish_time: i64, // 0 when the primary oracle could not be read
+    pub price: i128,
+    pub expo: i32,
+    pub secondary_sources: u8,
+    pub timestamp: i64,
+}
+
+// Event emitted when the admin fallback price is set
+#[event]
+pub struct AdminPriceSet {
+    pub price: u64,
+    pub expires_at: i64,
+}
+
+// Event emitted when the owner proposes a new owner
+#[event]
+pub struct OwnershipTransferProposed {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+    pub eta: i64,
+}
+
+// Event emitted when a pending ownership transfer is cancelled
+#[event]
+pub struct OwnershipTransferCancelled {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+// Event emitted when the pending owner accepts ownership
+#[event]
+pub struct OwnershipTransferred {
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+// Event emitted when a governance change is queued
+#[event]
+pub struct ConfigChangeQueued {
+    pub change: ConfigChange,
+    pub eta: i64,
+}
+
+// Event emitted when a queued governance change is cancelled
+#[event]
+pub struct ConfigChangeCancelled {
+    pub eta: i64,
+}
+
+// Event emitted when a queued governance change takes effect
+#[event]
+pub struct ConfigChangeExecuted {
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+// Event emitted when registrations are paused or resumed
+#[event]
+pub struct RegistrationPauseUpdated {
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+// Event emitted when swap and burn is paused or resumed
+#[event]
+pub struct SwapAndBurnPauseUpdated {
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+// Event emitted when reserve payouts are paused or resumed
+#[event]
+pub struct ReservePayoutsPauseUpdated {
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+// Event emitted when airdrop claims are paused or resumed
+#[event]
+pub struct AirdropClaimsPauseUpdated {
+    pub paused: bool,
+    pub timestamp: i64,
 }
 
 // Helper function to force memory cleanup
@@ -316,41 +726,81 @@ fn force_memory_cleanup() {
     let _dummy = Vec::<u8>::new();
 }
 
-// Function to get SOL/USD price from Chainlink feed
+// Function to get SOL/USD price from the configured oracles, applying the stale price policy
 fn get_sol_usd_price<'info>(
-    _chainlink_feed: &AccountInfo<'info>,
-    _chainlink_program: &AccountInfo<'info>,
-) -> Result<(i128, u32, i64, i64)> {
-    // TEMPORÁRIO: Retornar valores fixos
-    let price = 100_00000000; // $100 USD
-    let decimals = 8;
+    state: &ProgramState,
+    protocol_config: &ProtocolConfig,
+    oracle_config: &OracleConfig,
+    oracle_accounts: &[AccountInfo<'info>],
+) -> Result<OraclePrice> {
     let clock = Clock::get()?;
     let current_timestamp = clock.unix_timestamp;
-    
-    Ok((price, decimals, current_timestamp, current_timestamp))
+
+    let prices = oracle::read_prices(oracle_config, oracle_accounts, current_timestamp)?;
+    let primary_publish_time = prices
+        .first()
+        .and_then(|price| price.map(|price| price.publish_time))
+        .unwrap_or(0);
+
+    let resolved = oracle::resolve_price(
+        &prices,
+        current_timestamp,
+        protocol_config.max_price_feed_age,
+        state.stale_price_policy,
+        AdminPrice {
+            price: state.admin_sol_price,
+            expires_at: state.admin_price_expires_at,
+        },
+    );
+
+    match resolved {
+        Ok((price, PriceResolution::Primary)) => Ok(price),
+        Ok((price, resolution)) => {
+            let secondary_sources = match resolution {
+                PriceResolution::SecondaryMedian { sources } => sources,
+                _ => 0,
+            };
+            emit!(StalePriceHandled {
+                policy: state.stale_price_policy,
+                primary_publish_time,
+                price: price.price,
+                expo: price.expo,
+                secondary_sources,
+                timestamp: current_timestamp,
+            });
+            Ok(price)
+        }
+        Err(e) => {
+            // Events from a failed transaction are rolled back, so only log here
+            msg!(
+                "Stale price not handled: policy={:?}, primary_publish_time={}",
+                state.stale_price_policy,
+                primary_publish_time
+            );
+            Err(e)
+        }
+    }
 }
 
 // Function to calculate minimum SOL deposit based on USD price
 fn calculate_minimum_sol_deposit<'info>(
-    chainlink_feed: &AccountInfo<'info>, 
-    chainlink_program: &AccountInfo<'info>
+    state: &ProgramState,
+    protocol_config: &ProtocolConfig,
+    oracle_config: &OracleConfig,
+    oracle_accounts: &[AccountInfo<'info>],
 ) -> Result<u64> {
-    let (price, decimals, current_timestamp, feed_timestamp) = get_sol_usd_price(chainlink_feed, chainlink_program)?;
-    
-    let age = current_timestamp - feed_timestamp;
-    
-    let sol_price_per_unit = if age > MAX_PRICE_FEED_AGE {
-        DEFAULT_SOL_PRICE
-    } else {
-        price
-    };
-    
-    let price_f64 = sol_price_per_unit as f64 / 10f64.powf(decimals as f64);
-    let minimum_usd_f64 = MINIMUM_USD_DEPOSIT as f64 / 1_00000000.0;
-    let minimum_sol_f64 = minimum_usd_f64 / price_f64;
-    let minimum_lamports = (minimum_sol_f64 * 1_000_000_000.0) as u64;
-    
-    Ok(minimum_lamports)
+    let price = get_sol_usd_price(state, protocol_config, oracle_config, oracle_accounts)?;
+    minimum_deposit_lamports(protocol_config.minimum_usd_deposit, &price)
+}
+
+// Convert a USD minimum (8 decimals) into lamports, rounding up in favor of the protocol
+pub fn minimum_deposit_lamports(minimum_usd: u64, price: &OraclePrice) -> Result<u64> {
+    let minimum_usd = Decimal::new(i128::from(minimum_usd), 8);
+    let sol_price = Decimal::from_price(price.price, price.expo)
+        .ok_or(error!(ErrorCode::DepositCalculationOverflow))?;
+
+    decimal::usd_to_lamports(&minimum_usd, &sol_price)
+        .ok_or(error!(ErrorCode::DepositCalculationOverflow))
 }
 
 // Function to verify an address
@@ -363,22 +813,25 @@ fn verify_address_strict(provided: &Pubkey, expected: &Pubkey, error_code: Error
 }
 
 // Verify pool and vault A addresses
-fn verify_pool_and_vault_a_addresses<'info>(
+pub fn verify_pool_and_vault_a_addresses(
+    config: &ProtocolConfig,
     pool: &Pubkey,
     a_vault: &Pubkey,
     a_vault_lp: &Pubkey,
     a_vault_lp_mint: &Pubkey,
 ) -> Result<()> {
-    verify_address_strict(pool, &verified_addresses::POOL_ADDRESS, ErrorCode::InvalidPoolAddress)?;
-    verify_address_strict(a_vault, &verified_addresses::A_VAULT, ErrorCode::InvalidVaultAddress)?;
-    verify_address_strict(a_vault_lp, &verified_addresses::A_VAULT_LP, ErrorCode::InvalidVaultALpAddress)?;
-    verify_address_strict(a_vault_lp_mint, &verified_addresses::A_VAULT_LP_MINT, ErrorCode::InvalidVaultALpMintAddress)?;
+    verify_address_strict(pool, &config.addresses.pool, ErrorCode::InvalidPoolAddress)?;
+    verify_address_strict(a_vault, &config.addresses.a_vault, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(a_vault_lp, &config.addresses.a_vault_lp, ErrorCode::InvalidVaultALpAddress)?;
+    verify_address_strict(a_vault_lp_mint, &config.addresses.a_vault_lp_mint, ErrorCode::InvalidVaultALpMintAddress)?;
     
     Ok(())
 }
 
 // Function to verify all fixed addresses at once
-fn verify_all_fixed_addresses<'info>(
+#[allow(clippy::too_many_arguments)]
+pub fn verify_all_fixed_addresses(
+    config: &ProtocolConfig,
     pool: &Pubkey,
     b_vault: &Pubkey,        
     b_token_vault: &Pubkey,  
@@ -387,27 +840,61 @@ fn verify_all_fixed_addresses<'info>(
     token_mint: &Pubkey,
     wsol_mint: &Pubkey,
 ) -> Result<()> {
-    verify_address_strict(pool, &verified_addresses::POOL_ADDRESS, ErrorCode::InvalidPoolAddress)?;
-    verify_address_strict(b_vault_lp, &verified_addresses::B_VAULT_LP, ErrorCode::InvalidVaultAddress)?;
-    verify_address_strict(b_vault, &verified_addresses::B_VAULT, ErrorCode::InvalidVaultAddress)?;
-    verify_address_strict(b_token_vault, &verified_addresses::B_TOKEN_VAULT, ErrorCode::InvalidVaultAddress)?;
-    verify_address_strict(b_vault_lp_mint, &verified_addresses::B_VAULT_LP_MINT, ErrorCode::InvalidVaultAddress)?;
-    verify_address_strict(token_mint, &verified_addresses::TOKEN_MINT, ErrorCode::InvalidTokenMintAddress)?;
-    verify_address_strict(wsol_mint, &verified_addresses::WSOL_MINT, ErrorCode::InvalidTokenMintAddress)?;
+    verify_address_strict(pool, &config.addresses.pool, ErrorCode::InvalidPoolAddress)?;
+    verify_address_strict(b_vault_lp, &config.addresses.b_vault_lp, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(b_vault, &config.addresses.b_vault, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(b_token_vault, &config.addresses.b_token_vault, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(b_vault_lp_mint, &config.addresses.b_vault_lp_mint, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(token_mint, &config.addresses.token_mint, ErrorCode::InvalidTokenMintAddress)?;
+    verify_address_strict(wsol_mint, &config.addresses.wsol_mint, ErrorCode::InvalidTokenMintAddress)?;
     
     Ok(())
 }
 
-// Function to verify Chainlink addresses
-fn verify_chainlink_addresses<'info>(
-    _chainlink_program: &Pubkey,
-    _chainlink_feed: &Pubkey,
-) -> Result<()> {
-    // TEMPORÁRIO: Sempre retornar Ok
+// Venue of a registration. Both registration structs keep the legacy account layout, which
+// names the SOL side of the pool while its DONUT side follows the pool in remaining_accounts.
+macro_rules! registration_venue {
+    ($ctx:expr) => {
+        VenueAccounts {
+            pool: $ctx.accounts.pool.to_account_info(),
+            a_vault: $ctx.remaining_accounts[1].clone(),
+            a_vault_lp: $ctx.remaining_accounts[2].clone(),
+            a_vault_lp_mint: $ctx.remaining_accounts[3].clone(),
+            a_token_vault: $ctx.remaining_accounts[4].clone(),
+            b_vault: $ctx.accounts.b_vault.to_account_info(),
+            b_vault_lp: $ctx.accounts.b_vault_lp.to_account_info(),
+            b_vault_lp_mint: $ctx.accounts.b_vault_lp_mint.to_account_info(),
+            b_token_vault: $ctx.accounts.b_token_vault.to_account_info(),
+            protocol_token_fee: $ctx.accounts.protocol_token_fee.to_account_info(),
+            vault_program: $ctx.accounts.vault_program.to_account_info(),
+            amm_program: $ctx.accounts.amm_program.to_account_info(),
+            token_program: $ctx.accounts.token_program.to_account_info(),
+        }
+    };
+}
+
+// Verify every swap venue account against the protocol config
+pub fn verify_venue_accounts(config: &ProtocolConfig, venue: &VenueAccounts) -> Result<()> {
+    verify_pool_and_vault_a_addresses(
+        config,
+        venue.pool.key,
+        venue.a_vault.key,
+        venue.a_vault_lp.key,
+        venue.a_vault_lp_mint.key,
+    )?;
+    verify_address_strict(venue.a_token_vault.key, &config.addresses.a_token_vault, ErrorCode::InvalidTokenAVaultAddress)?;
+    verify_address_strict(venue.b_vault.key, &config.addresses.b_vault, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(venue.b_vault_lp.key, &config.addresses.b_vault_lp, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(venue.b_vault_lp_mint.key, &config.addresses.b_vault_lp_mint, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(venue.b_token_vault.key, &config.addresses.b_token_vault, ErrorCode::InvalidVaultAddress)?;
+    verify_address_strict(venue.protocol_token_fee.key, &config.addresses.protocol_token_b_fee, ErrorCode::InvalidProtocolFeeAccount)?;
+    verify_address_strict(venue.vault_program.key, &config.addresses.meteora_vault_program, ErrorCode::InvalidVaultProgram)?;
+    verify_address_strict(venue.amm_program.key, &config.addresses.meteora_amm_program, ErrorCode::InvalidAmmProgram)?;
     Ok(())
 }
 
-fn validate_all_remaining_accounts<'info>(
+pub fn validate_all_remaining_accounts<'info>(
+    config: &ProtocolConfig,
     remaining_accounts: &[AccountInfo<'info>],
     expected_base_count: usize,
 ) -> Result<()> {
@@ -416,7 +903,7 @@ fn validate_all_remaining_accounts<'info>(
     }
     
     let pool = &remaining_accounts[0];
-    verify_address_strict(&pool.key(), &verified_addresses::POOL_ADDRESS, 
+    verify_address_strict(&pool.key(), &config.addresses.pool, 
                          ErrorCode::InvalidPoolAddress)?;
     
     let a_vault = &remaining_accounts[1];
@@ -424,92 +911,48 @@ fn validate_all_remaining_accounts<'info>(
     let a_vault_lp_mint = &remaining_accounts[3];
     let _a_token_vault = &remaining_accounts[4];
     
-    verify_address_strict(&a_vault.key(), &verified_addresses::A_VAULT, 
+    verify_address_strict(&a_vault.key(), &config.addresses.a_vault, 
                          ErrorCode::InvalidVaultAddress)?;
-    verify_address_strict(&a_vault_lp.key(), &verified_addresses::A_VAULT_LP, 
+    verify_address_strict(&a_vault_lp.key(), &config.addresses.a_vault_lp, 
                          ErrorCode::InvalidVaultALpAddress)?;
-    verify_address_strict(&a_vault_lp_mint.key(), &verified_addresses::A_VAULT_LP_MINT, 
+    verify_address_strict(&a_vault_lp_mint.key(), &config.addresses.a_vault_lp_mint, 
                          ErrorCode::InvalidVaultALpMintAddress)?;
     
-    let chainlink_feed = &remaining_accounts[5];
-    let chainlink_program = &remaining_accounts[6];
-    
-    verify_address_strict(&chainlink_program.key(), &verified_addresses::CHAINLINK_PROGRAM, 
-                         ErrorCode::InvalidChainlinkProgram)?;
-    verify_address_strict(&chainlink_feed.key(), &verified_addresses::SOL_USD_FEED, 
-                         ErrorCode::InvalidPriceFeed)?;
+    // Oracle accounts (between vault A and the uplines) are verified by the oracle module
     
-    if remaining_accounts.len() > expected_base_count {
-        let upline_accounts = &remaining_accounts[expected_base_count..];
-        if upline_accounts.len() % 2 != 0 {
-            return Err(error!(ErrorCode::MissingUplineAccount));
-        }
-        
-        for chunk in upline_accounts.chunks(2) {
-            let upline_pda = &chunk[0];
-            let upline_wallet = &chunk[1];
-            
-            if upline_pda.owner != &crate::ID {
-                return Err(error!(ErrorCode::InvalidSlotOwner));
-            }
-            if upline_wallet.owner != &solana_program::system_program::ID {
-                return Err(error!(ErrorCode::PaymentWalletInvalid));
-            }
+    // Uplines are passed as their user account PDAs only; payouts are claimed, not pushed to wallets
+    for upline_pda in &remaining_accounts[expected_base_count..] {
+        if upline_pda.owner != &crate::ID {
+            return Err(error!(ErrorCode::InvalidSlotOwner));
         }
     }
     
     Ok(())
 }
 
+// Check the upline PDAs passed for a cascade step against the ones it is due to walk, in order
 fn validate_upline_accounts<'info>(
-    referrer: &Account<'_, UserAccount>,
+    expected_uplines: &[Pubkey],
     upline_accounts: &[AccountInfo<'info>],
 ) -> Result<()> {
-    let expected_uplines = referrer.upline.upline.len();
+    let upline_count = upline_accounts.len();
     
-    if upline_accounts.len() % 2 != 0 {
-        msg!("ERROR: Upline accounts not in pairs: {}", upline_accounts.len());
-        return Err(error!(ErrorCode::MissingUplineAccount));
-    }
-    
-    let pair_count = upline_accounts.len() / 2;
-    
-    if pair_count != expected_uplines {
+    if upline_count != expected_uplines.len() {
         msg!(
-            "CRITICAL: Must send ALL uplines! Referrer has {} uplines, got {} pairs",
-            expected_uplines,
-            pair_count
+            "CRITICAL: Must send the next {} uplines of the cascade, got {}",
+            expected_uplines.len(),
+            upline_count
         );
         return Err(error!(ErrorCode::InvalidUplineAccount));
     }
     
-    if expected_uplines == 0 {
-        msg!("Referrer has no uplines - this should not happen in SLOT 3 normal user");
-        return Ok(());
-    }
-    
-    msg!("Validating ALL {} upline pairs", pair_count);
+    msg!("Validating {} uplines", upline_count);
     
-    for i in 0..pair_count {
-        let base_idx = i * 2;
-        let upline_pda = &upline_accounts[base_idx];
-        let upline_wallet = &upline_accounts[base_idx + 1];
-        
-        let upline_entry_idx = expected_uplines - 1 - i;
-        let expected_upline = &referrer.upline.upline[upline_entry_idx];
-        
-        if upline_pda.key() != expected_upline.pda {
+    for (i, (upline_pda, expected_upline)) in upline_accounts.iter().zip(expected_uplines).enumerate() {
+        if upline_pda.key() != *expected_upline {
             msg!(
                 "UPLINE PDA MISMATCH at position {}: Expected {}, Got {}", 
-                i, expected_upline.pda, upline_pda.key()
-            );
-            return Err(error!(ErrorCode::InvalidUplineAccount));
-        }
-        
-        if upline_wallet.key() != expected_upline.wallet {
-            msg!(
-                "UPLINE WALLET MISMATCH at position {}: Expected {}, Got {}", 
-                i, expected_upline.wallet, upline_wallet.key()
+                i, expected_upline, upline_pda.key()
             );
             return Err(error!(ErrorCode::InvalidUplineAccount));
         }
@@ -518,339 +961,73 @@ fn validate_upline_accounts<'info>(
             return Err(error!(ErrorCode::InvalidSlotOwner));
         }
         
-        if upline_wallet.owner != &solana_program::system_program::ID {
-            return Err(error!(ErrorCode::PaymentWalletInvalid));
-        }
-        
         msg!("✅ Upline {} validated: {}", i, upline_pda.key());
     }
     
-    msg!("🎯 ALL {} upline pairs validated successfully", pair_count);
+    msg!("🎯 ALL {} uplines validated successfully", upline_count);
     Ok(())
 }
 
-// Verify if an account is a valid wallet (system account)
-fn verify_wallet_is_system_account<'info>(wallet: &AccountInfo<'info>) -> Result<()> {
-    if wallet.owner != &solana_program::system_program::ID {
-        return Err(error!(ErrorCode::PaymentWalletInvalid));
+// Amount of vault tokens owned through the pool's LP position in a Meteora vault,
+// leaving out the harvest profit the vault still has locked at `current_time`
+pub fn get_vault_token_amount<'info>(
+    vault: &AccountInfo<'info>,
+    vault_lp: &AccountInfo<'info>,
+    vault_lp_mint: &AccountInfo<'info>,
+    vault_program: &Pubkey,
+    current_time: i64,
+) -> Result<u64> {
+    layout::check_owner(vault, vault_program)?;
+    let vault_data = vault.try_borrow_data()?;
+    let vault_view = layout::VaultView::decode(&vault_data)?;
+    
+    if vault_lp_mint.key() != vault_view.lp_mint() {
+        msg!("Vault LP mint mismatch: {}", vault_lp_mint.key());
+        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
     }
     
-    Ok(())
-}
-
-// Manage WSOL operations (wrap/unwrap)
-fn manage_wsol_operation<'info>(
-    user_wallet: &AccountInfo<'info>,
-    user_wsol_account: &AccountInfo<'info>,
-    token_program: &Program<'info, Token>,
-    operation: &str,
-    amount: Option<u64>,
-) -> Result<()> {
-    match operation {
-        "wrap" => {
-            if let Some(transfer_amount) = amount {
-                // Transfer SOL to WSOL account
-                let transfer_ix = solana_program::system_instruction::transfer(
-                    &user_wallet.key(),
-                    &user_wsol_account.key(),
-                    transfer_amount
-                );
-                
-                solana_program::program::invoke(
-                    &transfer_ix,
-                    &[user_wallet.clone(), user_wsol_account.clone()],
-                ).map_err(|e| {
-                    msg!("Transfer to WSOL failed: {:?}", e);
-                    error!(ErrorCode::WrapSolFailed)
-                })?;
-                
-                // Sync native balance
-                let sync_native_ix = spl_token::instruction::sync_native(
-                    &token_program.key(),
-                    &user_wsol_account.key(),
-                ).map_err(|_| error!(ErrorCode::WrapSolFailed))?;
-                
-                solana_program::program::invoke(
-                    &sync_native_ix,
-                    &[user_wsol_account.clone()],
-                ).map_err(|e| {
-                    msg!("Sync native failed: {:?}", e);
-                    error!(ErrorCode::WrapSolFailed)
-                })?;
-                
-                msg!("WSOL wrapped successfully: {}", transfer_amount);
-            }
-        },
-        
-        "unwrap" => {
-            // Close WSOL account to recover SOL
-            let close_ix = spl_token::instruction::close_account(
-                &token_program.key(),
-                &user_wsol_account.key(),
-                &user_wallet.key(),
-                &user_wallet.key(),
-                &[]
-            ).map_err(|_| error!(ErrorCode::UnwrapSolFailed))?;
-            
-            solana_program::program::invoke(
-                &close_ix,
-                &[
-                    user_wsol_account.clone(),
-                    user_wallet.clone(),
-                    user_wallet.clone(),
-                ],
-            ).map_err(|e| {
-                msg!("Close WSOL account failed: {:?}", e);
-                error!(ErrorCode::UnwrapSolFailed)
-            })?;
-            
-            msg!("WSOL unwrapped successfully");
-        },
-        
-        _ => {
-            return Err(error!(ErrorCode::WrapSolFailed));
-        }
+    // Read LP amount
+    layout::check_owner(vault_lp, &spl_token::ID)?;
+    let lp_data = vault_lp.try_borrow_data()?;
+    let lp_view = layout::TokenAccountView::decode(&lp_data)?;
+    if lp_view.mint() != vault_lp_mint.key() {
+        msg!("Vault LP account holds another mint: {}", lp_view.mint());
+        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
     }
+    let vault_lp_amount = lp_view.amount();
     
-    Ok(())
+    // Read LP supply
+    let vault_lp_supply = layout::read_mint_supply(vault_lp_mint)?;
+    
+    let current_time = u64::try_from(current_time).map_err(|_| error!(ErrorCode::MeteoraCalculationOverflow))?;
+    vault_view
+        .get_amount_by_share(current_time, vault_lp_amount, vault_lp_supply)
+        .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))
 }
 
-// Calculate expected swap output
-fn calculate_swap_amount_out<'info>(
-    pool: &AccountInfo<'info>,
-    a_vault: &AccountInfo<'info>,
-    b_vault: &AccountInfo<'info>,
-    a_vault_lp: &AccountInfo<'info>,
-    b_vault_lp: &AccountInfo<'info>,
-    a_vault_lp_mint: &AccountInfo<'info>,
-    b_vault_lp_mint: &AccountInfo<'info>,
+// Process swap from WSOL to DONUT into the program burn account and burn, returning the DONUT burned.
+// `signer_seeds` sign for a `user` that is a program address; the burn account signs its own burn.
+#[allow(clippy::too_many_arguments)]
+fn process_swap_and_burn<'info>(
+    dex: &dyn DexAdapter<'info>,
+    user: &AccountInfo<'info>,
+    user_wsol_account: &AccountInfo<'info>,
+    burn_account: &AccountInfo<'info>,
+    burn_account_bump: u8,
+    token_program: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
     amount_in: u64,
+    minimum_amount_out: u64,
+    signer_seeds: &[&[&[u8]]],
 ) -> Result<u64> {
-    const PRECISION_FACTOR: i128 = 1_000_000_000;
+    msg!("Starting swap: {} WSOL for DONUT (min: {})", amount_in, minimum_amount_out);
+
+    dex::swap_for_donut(dex, user, user_wsol_account, burn_account, amount_in, minimum_amount_out, signer_seeds)?;
     
-    // Check if pool is enabled
-    let pool_data = pool.try_borrow_data()?;
-    let enabled_offset = 8 + 225;
+    msg!("Swap completed successfully");
     
-    if pool_data.len() <= enabled_offset {
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    let pool_enabled = pool_data[enabled_offset] != 0;
-    if !pool_enabled {
-        msg!("Pool is disabled");
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    // Read vault A total amount
-    let vault_data = a_vault.try_borrow_data()?;
-    if vault_data.len() < 19 {
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    let vault_a_total = u64::from_le_bytes([
-        vault_data[11], vault_data[12], vault_data[13], vault_data[14],
-        vault_data[15], vault_data[16], vault_data[17], vault_data[18]
-    ]);
-    
-    // Read vault B total amount
-    let vault_data = b_vault.try_borrow_data()?;
-    if vault_data.len() < 19 {
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    let vault_b_total = u64::from_le_bytes([
-        vault_data[11], vault_data[12], vault_data[13], vault_data[14],
-        vault_data[15], vault_data[16], vault_data[17], vault_data[18]
-    ]);
-    
-    // Read LP amounts
-    let a_data = a_vault_lp.try_borrow_data()?;
-    let b_data = b_vault_lp.try_borrow_data()?;
-    
-    if a_data.len() < 72 || b_data.len() < 72 {
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    let a_vault_lp_amount = u64::from_le_bytes([
-        a_data[64], a_data[65], a_data[66], a_data[67],
-        a_data[68], a_data[69], a_data[70], a_data[71]
-    ]);
-    
-    let b_vault_lp_amount = u64::from_le_bytes([
-        b_data[64], b_data[65], b_data[66], b_data[67],
-        b_data[68], b_data[69], b_data[70], b_data[71]
-    ]);
-    
-    // Read LP supplies
-    let a_mint_data = a_vault_lp_mint.try_borrow_data()?;
-    let b_mint_data = b_vault_lp_mint.try_borrow_data()?;
-    
-    if a_mint_data.len() < 44 || b_mint_data.len() < 44 {
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    let a_vault_lp_supply = u64::from_le_bytes([
-        a_mint_data[36], a_mint_data[37], a_mint_data[38], a_mint_data[39],
-        a_mint_data[40], a_mint_data[41], a_mint_data[42], a_mint_data[43]
-    ]);
-    
-    let b_vault_lp_supply = u64::from_le_bytes([
-        b_mint_data[36], b_mint_data[37], b_mint_data[38], b_mint_data[39],
-        b_mint_data[40], b_mint_data[41], b_mint_data[42], b_mint_data[43]
-    ]);
-    
-    // Calculate token amounts
-    let token_a_amount = if a_vault_lp_supply == 0 {
-        0
-    } else {
-        let numerator = (a_vault_lp_amount as u128)
-            .checked_mul(vault_a_total as u128)
-            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-        
-        let result = numerator
-            .checked_div(a_vault_lp_supply as u128)
-            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-        
-        u64::try_from(result).map_err(|_| error!(ErrorCode::MeteoraCalculationOverflow))?
-    };
-
-    let token_b_amount = if b_vault_lp_supply == 0 {
-        0
-    } else {
-        let numerator = (b_vault_lp_amount as u128)
-            .checked_mul(vault_b_total as u128)
-            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-        
-        let result = numerator
-            .checked_div(b_vault_lp_supply as u128)
-            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-        
-        u64::try_from(result).map_err(|_| error!(ErrorCode::MeteoraCalculationOverflow))?
-    };
-    
-    msg!("Token amounts - A: {}, B: {}", token_a_amount, token_b_amount);
-    
-    if token_a_amount == 0 || token_b_amount == 0 {
-        return Err(error!(ErrorCode::PriceMeteoraReadFailed));
-    }
-    
-    // Calculate ratio
-    let ratio = (token_a_amount as i128)
-        .checked_mul(PRECISION_FACTOR)
-        .and_then(|n| n.checked_div(token_b_amount as i128))
-        .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-    
-    // Calculate DONUT tokens
-    let donut_tokens = (amount_in as i128)
-        .checked_mul(ratio)
-        .and_then(|n| n.checked_div(PRECISION_FACTOR))
-        .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-    
-    if donut_tokens > i128::from(u64::MAX) || donut_tokens < 0 {
-        return Err(error!(ErrorCode::MeteoraCalculationOverflow));
-    }
-    
-    let result = donut_tokens as u64;
-    
-    // Apply 99% slippage tolerance (accept receiving only 1% of expected)
-    let minimum_out = result
-        .checked_mul(1)  // 1% of expected value
-        .and_then(|n| n.checked_div(100))
-        .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
-    
-    msg!("Expected output: {} DONUT, Minimum accepted (99% slippage): {} DONUT", result, minimum_out);
-    
-    Ok(if minimum_out == 0 { 1 } else { minimum_out })
-}
-
-// Process swap from WSOL to DONUT and burn
-fn process_swap_and_burn<'info>(
-    pool: &AccountInfo<'info>,
-    user: &AccountInfo<'info>,
-    user_wsol_account: &AccountInfo<'info>,
-    user_donut_account: &AccountInfo<'info>,
-    a_vault: &AccountInfo<'info>,
-    b_vault: &AccountInfo<'info>,
-    a_token_vault: &AccountInfo<'info>,
-    b_token_vault: &AccountInfo<'info>,
-    a_vault_lp_mint: &AccountInfo<'info>,
-    b_vault_lp_mint: &AccountInfo<'info>,
-    a_vault_lp: &AccountInfo<'info>,
-    b_vault_lp: &AccountInfo<'info>,
-    protocol_token_fee: &AccountInfo<'info>,
-    vault_program: &AccountInfo<'info>,
-    token_program: &AccountInfo<'info>,
-    amm_program: &AccountInfo<'info>,
-    token_mint: &AccountInfo<'info>,
-    amount_in: u64,
-    minimum_amount_out: u64,
-) -> Result<()> {
-    msg!("Starting swap: {} WSOL for DONUT (min: {})", amount_in, minimum_amount_out);
-    
-    // Build swap accounts
-    let swap_accounts = vec![
-        solana_program::instruction::AccountMeta::new(pool.key(), false),
-        solana_program::instruction::AccountMeta::new(user_wsol_account.key(), false),
-        solana_program::instruction::AccountMeta::new(user_donut_account.key(), false),
-        solana_program::instruction::AccountMeta::new(a_vault.key(), false),
-        solana_program::instruction::AccountMeta::new(b_vault.key(), false),
-        solana_program::instruction::AccountMeta::new(a_token_vault.key(), false),
-        solana_program::instruction::AccountMeta::new(b_token_vault.key(), false),
-        solana_program::instruction::AccountMeta::new(a_vault_lp_mint.key(), false),
-        solana_program::instruction::AccountMeta::new(b_vault_lp_mint.key(), false),
-        solana_program::instruction::AccountMeta::new(a_vault_lp.key(), false),
-        solana_program::instruction::AccountMeta::new(b_vault_lp.key(), false),
-        solana_program::instruction::AccountMeta::new(protocol_token_fee.key(), false),
-        solana_program::instruction::AccountMeta::new_readonly(user.key(), true),
-        solana_program::instruction::AccountMeta::new_readonly(vault_program.key(), false),
-        solana_program::instruction::AccountMeta::new_readonly(token_program.key(), false),
-    ];
-    
-    // Swap discriminator: sha256("global:swap")[0..8]
-    let mut data = vec![248, 198, 158, 145, 225, 117, 135, 200];
-    data.extend_from_slice(&amount_in.to_le_bytes());
-    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
-    
-    let swap_instruction = solana_program::instruction::Instruction {
-        program_id: amm_program.key(),
-        accounts: swap_accounts,
-        data,
-    };
-    
-    // Execute swap
-    solana_program::program::invoke(
-        &swap_instruction,
-        &[
-            pool.clone(),
-            user_wsol_account.clone(),
-            user_donut_account.clone(),
-            a_vault.clone(),
-            b_vault.clone(),
-            a_token_vault.clone(),
-            b_token_vault.clone(),
-            a_vault_lp_mint.clone(),
-            b_vault_lp_mint.clone(),
-            a_vault_lp.clone(),
-            b_vault_lp.clone(),
-            protocol_token_fee.clone(),
-            user.clone(),
-            vault_program.clone(),
-            token_program.clone(),
-        ],
-    ).map_err(|e| {
-        msg!("Swap failed: {:?}", e);
-        error!(ErrorCode::SwapFailed)
-    })?;
-    
-    msg!("Swap completed successfully");
-    
-    // Get DONUT balance
-    let donut_data = user_donut_account.try_borrow_data()?;
-    let donut_balance = u64::from_le_bytes([
-        donut_data[64], donut_data[65], donut_data[66], donut_data[67],
-        donut_data[68], donut_data[69], donut_data[70], donut_data[71]
-    ]);
+    // Get DONUT balance
+    let donut_balance = layout::read_token_amount(burn_account)?;
     
     msg!("DONUT balance after swap: {}", donut_balance);
     
@@ -860,20 +1037,20 @@ fn process_swap_and_burn<'info>(
         
         let burn_ix = spl_token::instruction::burn(
             &token_program.key(),
-            &user_donut_account.key(),
+            &burn_account.key(),
             &token_mint.key(),
-            &user.key(),
+            &burn_account.key(),
             &[],
             donut_balance,
         ).map_err(|_| error!(ErrorCode::BurnFailed))?;
         
-        solana_program::program::invoke(
+        solana_program::program::invoke_signed(
             &burn_ix,
             &[
-                user_donut_account.clone(),
+                burn_account.clone(),
                 token_mint.clone(),
-                user.clone(),
             ],
+            &[&[b"burn_account".as_ref(), &[burn_account_bump]]],
         ).map_err(|e| {
             msg!("Burn failed: {:?}", e);
             error!(ErrorCode::BurnFailed)
@@ -884,14 +1061,215 @@ fn process_swap_and_burn<'info>(
         msg!("⚠️ No DONUT balance to burn");
     }
     
+    Ok(donut_balance)
+}
+
+// Update the tracker with the pool price and refuse to burn from a vault while it is off its EMA
+fn check_vault_burn_price<'info>(
+    dex: &dyn DexAdapter<'info>,
+    price_tracker: &mut PriceTracker,
+    max_price_deviation_bps: u16,
+    current_time: i64,
+) -> Result<()> {
+    let (donut_amount, sol_amount) = dex.reserves(current_time)?;
+    let pool_price = price_guard::pool_price(donut_amount, sol_amount)
+        .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
+    price_tracker.update(pool_price, current_time);
+    if let Some(deviation_bps) = price_tracker.check(pool_price, max_price_deviation_bps) {
+        msg!("Pool price {} deviates {} bps from EMA {}", pool_price, deviation_bps, price_tracker.ema_price);
+        return Err(error!(ErrorCode::PriceDeviationTooHigh));
+    }
     Ok(())
 }
 
+// Where a Burn slot sends its deposit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BurnRoute {
+    Queue,                                         // Batched mode: the burn vault
+    Escrow,                                        // Pool cannot quote the swap: the pending burn vault
+    Hold { pool_price: u128, deviation_bps: u64 }, // Pool price off its EMA: the holding vault
+    Swap { expected_donut_out: u64 },              // Swapped and burned inside the registration
+}
+
+// Pick the route of a burn of `amount` lamports from the burn mode, the pool and the price guard
+#[allow(clippy::too_many_arguments)]
+pub fn route_burn<'info>(
+    dex: &dyn DexAdapter<'info>,
+    amount: u64,
+    burn_mode: BurnMode,
+    swap_failure_policy: SwapFailurePolicy,
+    price_tracker: &PriceTracker,
+    max_price_deviation_bps: u16,
+    current_time: i64,
+) -> Result<BurnRoute> {
+    if burn_mode == BurnMode::Batched {
+        return Ok(BurnRoute::Queue);
+    }
+
+    // A failing swap CPI aborts the whole transaction, so the escrow policy can only
+    // catch what fails before it: a disabled pool or reserves that cannot be quoted
+    let quote = dex.reserves(current_time)
+        .and_then(|(donut_amount, sol_amount)| {
+            price_guard::pool_price(donut_amount, sol_amount).ok_or(error!(ErrorCode::MeteoraCalculationOverflow))
+        })
+        .and_then(|pool_price| Ok((pool_price, dex.quote_donut_out(amount, current_time)?)));
+    let (pool_price, expected_donut_out) = match quote {
+        Ok(quote) => quote,
+        Err(err) if swap_failure_policy == SwapFailurePolicy::Escrow => {
+            msg!("Swap unavailable: {}", err);
+            return Ok(BurnRoute::Escrow);
+        }
+        Err(err) => return Err(err),
+    };
+
+    if let Some(deviation_bps) = price_tracker.check(pool_price, max_price_deviation_bps) {
+        return Ok(BurnRoute::Hold { pool_price, deviation_bps });
+    }
+    Ok(BurnRoute::Swap { expected_donut_out })
+}
+
+// Move the price history forward with the current pool price. Reserves that cannot be read
+// or are empty are skipped here; a burn that needs them escrows or fails through `route_burn`.
+pub fn record_pool_price<'info>(dex: &dyn DexAdapter<'info>, price_tracker: &mut PriceTracker, current_time: i64) {
+    let pool_price = dex.reserves(current_time)
+        .ok()
+        .and_then(|(donut_amount, sol_amount)| price_guard::pool_price(donut_amount, sol_amount))
+        .filter(|pool_price| *pool_price > 0);
+    match pool_price {
+        Some(pool_price) => price_tracker.update(pool_price, current_time),
+        None => msg!("Pool price unavailable, price tracker not updated"),
+    }
+}
+
+// Program vault a crank swaps for DONUT and burns, with its WSOL account
+pub struct VaultBurnAccounts<'info> {
+    pub vault: AccountInfo<'info>,
+    pub vault_wsol_account: AccountInfo<'info>,
+    pub burn_account: AccountInfo<'info>,
+    pub burn_account_bump: u8,
+    pub token_program: AccountInfo<'info>,
+    pub token_mint: AccountInfo<'info>,
+}
+
+// Wrap `amount` lamports of the program vault in its WSOL account, swap them for DONUT and
+// burn it, returning the DONUT burned. Refuses while the pool price is off its EMA.
+// `vault_seeds` sign for the vault.
+#[allow(clippy::too_many_arguments)]
+pub fn burn_vault_lamports<'info>(
+    dex: &dyn DexAdapter<'info>,
+    accounts: &VaultBurnAccounts<'info>,
+    price_tracker: &mut PriceTracker,
+    amount: u64,
+    max_price_deviation_bps: u16,
+    max_slippage_bps: u16,
+    vault_seeds: &[&[&[u8]]],
+    current_time: i64,
+) -> Result<u64> {
+    check_vault_burn_price(dex, price_tracker, max_price_deviation_bps, current_time)?;
+
+    let vault = &accounts.vault;
+    let vault_wsol_account = &accounts.vault_wsol_account;
+    let ix = solana_program::system_instruction::transfer(&vault.key(), &vault_wsol_account.key(), amount);
+    solana_program::program::invoke_signed(
+        &ix,
+        &[vault.clone(), vault_wsol_account.clone()],
+        vault_seeds,
+    ).map_err(|e| {
+        msg!("Vault wrap failed: {:?}", e);
+        error!(ErrorCode::WrapSolFailed)
+    })?;
+    let sync_native_ix = spl_token::instruction::sync_native(&spl_token::ID, &vault_wsol_account.key())
+        .map_err(|_| error!(ErrorCode::WrapSolFailed))?;
+    solana_program::program::invoke(&sync_native_ix, std::slice::from_ref(vault_wsol_account))
+        .map_err(|_| error!(ErrorCode::WrapSolFailed))?;
+
+    let expected_donut_out = dex.quote_donut_out(amount, current_time)?;
+    let minimum_donut_out = slippage::bounded_minimum_amount_out(
+        expected_donut_out,
+        dex::fair_donut_out(dex, price_tracker, amount, current_time),
+        max_slippage_bps,
+    );
+    process_swap_and_burn(
+        dex,
+        vault,
+        vault_wsol_account,
+        &accounts.burn_account,
+        accounts.burn_account_bump,
+        &accounts.token_program,
+        &accounts.token_mint,
+        amount,
+        minimum_donut_out,
+        vault_seeds,
+    )
+}
+
+// Program vault a burn crank drains, with the PDA seeds signing for it and the smallest
+// queued amount worth a swap
+pub struct QueuedVault<'a, 'info> {
+    pub account: AccountInfo<'info>,
+    pub wsol_account: AccountInfo<'info>,
+    pub seeds: &'a [&'a [u8]],
+    pub minimum_amount: u64,
+    pub below_minimum: ErrorCode,
+}
+
+// Body of the vault burn cranks: swap the lamports queued in `vault` for DONUT and burn it,
+// returning the lamports swapped and the DONUT burned
+fn burn_queued_vault<'info>(
+    burn: &mut VaultBurn<'info>,
+    burn_account_bump: u8,
+    token_program: &AccountInfo<'info>,
+    vault: &QueuedVault<'_, 'info>,
+    max_slippage_bps: u16,
+    now: i64,
+) -> Result<(u64, u64)> {
+    burn.state.pause_flags.ensure_not_paused(PauseFlag::SwapAndBurn)?;
+    let max_slippage_bps = slippage::clamp_slippage_bps(max_slippage_bps, burn.protocol_config.max_slippage_bps);
+
+    // Released on every exit path when the guard goes out of scope
+    let _reentrancy_guard = ReentrancyGuard::acquire(&burn.state.to_account_info())?;
+
+    let config = &burn.protocol_config;
+    verify_address_strict(&burn.token_mint.key(), &config.addresses.token_mint, ErrorCode::InvalidTokenMintAddress)?;
+    verify_address_strict(&burn.wsol_mint.key(), &config.addresses.wsol_mint, ErrorCode::InvalidTokenMintAddress)?;
+
+    let venue = burn.pool_accounts.venue(token_program);
+    verify_venue_accounts(config, &venue)?;
+
+    let amount = buyback::queued_amount(vault.account.lamports(), Rent::get()?.minimum_balance(0));
+    if amount < vault.minimum_amount {
+        msg!("Vault holds {} lamports, minimum is {}", amount, vault.minimum_amount);
+        return Err(error!(vault.below_minimum));
+    }
+
+    let dex = dex::adapter(config.swap_venue, &venue, config.addresses.token_mint);
+    let vault_accounts = VaultBurnAccounts {
+        vault: vault.account.clone(),
+        vault_wsol_account: vault.wsol_account.clone(),
+        burn_account: burn.burn_account.to_account_info(),
+        burn_account_bump,
+        token_program: token_program.clone(),
+        token_mint: burn.token_mint.to_account_info(),
+    };
+    let donut_burned = burn_vault_lamports(
+        dex.as_ref(),
+        &vault_accounts,
+        &mut burn.price_tracker,
+        amount,
+        config.max_price_deviation_bps,
+        max_slippage_bps,
+        &[vault.seeds],
+        now,
+    )?;
+    Ok((amount, donut_burned))
+}
+
 // Function to process SOL reserve
 fn process_reserve_sol<'info>(
     from: &AccountInfo<'info>,
     to: &AccountInfo<'info>,
     amount: u64,
+    signer_seeds: &[&[&[u8]]],
 ) -> Result<()> {
     let ix = solana_program::system_instruction::transfer(
         &from.key(),
@@ -899,9 +1277,10 @@ fn process_reserve_sol<'info>(
         amount
     );
     
-    solana_program::program::invoke(
+    solana_program::program::invoke_signed(
         &ix,
         &[from.clone(), to.clone()],
+        signer_seeds,
     ).map_err(|e| {
         msg!("Reserve SOL failed: {:?}", e);
         error!(ErrorCode::SolReserveFailed)
@@ -911,85 +1290,1285 @@ fn process_reserve_sol<'info>(
     Ok(())
 }
 
-// Function process_pay_referrer with explicit lifetimes
-fn process_pay_referrer<'info>(
-    from: &AccountInfo<'info>,
-    to: &AccountInfo<'info>,
+// Function to deposit WSOL into the pool's vault B, `signer_seeds` signing for a program address `user`
+#[allow(clippy::too_many_arguments)]
+fn process_deposit_to_pool<'info>(
+    user: &AccountInfo<'info>,
+    user_source_token: &AccountInfo<'info>,
+    b_vault_lp: &AccountInfo<'info>,
+    b_vault: &AccountInfo<'info>,
+    b_token_vault: &AccountInfo<'info>,
+    b_vault_lp_mint: &AccountInfo<'info>,
+    vault_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
     amount: u64,
     signer_seeds: &[&[&[u8]]],
 ) -> Result<()> {
-    verify_wallet_is_system_account(to)?;
-    
-    let ix = solana_program::system_instruction::transfer(
-        &from.key(),
-        &to.key(),
-        amount
-    );
-    
-    let mut accounts = Vec::with_capacity(2);
-    accounts.push(from.clone());
-    accounts.push(to.clone());
-    
+    let deposit_accounts = [
+        b_vault.clone(),
+        b_token_vault.clone(),
+        b_vault_lp_mint.clone(),
+        user_source_token.clone(),
+        b_vault_lp.clone(),
+        user.clone(),
+        token_program.clone(),
+    ];
+
+    let mut deposit_data = Vec::with_capacity(24);
+    deposit_data.extend_from_slice(&[242, 35, 198, 137, 82, 225, 242, 182]); // Deposit sighash
+    deposit_data.extend_from_slice(&amount.to_le_bytes());
+    deposit_data.extend_from_slice(&0u64.to_le_bytes()); // minimum_lp_token_amount = 0
+
     solana_program::program::invoke_signed(
-        &ix,
-        &accounts,
+        &solana_program::instruction::Instruction {
+            program_id: vault_program.key(),
+            accounts: deposit_accounts.iter().enumerate().map(|(i, a)| {
+                if i == 5 {
+                    solana_program::instruction::AccountMeta::new_readonly(a.key(), true)
+                } else if i < 5 {
+                    solana_program::instruction::AccountMeta::new(a.key(), false)
+                } else {
+                    solana_program::instruction::AccountMeta::new_readonly(a.key(), false)
+                }
+            }).collect::<Vec<solana_program::instruction::AccountMeta>>(),
+            data: deposit_data,
+        },
+        &deposit_accounts,
         signer_seeds,
     ).map_err(|e| {
-        msg!("Pay referrer failed: {:?}", e);
-        error!(ErrorCode::ReferrerPaymentFailed)
+        msg!("Deposit to pool failed: {:?}", e);
+        error!(ErrorCode::DepositToPoolFailed)
     })?;
-    
-    msg!("Referrer paid: {}", amount);
+
+    msg!("Deposited {} to pool", amount);
     Ok(())
 }
 
-/// Process the direct referrer's matrix when a new user registers
-fn process_referrer_chain<'info>(
-   user_key: &Pubkey,
-   referrer: &mut Account<'_, UserAccount>,
-   next_chain_id: u32,
-) -> Result<(bool, Pubkey)> {
-   let slot_idx = referrer.chain.filled_slots as usize;
-   if slot_idx >= 3 {
-       return Ok((false, referrer.key())); 
-   }
+// Bound a Mint slot to 3x the previous mint, falling back to the previous amount above it.
+// The returned amount is minted and becomes the next `last_mint_amount`.
+pub fn limit_mint_amount(last_mint_amount: u64, proposed_mint_amount: u64) -> u64 {
+    // First mint establishes the base value
+    if last_mint_amount == 0 {
+        return proposed_mint_amount;
+    }
+
+    let current_limit = last_mint_amount.saturating_mul(3);
+    if proposed_mint_amount > current_limit {
+        msg!(
+            "Mint adjustment: {} exceeds limit of {} (3x last mint). Using previous value: {}",
+            proposed_mint_amount,
+            current_limit,
+            last_mint_amount
+        );
+        return last_mint_amount;
+    }
 
-   referrer.chain.slots[slot_idx] = Some(*user_key);
+    proposed_mint_amount
+}
 
-   emit!(SlotFilled {
-       slot_idx: slot_idx as u8,
-       chain_id: referrer.chain.id,
-       user: *user_key,
-       owner: referrer.key(),
-   });
+// Program DONUT vault: the token vault authority PDA's associated token account
+pub fn program_token_vault_address(token_mint: &Pubkey) -> Pubkey {
+    let (vault_authority, _) = Pubkey::find_program_address(&[b"token_vault_authority"], &crate::ID);
+    associated_token::get_associated_token_address(&vault_authority, token_mint)
+}
+
+// Function to mint tokens for the program vault
+fn process_mint_tokens<'info>(
+    token_mint: &AccountInfo<'info>,
+    program_token_vault: &AccountInfo<'info>,
+    token_mint_authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+    mint_authority_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mint_instruction = spl_token::instruction::mint_to(
+        &token_program.key(),
+        &token_mint.key(),
+        &program_token_vault.key(),
+        &token_mint_authority.key(),
+        &[],
+        amount
+    ).map_err(|_| error!(ErrorCode::TokenMintFailed))?;
 
-   referrer.chain.filled_slots += 1;
+    solana_program::program::invoke_signed(
+        &mint_instruction,
+        &[
+            token_mint.clone(),
+            program_token_vault.clone(),
+            token_mint_authority.clone(),
+            token_program.clone(),
+        ],
+        mint_authority_seeds,
+    ).map_err(|e| {
+        msg!("Mint failed: {:?}", e);
+        error!(ErrorCode::TokenMintFailed)
+    })?;
 
-   if referrer.chain.filled_slots == 3 {
-       referrer.chain.id = next_chain_id;
-       referrer.chain.slots = [None, None, None];
-       referrer.chain.filled_slots = 0;
+    msg!("Minted {} tokens to the program vault", amount);
+    Ok(())
+}
 
-       return Ok((true, referrer.key()));
-   }
+// Count a completed matrix for the airdrop and emit the event
+fn record_airdrop_matrix(airdrop_state: &mut AirdropState, user: &mut UserAccount) -> Result<()> {
+    if let Some(week_number) = airdrop_state.record_matrix_completion(user)? {
+        emit!(MatrixCompleted {
+            user: user.owner_wallet,
+            week_number,
+            total_matrices: user.completed_matrices_total,
+        });
+    }
+    Ok(())
+}
 
-   Ok((false, referrer.key()))
+// Close the running airdrop week if `now` is past it and emit the event
+fn advance_airdrop_week(airdrop_state: &mut AirdropState, now: i64) -> Result<()> {
+    if let Some(snapshot) = airdrop_state.advance_week(now)? {
+        emit!(WeekClosed {
+            week_number: snapshot.week_number,
+            total_matrices: snapshot.total_matrices,
+            donut_distributed: snapshot.donut_distributed,
+            donut_per_matrix: snapshot.donut_per_matrix,
+        });
+    }
+    Ok(())
 }
 
-// Accounts for initialize instruction
-#[derive(Accounts)]
-pub struct Initialize<'info> {
+// Accounts a registration needs to carry out any slot action
+struct SlotActionAccounts<'a, 'info> {
+    user: Pubkey,               // Registering wallet the events are reported for
+    payer: AccountInfo<'info>,  // Funds the actions: the registering wallet or the cascade vault
+    cascade_vault_bump: Option<u8>, // Set when the cascade vault is the payer
+    payer_wsol_account: AccountInfo<'info>,
+    wsol: WsolSession<SplWsolAccount<'info>>,
+    burn_account: AccountInfo<'info>,
+    burn_account_bump: u8,
+    venue: VenueAccounts<'info>,
+    swap_venue: SwapVenue,
+    token_mint: AccountInfo<'info>,
+    program_sol_vault: AccountInfo<'info>,
+    program_token_vault: AccountInfo<'info>,
+    token_mint_authority: AccountInfo<'info>,
+    token_mint_authority_bump: u8,
+    multisig_treasury: AccountInfo<'info>,
+    token_program: &'a Program<'info, Token>,
+    pause_flags: PauseFlags,
+    max_slippage_bps: u16,
+    last_mint_amount: u64,
+    current_time: i64,
+    holding_vault: AccountInfo<'info>,
+    burn_mode: BurnMode,
+    burn_vault: AccountInfo<'info>,
+    swap_failure_policy: SwapFailurePolicy,
+    pending_burn_vault: AccountInfo<'info>,
+    price_tracker: PriceTracker,
+    max_price_deviation_bps: u16,
+    terminal_sink_policy: TerminalSinkPolicy,
+    protocol_reserve: AccountInfo<'info>,
+}
+
+impl<'a, 'info> SlotActionAccounts<'a, 'info> {
+    fn dex(&self) -> Box<dyn DexAdapter<'info> + '_> {
+        dex::adapter(self.swap_venue, &self.venue, self.token_mint.key())
+    }
+
+    fn expected_donut_out(&self, amount: u64) -> Result<u64> {
+        self.dex().quote_donut_out(amount, self.current_time)
+    }
+
+    // Run `f` with the seeds signing for the payer, none when the wallet signs the transaction
+    fn with_payer_seeds<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        match self.cascade_vault_bump {
+            Some(bump) => f(&[&[b"cascade_vault".as_ref(), &[bump]]]),
+            None => f(&[]),
+        }
+    }
+
+    // Transfer `amount` lamports from the payer to `to`
+    fn transfer_from_payer(&self, to: &AccountInfo<'info>, amount: u64) -> std::result::Result<(), ProgramError> {
+        let ix = solana_program::system_instruction::transfer(&self.payer.key(), &to.key(), amount);
+        self.with_payer_seeds(|seeds| {
+            solana_program::program::invoke_signed(&ix, &[self.payer.clone(), to.clone()], seeds)
+        })
+    }
+
+    // Swap `amount` WSOL from the payer's WSOL account for DONUT and burn it
+    fn swap_and_burn(&self, amount: u64, expected_donut_out: u64) -> Result<()> {
+        self.pause_flags.ensure_not_paused(PauseFlag::SwapAndBurn)?;
+
+        // Calculate minimum DONUT expected, no lower than the TWAP value allows
+        let minimum_donut_out = slippage::bounded_minimum_amount_out(
+            expected_donut_out,
+            dex::fair_donut_out(self.dex().as_ref(), &self.price_tracker, amount, self.current_time),
+            self.max_slippage_bps,
+        );
+
+        self.with_payer_seeds(|seeds| {
+            process_swap_and_burn(
+                self.dex().as_ref(),
+                &self.payer,
+                &self.payer_wsol_account,
+                &self.burn_account,
+                self.burn_account_bump,
+                &self.token_program.to_account_info(),
+                &self.token_mint,
+                amount,
+                minimum_donut_out,
+                seeds,
+            )
+        })?;
+        Ok(())
+    }
+
+    // Wrap the deposit right before an action spends all of it as WSOL, so nothing is left to unwrap
+    fn wrap_deposit(&mut self, amount: u64) -> Result<()> {
+        self.wsol.ensure_wrapped(amount)
+    }
+
+    fn record_pool_price(&mut self) {
+        let dex = dex::adapter(self.swap_venue, &self.venue, self.token_mint.key());
+        record_pool_price(dex.as_ref(), &mut self.price_tracker, self.current_time);
+    }
+
+    // Swap and burn the deposit, or hold it while the pool price is off its EMA.
+    // In batched mode the deposit is queued for the buyback crank instead.
+    fn burn_deposit(&mut self, amount: u64) -> Result<()> {
+        let route = route_burn(
+            self.dex().as_ref(),
+            amount,
+            self.burn_mode,
+            self.swap_failure_policy,
+            &self.price_tracker,
+            self.max_price_deviation_bps,
+            self.current_time,
+        )?;
+        match route {
+            BurnRoute::Queue => self.queue_burn(amount),
+            BurnRoute::Escrow => self.escrow_burn(amount),
+            BurnRoute::Hold { pool_price, deviation_bps } => self.hold_deposit(amount, pool_price, deviation_bps),
+            BurnRoute::Swap { expected_donut_out } => {
+                self.wrap_deposit(amount)?;
+                self.swap_and_burn(amount, expected_donut_out)
+            }
+        }
+    }
+
+    // Hand a deposit no slot used to the terminal sink policy
+    fn sink_deposit(&mut self, amount: u64) -> Result<()> {
+        match self.terminal_sink_policy {
+            TerminalSinkPolicy::Burn => self.burn_deposit(amount)?,
+            TerminalSinkPolicy::Treasury => {
+                self.transfer_from_payer(&self.multisig_treasury, amount).map_err(|e| {
+                    msg!("Treasury payment failed: {:?}", e);
+                    error!(ErrorCode::TreasuryPaymentFailed)
+                })?;
+            }
+            TerminalSinkPolicy::Reserve => {
+                self.transfer_from_payer(&self.protocol_reserve, amount).map_err(|e| {
+                    msg!("Protocol reserve transfer failed: {:?}", e);
+                    error!(ErrorCode::TokenTransferFailed)
+                })?;
+            }
+        }
+
+        emit!(DepositSunk {
+            user: self.user,
+            amount,
+            policy: self.terminal_sink_policy,
+            timestamp: self.current_time,
+        });
+        msg!("Terminal sink: {:?} applied to {} lamports", self.terminal_sink_policy, amount);
+        Ok(())
+    }
+
+    // Send the deposit to the pending burn vault, swapped and burned later by `retry_pending_burn`
+    fn escrow_burn(&self, amount: u64) -> Result<()> {
+        self.transfer_from_payer(&self.pending_burn_vault, amount).map_err(|e| {
+            msg!("Pending burn vault transfer failed: {:?}", e);
+            error!(ErrorCode::TokenTransferFailed)
+        })?;
+
+        emit!(BurnDeferred {
+            user: self.user,
+            amount,
+            total_pending: self.pending_burn_vault.lamports(),
+            timestamp: self.current_time,
+        });
+        msg!("Escrowed {} lamports until the burn can be retried", amount);
+        Ok(())
+    }
+
+    // Send the deposit to the burn vault, swapped and burned later by `execute_buyback_burn`
+    fn queue_burn(&self, amount: u64) -> Result<()> {
+        self.transfer_from_payer(&self.burn_vault, amount).map_err(|e| {
+            msg!("Burn vault transfer failed: {:?}", e);
+            error!(ErrorCode::TokenTransferFailed)
+        })?;
+
+        msg!("Queued {} lamports for the buyback burn", amount);
+        Ok(())
+    }
+
+    // Send the deposit to the holding vault instead of swapping at a suspect price,
+    // swapped and burned later by `release_held_deposits`
+    fn hold_deposit(&self, amount: u64, pool_price: u128, deviation_bps: u64) -> Result<()> {
+        msg!(
+            "Pool price {} deviates {} bps from EMA {}, holding {} lamports",
+            pool_price, deviation_bps, self.price_tracker.ema_price, amount
+        );
+
+        self.transfer_from_payer(&self.holding_vault, amount).map_err(|e| {
+            msg!("Holding vault transfer failed: {:?}", e);
+            error!(ErrorCode::TokenTransferFailed)
+        })?;
+
+        emit!(DepositHeld {
+            user: self.user,
+            amount,
+            pool_price,
+            ema_price: self.price_tracker.ema_price,
+            deviation_bps,
+            timestamp: self.current_time,
+        });
+        Ok(())
+    }
+
+    // Carry out `action` for the owner of the filled slot.
+    // Returns true when the deposit was used, false when it moves on to the upline.
+    fn apply_slot_action(
+        &mut self,
+        action: SlotAction,
+        amount: u64,
+        owner: &mut UserAccount,
+    ) -> Result<bool> {
+        // Burn decides between WSOL and SOL once it has checked the pool price
+        if action != SlotAction::Burn && action.uses_wsol() {
+            self.wrap_deposit(amount)?;
+        }
+
+        match action {
+            SlotAction::Burn => {
+                self.burn_deposit(amount)?;
+                msg!("Slot action: burn of {} processed", amount);
+                Ok(true)
+            }
+            SlotAction::Reserve => {
+                self.reserve_sol(amount, owner)?;
+                Ok(true)
+            }
+            SlotAction::Mint => {
+                self.reserve_sol(amount, owner)?;
+
+                // Never mint more than the deposit is worth at the TWAP
+                let mut proposed_amount = self.expected_donut_out(amount)?;
+                if let Some(twap_amount) = self.price_tracker.donut_value(amount, self.current_time) {
+                    proposed_amount = proposed_amount.min(twap_amount);
+                }
+                let donut_amount = limit_mint_amount(self.last_mint_amount, proposed_amount);
+                process_mint_tokens(
+                    &self.token_mint,
+                    &self.program_token_vault,
+                    &self.token_mint_authority,
+                    &self.token_program.to_account_info(),
+                    donut_amount,
+                    &[&[
+                        b"token_mint_authority".as_ref(),
+                        &[self.token_mint_authority_bump]
+                    ]],
+                )?;
+                self.last_mint_amount = donut_amount;
+
+                owner.reserved_tokens = owner.reserved_tokens
+                    .checked_add(donut_amount)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                msg!("Slot action: reserved {} SOL and {} minted DONUT", amount, donut_amount);
+                Ok(true)
+            }
+            SlotAction::PayReserved => {
+                // Credited only; the owner claims it from the program SOL vault
+                if owner.reserved_sol > 0 {
+                    let released = owner.release_reserved_sol()?;
+                    msg!("Slot action: {} reserved SOL now claimable", released);
+                }
+                if owner.reserved_tokens > 0 {
+                    let released = owner.release_reserved_tokens()?;
+                    msg!("Slot action: {} reserved DONUT now claimable", released);
+                }
+                msg!("Slot action: released reserved SOL, passing {} to the upline", amount);
+                Ok(false)
+            }
+            SlotAction::PayTreasury => {
+                self.transfer_from_payer(&self.multisig_treasury, amount).map_err(|e| {
+                    msg!("Treasury payment failed: {:?}", e);
+                    error!(ErrorCode::TreasuryPaymentFailed)
+                })?;
+                msg!("Slot action: sent {} SOL to the treasury", amount);
+                Ok(true)
+            }
+            SlotAction::DepositToPool => {
+                self.with_payer_seeds(|seeds| {
+                    process_deposit_to_pool(
+                        &self.payer,
+                        &self.payer_wsol_account,
+                        &self.venue.b_vault_lp,
+                        &self.venue.b_vault,
+                        &self.venue.b_token_vault,
+                        &self.venue.b_vault_lp_mint,
+                        &self.venue.vault_program,
+                        &self.token_program.to_account_info(),
+                        amount,
+                        seeds,
+                    )
+                })?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn reserve_sol(&self, amount: u64, owner: &mut UserAccount) -> Result<()> {
+        self.with_payer_seeds(|seeds| process_reserve_sol(&self.payer, &self.program_sol_vault, amount, seeds))?;
+        owner.reserved_sol = owner.reserved_sol
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        msg!("Slot action: reserved {} SOL", amount);
+        Ok(())
+    }
+}
+
+// Fill the next slot of the upline at `upline_info` with `current_user` and apply its action to the deposit.
+// Returns whether the deposit was used and whether the upline's matrix completed.
+#[allow(clippy::too_many_arguments)]
+fn process_upline_slot<'info>(
+    slot_accounts: &mut SlotActionAccounts<'_, 'info>,
+    airdrop_state: &mut AirdropState,
+    next_chain_id: &mut u32,
+    slot_actions: &[SlotAction],
+    upline_info: &AccountInfo<'info>,
+    current_user: Pubkey,
+    deposit: u64,
+) -> Result<(bool, bool)> {
+    if !upline_info.owner.eq(&crate::ID) {
+        return Err(error!(ErrorCode::InvalidSlotOwner));
+    }
+
+    let mut upline_account_data;
+    {
+        let data = upline_info.try_borrow_data()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if data.len() <= 8 {
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+        UserAccount::check_layout(upline_info)?;
+
+        let mut account_slice = &data[8..];
+        upline_account_data = UserAccount::deserialize(&mut account_slice)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        if !upline_account_data.is_registered {
+            return Err(error!(ErrorCode::SlotNotRegistered));
+        }
+    }
+
+    force_memory_cleanup();
+
+    // The saved cascade of an earlier registration fills this matrix first
+    upline_account_data.check_cascade_lock(&slot_accounts.user)?;
+    upline_account_data.release_cascade_lock(&slot_accounts.user);
+
+    let upline_key = *upline_info.key;
+    let upline_chain_id = upline_account_data.chain.id;
+    let upline_action = matrix::slot_action(slot_actions, upline_account_data.chain.filled_slots as usize);
+    let (upline_slot_idx, chain_completed) = upline_account_data.chain.fill_next_slot(
+        current_user,
+        slot_actions.len(),
+        *next_chain_id,
+    )?;
+
+    emit!(SlotFilled {
+        slot_idx: upline_slot_idx as u8,
+        chain_id: upline_chain_id,
+        user: current_user,
+        owner: upline_key,
+    });
+
+    // Apply slot logic
+    msg!("Recursion: Found SLOT {} - {:?}", upline_slot_idx + 1, upline_action);
+    let deposit_used = slot_accounts.apply_slot_action(
+        upline_action,
+        deposit,
+        &mut upline_account_data,
+    )?;
+
+    if chain_completed {
+        *next_chain_id += 1;
+        record_airdrop_matrix(airdrop_state, &mut upline_account_data)?;
+    }
+
+    // Serializar as mudanças
+    {
+        let mut data = upline_info.try_borrow_mut_data()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let mut write_data = &mut data[8..];
+        upline_account_data.serialize(&mut write_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+    }
+
+    force_memory_cleanup();
+
+    Ok((deposit_used, chain_completed))
+}
+
+// Create the program-owned account `target` at the PDA signed by `seeds`, paid by the signer `payer`,
+// and return the lamports it cost. Lamports someone already sent to the address are topped up
+// instead of making `create_account` fail.
+fn create_program_account<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<u64> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let accounts = [payer.clone(), target.clone(), system_program.clone()];
+
+    let paid = if target.lamports() == 0 {
+        let ix = solana_program::system_instruction::create_account(payer.key, target.key, rent, space as u64, &crate::ID);
+        solana_program::program::invoke_signed(&ix, &accounts, &[seeds])?;
+        rent
+    } else {
+        let top_up = rent.saturating_sub(target.lamports());
+        if top_up > 0 {
+            let ix = solana_program::system_instruction::transfer(payer.key, target.key, top_up);
+            solana_program::program::invoke(&ix, &accounts)?;
+        }
+        let ix = solana_program::system_instruction::allocate(target.key, space as u64);
+        solana_program::program::invoke_signed(&ix, &accounts, &[seeds])?;
+        let ix = solana_program::system_instruction::assign(target.key, &crate::ID);
+        solana_program::program::invoke_signed(&ix, &accounts, &[seeds])?;
+        top_up
+    };
+    Ok(paid)
+}
+
+// Apply `update` to the registered user account at `upline_info` and write it back
+fn update_upline_account(upline_info: &AccountInfo, update: impl FnOnce(&mut UserAccount) -> Result<()>) -> Result<()> {
+    if !upline_info.owner.eq(&crate::ID) {
+        return Err(error!(ErrorCode::InvalidSlotOwner));
+    }
+    UserAccount::check_layout(upline_info)?;
+
+    let mut data = upline_info.try_borrow_mut_data()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut upline_account_data = UserAccount::deserialize(&mut &data[8..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if !upline_account_data.is_registered {
+        return Err(error!(ErrorCode::SlotNotRegistered));
+    }
+
+    update(&mut upline_account_data)?;
+
+    upline_account_data.serialize(&mut &mut data[8..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(())
+}
+
+// Walk `upline_accounts` in order until one uses the deposit or a matrix stays open.
+// `current_user` ends up as the user the next upline's slot would be filled with.
+fn run_cascade<'info>(
+    slot_accounts: &mut SlotActionAccounts<'_, 'info>,
+    airdrop_state: &mut AirdropState,
+    next_chain_id: &mut u32,
+    slot_actions: &[SlotAction],
+    upline_accounts: &[AccountInfo<'info>],
+    current_user: &mut Pubkey,
+    deposit: u64,
+) -> Result<CascadeProgress> {
+    for upline_info in upline_accounts {
+        let (deposit_used, chain_completed) = process_upline_slot(
+            slot_accounts,
+            airdrop_state,
+            next_chain_id,
+            slot_actions,
+            upline_info,
+            *current_user,
+            deposit,
+        )?;
+
+        if deposit_used {
+            return Ok(CascadeProgress::Used);
+        }
+        if !chain_completed {
+            return Ok(CascadeProgress::Stopped);
+        }
+        *current_user = *upline_info.key;
+    }
+    Ok(CascadeProgress::Forwarded)
+}
+
+/// Process the direct referrer's matrix when a new user registers
+fn process_referrer_chain(
+   user_key: &Pubkey,
+   referrer: &mut Account<'_, UserAccount>,
+   width: usize,
+   next_chain_id: u32,
+) -> Result<(bool, Pubkey)> {
+   let chain_id = referrer.chain.id;
+   let (slot_idx, chain_completed) = referrer.chain.fill_next_slot(*user_key, width, next_chain_id)?;
+
+   emit!(SlotFilled {
+       slot_idx: slot_idx as u8,
+       chain_id,
+       user: *user_key,
+       owner: referrer.key(),
+   });
+
+   Ok((chain_completed, referrer.key()))
+}
+
+// Accounts for initialize instruction
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ProgramState::SIZE
+    )]
+    pub state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ProtocolConfig::SIZE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AirdropState::SIZE,
+        seeds = [b"airdrop_state"],
+        bump
+    )]
+    pub airdrop_state: Account<'info, AirdropState>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PriceTracker::SIZE,
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Account<'info, PriceTracker>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + BuybackState::SIZE,
+        seeds = [b"buyback_state"],
+        bump
+    )]
+    pub buyback_state: Account<'info, BuybackState>,
+    // DONUT account the burn swaps pay into; it is its own authority so the program signs the burns
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"burn_account"],
+        bump,
+        token::mint = token_mint,
+        token::authority = burn_account
+    )]
+    pub burn_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: DONUT mint, verified against the protocol addresses
+    pub token_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for starting the airdrop weeks
+#[derive(Accounts)]
+pub struct StartAirdrop<'info> {
+    pub state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [b"airdrop_state"],
+        bump
+    )]
+    pub airdrop_state: Account<'info, AirdropState>,
+    pub owner: Signer<'info>,
+}
+
+// Accounts for claiming earned DONUT from the program token vault
+#[derive(Accounts)]
+pub struct ClaimDonut<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"airdrop_state"],
+        bump
+    )]
+    pub airdrop_state: Account<'info, AirdropState>,
+
+    pub user_wallet: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_account", user_wallet.key().as_ref()],
+        bump
+    )]
+    pub user: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user_wallet
+    )]
+    pub user_donut_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Program DONUT vault, verified against the vault authority's ATA
+    #[account(mut)]
+    pub program_token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Token vault authority PDA
+    #[account(
+        seeds = [b"token_vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Token mint, verified against the protocol config
+    pub token_mint: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Accounts for claiming the SOL credited by PayReserved slots
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+
+    #[account(mut)]
+    pub user_wallet: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_account", user_wallet.key().as_ref()],
+        bump
+    )]
+    pub user: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"program_sol_vault"],
+        bump
+    )]
+    pub program_sol_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for the permissionless price tracker crank
+#[derive(Accounts)]
+pub struct UpdatePriceTracker<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Account<'info, PriceTracker>,
+
+    // Swap venue accounts, verified against the protocol config
+    pub pool_accounts: PoolAccounts<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Accounts for the permissionless crank feeding a pool TWAP oracle from a SOL/stable pool
+#[derive(Accounts)]
+pub struct UpdatePoolOracle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PriceTracker::SIZE,
+        seeds = [b"pool_oracle", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_oracle: Box<Account<'info, PriceTracker>>,
+
+    /// CHECK: SOL/stable pool, owner and layout checked when reading its reserves
+    pub pool: UncheckedAccount<'info>,
+
+    /// CHECK: Vault A, verified against the pool
+    pub a_vault: UncheckedAccount<'info>,
+
+    /// CHECK: LP token account for vault A, verified against the pool
+    pub a_vault_lp: UncheckedAccount<'info>,
+
+    /// CHECK: LP token mint for vault A, verified against vault A
+    pub a_vault_lp_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Vault B, verified against the pool
+    pub b_vault: UncheckedAccount<'info>,
+
+    /// CHECK: LP token account for vault B, verified against the pool
+    pub b_vault_lp: UncheckedAccount<'info>,
+
+    /// CHECK: LP token mint for vault B, verified against vault B
+    pub b_vault_lp_mint: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Pool, vault and program accounts of the swap venue, in `VenueAccounts` order
+#[derive(Accounts)]
+pub struct PoolAccounts<'info> {
+    /// CHECK: Pool account, verified against the protocol config
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+
+    /// CHECK: Vault A (DONUT), verified against the protocol config
+    #[account(mut)]
+    pub a_vault: UncheckedAccount<'info>,
+
+    /// CHECK: LP token account for vault A, verified against the protocol config
+    #[account(mut)]
+    pub a_vault_lp: UncheckedAccount<'info>,
+
+    /// CHECK: LP token mint for vault A, verified against the protocol config
+    #[account(mut)]
+    pub a_vault_lp_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Token vault for token A, verified against the protocol config
+    #[account(mut)]
+    pub a_token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Vault B (SOL), verified against the protocol config
+    #[account(mut)]
+    pub b_vault: UncheckedAccount<'info>,
+
+    /// CHECK: LP token account for vault B, verified against the protocol config
+    #[account(mut)]
+    pub b_vault_lp: UncheckedAccount<'info>,
+
+    /// CHECK: LP token mint for vault B, verified against the protocol config
+    #[account(mut)]
+    pub b_vault_lp_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Token vault for token B, verified against the protocol config
+    #[account(mut)]
+    pub b_token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Protocol fee account for token B, verified against the protocol config
+    #[account(mut)]
+    pub protocol_token_fee: UncheckedAccount<'info>,
+
+    /// CHECK: Meteora vault program, verified against the protocol config
+    pub vault_program: UncheckedAccount<'info>,
+
+    /// CHECK: Swap venue program, verified against the protocol config
+    pub amm_program: UncheckedAccount<'info>,
+}
+
+impl<'info> PoolAccounts<'info> {
+    pub fn venue(&self, token_program: &AccountInfo<'info>) -> VenueAccounts<'info> {
+        VenueAccounts {
+            pool: self.pool.to_account_info(),
+            a_vault: self.a_vault.to_account_info(),
+            a_vault_lp: self.a_vault_lp.to_account_info(),
+            a_vault_lp_mint: self.a_vault_lp_mint.to_account_info(),
+            a_token_vault: self.a_token_vault.to_account_info(),
+            b_vault: self.b_vault.to_account_info(),
+            b_vault_lp: self.b_vault_lp.to_account_info(),
+            b_vault_lp_mint: self.b_vault_lp_mint.to_account_info(),
+            b_token_vault: self.b_token_vault.to_account_info(),
+            protocol_token_fee: self.protocol_token_fee.to_account_info(),
+            vault_program: self.vault_program.to_account_info(),
+            amm_program: self.amm_program.to_account_info(),
+            token_program: token_program.clone(),
+        }
+    }
+}
+
+// Accounts the vault burn cranks share; each crank adds the vault it drains and the vault's
+// WSOL account
+#[derive(Accounts)]
+pub struct VaultBurn<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Account<'info, PriceTracker>,
+
+    // Program DONUT account the swap pays into and the burn draws from
+    #[account(
+        mut,
+        seeds = [b"burn_account"],
+        bump
+    )]
+    pub burn_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: WSOL mint, verified against the protocol config
+    pub wsol_mint: UncheckedAccount<'info>,
+
+    /// CHECK: DONUT mint, verified against the protocol config
+    #[account(mut)]
+    pub token_mint: UncheckedAccount<'info>,
+
+    // Swap venue accounts, verified against the protocol config
+    pub pool_accounts: PoolAccounts<'info>,
+
+    // Pays for the vault token accounts the first time
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+// Accounts for the permissionless buyback-and-burn crank
+#[derive(Accounts)]
+pub struct ExecuteBuybackBurn<'info> {
+    pub burn: VaultBurn<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"buyback_state"],
+        bump
+    )]
+    pub buyback_state: Account<'info, BuybackState>,
+
+    #[account(
+        mut,
+        seeds = [b"burn_vault"],
+        bump
+    )]
+    pub burn_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = burn.payer,
+        associated_token::mint = burn.wsol_mint,
+        associated_token::authority = burn_vault
+    )]
+    pub burn_vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for retrying the burns escrowed in the pending burn vault
+#[derive(Accounts)]
+pub struct RetryPendingBurn<'info> {
+    pub burn: VaultBurn<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_burn_vault"],
+        bump
+    )]
+    pub pending_burn_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = burn.payer,
+        associated_token::mint = burn.wsol_mint,
+        associated_token::authority = pending_burn_vault
+    )]
+    pub pending_burn_vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for releasing the deposits held while the pool price was off its EMA
+#[derive(Accounts)]
+pub struct ReleaseHeldDeposits<'info> {
+    pub burn: VaultBurn<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"holding_vault"],
+        bump
+    )]
+    pub holding_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = burn.payer,
+        associated_token::mint = burn.wsol_mint,
+        associated_token::authority = holding_vault
+    )]
+    pub holding_vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for continuing a saved cascade; the upline PDAs it walks follow in remaining_accounts
+#[derive(Accounts)]
+pub struct ContinueCascade<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_cascade", pending_cascade.registrant.as_ref()],
+        bump
+    )]
+    pub pending_cascade: Box<Account<'info, PendingCascade>>,
+
+    // Gets the pending cascade's rent back when it completes
+    #[account(
+        mut,
+        address = pending_cascade.registrant
+    )]
+    pub registrant: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"cascade_vault"],
+        bump
+    )]
+    pub cascade_vault: SystemAccount<'info>,
+
+    // Wraps the deposit for the WSOL slot actions
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = cascade_vault
+    )]
+    pub cascade_vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"airdrop_state"],
+        bump
+    )]
+    pub airdrop_state: Box<Account<'info, AirdropState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Box<Account<'info, PriceTracker>>,
+
+    #[account(
+        mut,
+        seeds = [b"program_sol_vault"],
+        bump
+    )]
+    pub program_sol_vault: SystemAccount<'info>,
+
+    // Receives PayTreasury slots, must match the state treasury
+    #[account(mut)]
+    pub multisig_treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"holding_vault"],
+        bump
+    )]
+    pub holding_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"burn_vault"],
+        bump
+    )]
+    pub burn_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_burn_vault"],
+        bump
+    )]
+    pub pending_burn_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_reserve"],
+        bump
+    )]
+    pub protocol_reserve: SystemAccount<'info>,
+
+    // Program DONUT account the swap pays into and the burn draws from
+    #[account(
+        mut,
+        seeds = [b"burn_account"],
+        bump
+    )]
+    pub burn_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Program DONUT vault, verified against the vault authority's ATA
+    #[account(mut)]
+    pub program_token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Mint authority PDA
+    #[account(
+        seeds = [b"token_mint_authority"],
+        bump
+    )]
+    pub token_mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: WSOL mint, verified against the protocol config
+    pub wsol_mint: UncheckedAccount<'info>,
+
+    /// CHECK: DONUT mint, verified against the protocol config
+    #[account(mut)]
+    pub token_mint: UncheckedAccount<'info>,
+
+    // Swap venue accounts, verified against the protocol config
+    pub pool_accounts: PoolAccounts<'info>,
+
+    // Pays for the cascade vault token account the first time
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for reading the tracked prices
+#[derive(Accounts)]
+pub struct GetPrice<'info> {
+    #[account(
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Account<'info, PriceTracker>,
+}
+
+// Accounts for accepting a proposed ownership transfer
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+    pub new_owner: Signer<'info>,
+}
+
+// Accounts for queueing a governance change
+#[derive(Accounts)]
+pub struct QueueConfigChange<'info> {
+    pub state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PendingConfigChange::SIZE,
+        seeds = [b"pending_config_change"],
+        bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for cancelling a queued governance change
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    pub state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending_config_change"],
+        bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+// Accounts for executing a queued governance change
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    // Created by the first oracle change
     #[account(
-        init,
+        init_if_needed,
         payer = owner,
-        space = 8 + ProgramState::SIZE
+        space = 8 + OracleConfig::SIZE,
+        seeds = [b"oracle_config"],
+        bump
     )]
-    pub state: Account<'info, ProgramState>,
+    pub oracle_config: Account<'info, OracleConfig>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending_config_change"],
+        bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+    // Source of a ReserveWithdrawal
+    #[account(
+        mut,
+        seeds = [b"protocol_reserve"],
+        bump
+    )]
+    pub protocol_reserve: SystemAccount<'info>,
+    // Destination of a ReserveWithdrawal, checked against the state
+    #[account(mut)]
+    pub multisig_treasury: SystemAccount<'info>,
     #[account(mut)]
     pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+// Accounts for owner-only updates of the program state
+#[derive(Accounts)]
+pub struct UpdateState<'info> {
+    #[account(mut)]
+    pub state: Account<'info, ProgramState>,
+    pub owner: Signer<'info>,
+}
+
 // Accounts for registration without referrer with swap and burn
 #[derive(Accounts)]
 #[instruction(deposit_amount: u64)]
@@ -997,6 +2576,12 @@ pub struct RegisterWithoutReferrerDeposit<'info> {
     #[account(mut)]
     pub state: Account<'info, ProgramState>,
 
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
     
@@ -1012,18 +2597,22 @@ pub struct RegisterWithoutReferrerDeposit<'info> {
     )]
     pub user: Account<'info, UserAccount>,
 
-    /// CHECK: User token account for Wrapped SOL, verified in the instruction code
-    #[account(mut)]
-    pub user_source_token: UncheckedAccount<'info>,
-    
-    /// Account to receive DONUT tokens
+    // User's WSOL ATA, created unless the user already has one
     #[account(
         init_if_needed,
         payer = user_wallet,
-        associated_token::mint = token_mint,
+        associated_token::mint = wsol_mint,
         associated_token::authority = user_wallet
     )]
-    pub user_donut_account: Account<'info, TokenAccount>,
+    pub user_wsol_account: Account<'info, TokenAccount>,
+    
+    // Program DONUT account the swap pays into and the burn draws from
+    #[account(
+        mut,
+        seeds = [b"burn_account"],
+        bump
+    )]
+    pub burn_account: Account<'info, TokenAccount>,
     
     // WSOL mint
     /// CHECK: This is the fixed WSOL mint address
@@ -1054,6 +2643,69 @@ pub struct RegisterWithoutReferrerDeposit<'info> {
     /// CHECK: Vault program
     pub vault_program: UncheckedAccount<'info>,
 
+    // Accounts the slot actions are built with; only the terminal sink ones are used here
+    #[account(
+        mut,
+        seeds = [b"program_sol_vault"],
+        bump
+    )]
+    pub program_sol_vault: SystemAccount<'info>,
+
+    // Receives the deposit under the treasury terminal sink policy, must match the state treasury
+    #[account(mut)]
+    pub multisig_treasury: SystemAccount<'info>,
+
+    // Pool price history, updated here and checked by the burn
+    #[account(
+        mut,
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Account<'info, PriceTracker>,
+
+    // Receives the deposit while the pool price is off its EMA
+    #[account(
+        mut,
+        seeds = [b"holding_vault"],
+        bump
+    )]
+    pub holding_vault: SystemAccount<'info>,
+
+    // Collects the deposit for the buyback crank in batched mode
+    #[account(
+        mut,
+        seeds = [b"burn_vault"],
+        bump
+    )]
+    pub burn_vault: SystemAccount<'info>,
+
+    // Escrows the deposit when the pool cannot swap it under the escrow policy
+    #[account(
+        mut,
+        seeds = [b"pending_burn_vault"],
+        bump
+    )]
+    pub pending_burn_vault: SystemAccount<'info>,
+
+    // Holds the deposit under the reserve terminal sink policy
+    #[account(
+        mut,
+        seeds = [b"protocol_reserve"],
+        bump
+    )]
+    pub protocol_reserve: SystemAccount<'info>,
+
+    /// CHECK: Program DONUT vault, verified against the vault authority's ATA
+    #[account(mut)]
+    pub program_token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Mint authority PDA
+    #[account(
+        seeds = [b"token_mint_authority"],
+        bump
+    )]
+    pub token_mint_authority: UncheckedAccount<'info>,
+
     // TOKEN MINT
     /// CHECK: Token mint - needs to be mutable for burn
     #[account(mut)]
@@ -1080,15 +2732,24 @@ pub struct RegisterWithSolDeposit<'info> {
     #[account(mut)]
     pub state: Account<'info, ProgramState>,
 
+    #[account(
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"oracle_config"],
+        bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
     #[account(mut)]
     pub user_wallet: Signer<'info>,
 
     // Reference accounts
     #[account(mut)]
     pub referrer: Account<'info, UserAccount>,
-    
-    #[account(mut)]
-    pub referrer_wallet: SystemAccount<'info>,
 
     // User account
     #[account(
@@ -1100,23 +2761,22 @@ pub struct RegisterWithSolDeposit<'info> {
     )]
     pub user: Account<'info, UserAccount>,
 
-    // New WSOL ATA account
+    // User's WSOL ATA, created unless the user already has one
     #[account(
-        init,
+        init_if_needed,
         payer = user_wallet,
         associated_token::mint = wsol_mint,
         associated_token::authority = user_wallet
     )]
     pub user_wsol_account: Account<'info, TokenAccount>,
     
-    /// Account to receive DONUT tokens
+    // Program DONUT account the swap pays into and the burn draws from
     #[account(
-        init_if_needed,
-        payer = user_wallet,
-        associated_token::mint = token_mint,
-        associated_token::authority = user_wallet
+        mut,
+        seeds = [b"burn_account"],
+        bump
     )]
-    pub user_donut_account: Account<'info, TokenAccount>,
+    pub burn_account: Account<'info, TokenAccount>,
     
     // WSOL mint
     /// CHECK: This is the fixed WSOL mint address
@@ -1147,13 +2807,94 @@ pub struct RegisterWithSolDeposit<'info> {
     /// CHECK: Vault program - CRITICAL: Must be validated against hardcoded address
     pub vault_program: UncheckedAccount<'info>,
 
-    // Accounts for SOL reserve (Slot 2)
+    // Accounts for SOL reserve (Reserve slots)
     #[account(
         mut,
         seeds = [b"program_sol_vault"],
         bump
     )]
     pub program_sol_vault: SystemAccount<'info>,
+
+    // Receives PayTreasury slots, must match the state treasury
+    #[account(mut)]
+    pub multisig_treasury: SystemAccount<'info>,
+
+    // Airdrop weeks, counts completed matrices while the airdrop runs
+    #[account(
+        mut,
+        seeds = [b"airdrop_state"],
+        bump
+    )]
+    pub airdrop_state: Account<'info, AirdropState>,
+
+    // Pool price history, updated here and checked by burns
+    #[account(
+        mut,
+        seeds = [b"price_tracker"],
+        bump
+    )]
+    pub price_tracker: Account<'info, PriceTracker>,
+
+    // Receives deposits held back while the pool price is off its EMA
+    #[account(
+        mut,
+        seeds = [b"holding_vault"],
+        bump
+    )]
+    pub holding_vault: SystemAccount<'info>,
+
+    // Collects burn deposits for the buyback crank in batched mode
+    #[account(
+        mut,
+        seeds = [b"burn_vault"],
+        bump
+    )]
+    pub burn_vault: SystemAccount<'info>,
+
+    // Escrows burn deposits the pool cannot swap under the escrow policy
+    #[account(
+        mut,
+        seeds = [b"pending_burn_vault"],
+        bump
+    )]
+    pub pending_burn_vault: SystemAccount<'info>,
+
+    // Holds deposits no slot used under the reserve terminal sink policy
+    #[account(
+        mut,
+        seeds = [b"protocol_reserve"],
+        bump
+    )]
+    pub protocol_reserve: SystemAccount<'info>,
+
+    // Cascade of this registration when it is too deep for one transaction
+    /// CHECK: Only created when the cascade is saved, closed back to the registrant once it completes
+    #[account(
+        mut,
+        seeds = [b"pending_cascade", user_wallet.key().as_ref()],
+        bump
+    )]
+    pub pending_cascade: UncheckedAccount<'info>,
+
+    // Holds the deposit of a saved cascade until `continue_cascade` uses it
+    #[account(
+        mut,
+        seeds = [b"cascade_vault"],
+        bump
+    )]
+    pub cascade_vault: SystemAccount<'info>,
+
+    // Accounts for Mint slots
+    /// CHECK: Program DONUT vault, verified against the vault authority's ATA
+    #[account(mut)]
+    pub program_token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Mint authority PDA
+    #[account(
+        seeds = [b"token_mint_authority"],
+        bump
+    )]
+    pub token_mint_authority: UncheckedAccount<'info>,
     
     // TOKEN MINT
     /// CHECK: Token mint - needs to be mutable for burn
@@ -1179,7 +2920,7 @@ pub mod referral_system {
     use super::*;
 
     // Initialize program state
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    pub fn initialize(ctx: Context<Initialize>, config: ProtocolConfig) -> Result<()> {
 
         if ctx.accounts.owner.key() != admin_addresses::AUTHORIZED_INITIALIZER {
             return Err(error!(ErrorCode::NotAuthorized));
@@ -1191,29 +2932,352 @@ pub mod referral_system {
         state.next_upline_id = 1;
         state.next_chain_id = 1;
         state.is_locked = false;
+        state.stale_price_policy = StalePricePolicy::SecondaryMedian;
+        state.admin_sol_price = 0;
+        state.admin_price_expires_at = 0;
+        state.pending_owner = None;
+        state.ownership_transfer_eta = 0;
+        state.pause_flags = PauseFlags::default();
+        state.last_mint_amount = 0;
+
+        config.validate()?;
+
+        // The burn account is created for the configured DONUT mint
+        verify_address_strict(&ctx.accounts.token_mint.key(), &config.addresses.token_mint, ErrorCode::InvalidTokenMintAddress)?;
+
+        ctx.accounts.protocol_config.set_inner(config);
+
+        // Weekly airdrop stays off until the owner starts it
+        ctx.accounts.airdrop_state.active = false;
+
+        // Price history starts with the first registration or crank
+        ctx.accounts.price_tracker.set_inner(PriceTracker::default());
+
+        // Buyback totals start at zero
+        ctx.accounts.buyback_state.set_inner(BuybackState::default());
         
         Ok(())
     }
+
+    // Start the weekly airdrop to users completing matrices
+    pub fn start_airdrop(ctx: Context<StartAirdrop>) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        let airdrop_state = &mut ctx.accounts.airdrop_state;
+        if airdrop_state.active || !airdrop_state.closed_weeks.is_empty() {
+            return Err(error!(ErrorCode::AirdropAlreadyStarted));
+        }
+
+        let start_timestamp = Clock::get()?.unix_timestamp;
+        airdrop_state.start(start_timestamp);
+
+        emit!(AirdropStarted { start_timestamp });
+        msg!("Airdrop started: {} weeks", AIRDROP_WEEKS);
+        Ok(())
+    }
+
+    // Claim airdrop shares and DONUT released by Mint slots
+    pub fn claim_donut(ctx: Context<ClaimDonut>) -> Result<()> {
+        ctx.accounts.state.pause_flags.ensure_not_paused(PauseFlag::AirdropClaims)?;
+        UserAccount::check_layout(&ctx.accounts.user.to_account_info())?;
+
+        // Released on every exit path when the guard goes out of scope
+        let _reentrancy_guard = ReentrancyGuard::acquire(&ctx.accounts.state.to_account_info())?;
+
+        verify_address_strict(
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.protocol_config.addresses.token_mint,
+            ErrorCode::InvalidTokenMintAddress
+        )?;
+        verify_address_strict(
+            &ctx.accounts.program_token_vault.key(),
+            &program_token_vault_address(&ctx.accounts.token_mint.key()),
+            ErrorCode::InvalidProgramTokenVault
+        )?;
+
+        advance_airdrop_week(&mut ctx.accounts.airdrop_state, Clock::get()?.unix_timestamp)?;
+        ctx.accounts.airdrop_state.settle_user(&mut ctx.accounts.user)?;
+
+        let available = ctx.accounts.user.claimable_donut();
+        if available == 0 {
+            return Err(error!(ErrorCode::NothingToClaim));
+        }
+
+        let transfer_instruction = spl_token::instruction::transfer(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.program_token_vault.key(),
+            &ctx.accounts.user_donut_account.key(),
+            &ctx.accounts.vault_authority.key(),
+            &[],
+            available,
+        ).map_err(|_| error!(ErrorCode::TokenTransferFailed))?;
+
+        solana_program::program::invoke_signed(
+            &transfer_instruction,
+            &[
+                ctx.accounts.program_token_vault.to_account_info(),
+                ctx.accounts.user_donut_account.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            &[&[
+                b"token_vault_authority".as_ref(),
+                &[ctx.bumps.vault_authority]
+            ]],
+        ).map_err(|e| {
+            msg!("DONUT claim transfer failed: {:?}", e);
+            error!(ErrorCode::TokenTransferFailed)
+        })?;
+
+        let user = &mut ctx.accounts.user;
+        user.total_donut_claimed = user.total_donut_claimed
+            .checked_add(available)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(DonutClaimed {
+            user: ctx.accounts.user_wallet.key(),
+            amount: available,
+            total_earned: user.total_donut_earned,
+            total_claimed: user.total_donut_claimed,
+        });
+
+        msg!("Claimed {} DONUT", available);
+        Ok(())
+    }
+
+    // Pay out the SOL that PayReserved slots credited to the caller
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        ctx.accounts.state.pause_flags.ensure_not_paused(PauseFlag::ReservePayouts)?;
+        UserAccount::check_layout(&ctx.accounts.user.to_account_info())?;
+
+        // Released on every exit path when the guard goes out of scope
+        let _reentrancy_guard = ReentrancyGuard::acquire(&ctx.accounts.state.to_account_info())?;
+
+        let amount = ctx.accounts.user.take_claimable_sol()?;
+
+        let ix = solana_program::system_instruction::transfer(
+            &ctx.accounts.program_sol_vault.key(),
+            &ctx.accounts.user_wallet.key(),
+            amount
+        );
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.program_sol_vault.to_account_info(),
+                ctx.accounts.user_wallet.to_account_info(),
+            ],
+            &[&[
+                b"program_sol_vault".as_ref(),
+                &[ctx.bumps.program_sol_vault]
+            ]],
+        ).map_err(|e| {
+            msg!("SOL claim transfer failed: {:?}", e);
+            error!(ErrorCode::ReferrerPaymentFailed)
+        })?;
+
+        emit!(SolClaimed {
+            user: ctx.accounts.user_wallet.key(),
+            amount,
+        });
+
+        msg!("Claimed {} SOL", amount);
+        Ok(())
+    }
+
+    // Pause or resume a single operation
+    pub fn set_pause_flag(ctx: Context<UpdateState>, flag: PauseFlag, paused: bool) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        ctx.accounts.state.pause_flags.set(flag, paused);
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        match flag {
+            PauseFlag::Registration => emit!(RegistrationPauseUpdated { paused, timestamp }),
+            PauseFlag::SwapAndBurn => emit!(SwapAndBurnPauseUpdated { paused, timestamp }),
+            PauseFlag::ReservePayouts => emit!(ReservePayoutsPauseUpdated { paused, timestamp }),
+            PauseFlag::AirdropClaims => emit!(AirdropClaimsPauseUpdated { paused, timestamp }),
+        }
+        Ok(())
+    }
+
+    // Propose a new owner, who can accept once the governance delay has passed
+    pub fn propose_ownership_transfer(ctx: Context<UpdateState>, new_owner: Pubkey) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        let eta = governance::timelock_eta(Clock::get()?.unix_timestamp);
+
+        let state = &mut ctx.accounts.state;
+        state.pending_owner = Some(new_owner);
+        state.ownership_transfer_eta = eta;
+
+        emit!(OwnershipTransferProposed {
+            owner: state.owner,
+            pending_owner: new_owner,
+            eta,
+        });
+        Ok(())
+    }
+
+    // Withdraw a pending ownership transfer
+    pub fn cancel_ownership_transfer(ctx: Context<UpdateState>) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        let state = &mut ctx.accounts.state;
+        let pending_owner = state.pending_owner.take()
+            .ok_or(error!(ErrorCode::NoPendingOwnershipTransfer))?;
+        state.ownership_transfer_eta = 0;
+
+        emit!(OwnershipTransferCancelled {
+            owner: state.owner,
+            pending_owner,
+        });
+        Ok(())
+    }
+
+    // Accept a proposed ownership transfer (signed by the new owner)
+    pub fn accept_ownership_transfer(ctx: Context<AcceptOwnership>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let pending_owner = state.pending_owner
+            .ok_or(error!(ErrorCode::NoPendingOwnershipTransfer))?;
+
+        if ctx.accounts.new_owner.key() != pending_owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        governance::check_timelock(state.ownership_transfer_eta, Clock::get()?.unix_timestamp)?;
+
+        let previous_owner = state.owner;
+        state.owner = pending_owner;
+        state.pending_owner = None;
+        state.ownership_transfer_eta = 0;
+
+        emit!(OwnershipTransferred {
+            previous_owner,
+            new_owner: pending_owner,
+        });
+        Ok(())
+    }
+
+    // Queue a governance change, applied after the governance delay
+    pub fn queue_config_change(ctx: Context<QueueConfigChange>, change: ConfigChange) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        let eta = governance::timelock_eta(Clock::get()?.unix_timestamp);
+        change.validate(eta)?;
+
+        let pending = &mut ctx.accounts.pending_config_change;
+        pending.change = change.clone();
+        pending.eta = eta;
+
+        emit!(ConfigChangeQueued { change, eta });
+        Ok(())
+    }
+
+    // Drop the queued governance change
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        emit!(ConfigChangeCancelled {
+            eta: ctx.accounts.pending_config_change.eta,
+        });
+        Ok(())
+    }
+
+    // Apply the queued governance change once its timelock has elapsed
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+        if ctx.accounts.owner.key() != ctx.accounts.state.owner {
+            return Err(error!(ErrorCode::NotAuthorized));
+        }
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        let pending = &ctx.accounts.pending_config_change;
+        governance::check_timelock(pending.eta, current_timestamp)?;
+
+        pending.change.apply(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.protocol_config,
+            &mut ctx.accounts.oracle_config,
+            current_timestamp,
+        )?;
+
+        match &pending.change {
+            ConfigChange::AdminSolPrice { price, expires_at } => emit!(AdminPriceSet {
+                price: *price,
+                expires_at: *expires_at,
+            }),
+            ConfigChange::Oracles { sources } => msg!("Oracle config updated: {} sources", sources.len()),
+            ConfigChange::StalePricePolicy { policy } => msg!("Stale price policy set to {:?}", policy),
+            ConfigChange::Protocol { .. } => {}
+            ConfigChange::ReserveWithdrawal { amount } => {
+                let destination = ctx.accounts.state.multisig_treasury;
+                verify_address_strict(
+                    &ctx.accounts.multisig_treasury.key(),
+                    &destination,
+                    ErrorCode::InvalidTreasuryAccount
+                )?;
+
+                let protocol_reserve = ctx.accounts.protocol_reserve.to_account_info();
+                sink::check_reserve_withdrawal(*amount, protocol_reserve.lamports(), Rent::get()?.minimum_balance(0))?;
+
+                let ix = solana_program::system_instruction::transfer(&protocol_reserve.key(), &destination, *amount);
+                solana_program::program::invoke_signed(
+                    &ix,
+                    &[
+                        protocol_reserve,
+                        ctx.accounts.multisig_treasury.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                    &[&[b"protocol_reserve", &[ctx.bumps.protocol_reserve]]],
+                )?;
+
+                emit!(ReserveWithdrawn {
+                    amount: *amount,
+                    destination,
+                    timestamp: current_timestamp,
+                });
+            }
+        }
+
+        emit!(ConfigChangeExecuted {
+            eta: pending.eta,
+            timestamp: current_timestamp,
+        });
+        Ok(())
+    }
     
     // Register without referrer
     pub fn register_without_referrer<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, RegisterWithoutReferrerDeposit<'info>>, 
-        deposit_amount: u64
+        deposit_amount: u64,
+        max_slippage_bps: u16,
     ) -> Result<()> {
-        // PROTEÇÃO REENTRANCY
-        if ctx.accounts.state.is_locked {
-            return Err(error!(ErrorCode::ReentrancyLock));
-        }
-        ctx.accounts.state.is_locked = true;
+        ctx.accounts.state.pause_flags.ensure_not_paused(PauseFlag::Registration)?;
+        slippage::check_slippage_bps(max_slippage_bps, ctx.accounts.protocol_config.max_slippage_bps)?;
+        let pause_flags = ctx.accounts.state.pause_flags;
+
+        // Released on every exit path when the guard goes out of scope
+        let _reentrancy_guard = ReentrancyGuard::acquire(&ctx.accounts.state.to_account_info())?;
 
         // Verify if the caller is the multisig treasury
         if ctx.accounts.owner.key() != ctx.accounts.state.multisig_treasury {
-            ctx.accounts.state.is_locked = false;
             return Err(error!(ErrorCode::NotAuthorized));
         }
     
         // STRICT VERIFICATION OF ALL ADDRESSES
-        if let Err(e) = verify_all_fixed_addresses(
+        verify_all_fixed_addresses(
+            &ctx.accounts.protocol_config,
             &ctx.accounts.pool.key(),
             &ctx.accounts.b_vault.key(),
             &ctx.accounts.b_token_vault.key(),
@@ -1221,40 +3285,28 @@ pub mod referral_system {
             &ctx.accounts.b_vault_lp.key(),
             &ctx.accounts.token_mint.key(),
             &ctx.accounts.wsol_mint.key(),
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
+        )?;
 
         // CRITICAL: Validate vault program
-        if let Err(e) = verify_address_strict(
+        verify_address_strict(
             &ctx.accounts.vault_program.key(), 
-            &verified_addresses::METEORA_VAULT_PROGRAM, 
+            &ctx.accounts.protocol_config.addresses.meteora_vault_program, 
             ErrorCode::InvalidVaultProgram
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
+        )?;
         
         // Validate AMM program
-        if let Err(e) = verify_address_strict(
+        verify_address_strict(
             &ctx.accounts.amm_program.key(),
-            &verified_addresses::METEORA_AMM_PROGRAM,
+            &ctx.accounts.protocol_config.addresses.meteora_amm_program,
             ErrorCode::InvalidAmmProgram
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
+        )?;
         
         // Validate protocol fee account - using TOKEN_B_FEE since we're swapping WSOL
-        if let Err(e) = verify_address_strict(
+        verify_address_strict(
             &ctx.accounts.protocol_token_fee.key(),
-            &verified_addresses::PROTOCOL_TOKEN_B_FEE,
+            &ctx.accounts.protocol_config.addresses.protocol_token_b_fee,
             ErrorCode::InvalidProtocolFeeAccount
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
+        )?;
 
         // Use global upline ID
         let state = &mut ctx.accounts.state;
@@ -1276,941 +3328,788 @@ pub mod referral_system {
             depth: 1,
             upline: vec![],
         };
-        user.chain = ReferralChain {
-            id: chain_id,
-            slots: [None, None, None],
-            filled_slots: 0,
-        };
+        user.chain = ReferralChain::new(chain_id);
         
         // Initialize financial data
         user.reserved_sol = 0;
 
-        // Sync the WSOL account 
-        let sync_native_ix = spl_token::instruction::sync_native(
-            &spl_token::ID,
-            &ctx.accounts.user_source_token.key(),
-        ).map_err(|_| {
-            ctx.accounts.state.is_locked = false;
-            error!(ErrorCode::WrapSolFailed)
-        })?;
-        
-        let sync_accounts = [ctx.accounts.user_source_token.to_account_info()];
-        
-        solana_program::program::invoke(
-            &sync_native_ix,
-            &sync_accounts,
-        ).map_err(|_| {
-            ctx.accounts.state.is_locked = false;
-            error!(ErrorCode::WrapSolFailed)
-        })?;
-
-        // SWAP AND BURN
-        if ctx.remaining_accounts.len() < 4 {
-            ctx.accounts.state.is_locked = false;
-            return Err(error!(ErrorCode::MissingVaultAAccounts));
-        }
+        // SWAP AND BURN: remaining accounts are the pool and vault A, as in register_with_sol_deposit
+        validate_all_remaining_accounts(&ctx.accounts.protocol_config, ctx.remaining_accounts, POOL_AND_VAULT_A_ACCOUNTS_COUNT)?;
         
-        let a_vault = &ctx.remaining_accounts[0];
-        let a_vault_lp = &ctx.remaining_accounts[1];
-        let a_vault_lp_mint = &ctx.remaining_accounts[2];
-        let a_token_vault = &ctx.remaining_accounts[3];
+        let pool = &ctx.remaining_accounts[0];
+        let a_vault = &ctx.remaining_accounts[1];
+        let a_vault_lp = &ctx.remaining_accounts[2];
+        let a_vault_lp_mint = &ctx.remaining_accounts[3];
+        let a_token_vault = &ctx.remaining_accounts[4];
         
         // Verify vault A addresses
-        if let Err(e) = verify_pool_and_vault_a_addresses(
-            &ctx.accounts.pool.key(),
+        verify_pool_and_vault_a_addresses(
+            &ctx.accounts.protocol_config,
+            &pool.key(),
             &a_vault.key(),
             &a_vault_lp.key(),
             &a_vault_lp_mint.key()
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
+        )?;
         
-        if let Err(e) = verify_address_strict(&a_token_vault.key(), &verified_addresses::A_TOKEN_VAULT, ErrorCode::InvalidTokenAVaultAddress) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
+        verify_address_strict(&a_token_vault.key(), &ctx.accounts.protocol_config.addresses.a_token_vault, ErrorCode::InvalidTokenAVaultAddress)?;
+
+        verify_address_strict(
+            &ctx.accounts.multisig_treasury.key(),
+            &state.multisig_treasury,
+            ErrorCode::InvalidTreasuryAccount
+        )?;
+        verify_address_strict(
+            &ctx.accounts.program_token_vault.key(),
+            &program_token_vault_address(&ctx.accounts.token_mint.key()),
+            ErrorCode::InvalidProgramTokenVault
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut slot_accounts = SlotActionAccounts {
+            user: ctx.accounts.user_wallet.key(),
+            payer: ctx.accounts.user_wallet.to_account_info(),
+            cascade_vault_bump: None,
+            payer_wsol_account: ctx.accounts.user_wsol_account.to_account_info(),
+            wsol: WsolSession::open(SplWsolAccount {
+                wallet: ctx.accounts.user_wallet.to_account_info(),
+                wallet_seeds: Vec::new(),
+                account: ctx.accounts.user_wsol_account.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            })?,
+            burn_account: ctx.accounts.burn_account.to_account_info(),
+            burn_account_bump: ctx.bumps.burn_account,
+            venue: registration_venue!(ctx),
+            swap_venue: ctx.accounts.protocol_config.swap_venue,
+            token_mint: ctx.accounts.token_mint.to_account_info(),
+            program_sol_vault: ctx.accounts.program_sol_vault.to_account_info(),
+            program_token_vault: ctx.accounts.program_token_vault.to_account_info(),
+            token_mint_authority: ctx.accounts.token_mint_authority.to_account_info(),
+            token_mint_authority_bump: ctx.bumps.token_mint_authority,
+            multisig_treasury: ctx.accounts.multisig_treasury.to_account_info(),
+            token_program: &ctx.accounts.token_program,
+            pause_flags,
+            max_slippage_bps,
+            last_mint_amount: state.last_mint_amount,
+            current_time: now,
+            holding_vault: ctx.accounts.holding_vault.to_account_info(),
+            burn_mode: ctx.accounts.protocol_config.burn_mode,
+            burn_vault: ctx.accounts.burn_vault.to_account_info(),
+            swap_failure_policy: ctx.accounts.protocol_config.swap_failure_policy,
+            pending_burn_vault: ctx.accounts.pending_burn_vault.to_account_info(),
+            price_tracker: *ctx.accounts.price_tracker,
+            max_price_deviation_bps: ctx.accounts.protocol_config.max_price_deviation_bps,
+            terminal_sink_policy: ctx.accounts.protocol_config.terminal_sink_policy,
+            protocol_reserve: ctx.accounts.protocol_reserve.to_account_info(),
+        };
+
+        // Every lamport the wallet sends out from here on must be the deposit
+        let deposit_ledger = DepositLedger::open(deposit_amount, ctx.accounts.user_wallet.lamports());
+
+        slot_accounts.record_pool_price();
+
+        // A base user has no matrix to fill, so the deposit goes to the terminal sink
+        slot_accounts.sink_deposit(deposit_amount)?;
+
+        ctx.accounts.price_tracker.set_inner(slot_accounts.price_tracker);
+
+        // The session only wraps what the slots spend, so no deposit WSOL is left over
+        slot_accounts.wsol.settle()?;
+
+        // INVARIANT: the wallet spent the deposit, no more and no less
+        deposit_ledger.check(ctx.accounts.user_wallet.lamports())?;
+
+        Ok(())
+    }
+
+    // Permissionless crank recording the current pool price in the price tracker
+    pub fn update_price_tracker(ctx: Context<UpdatePriceTracker>) -> Result<()> {
+        let config = &ctx.accounts.protocol_config;
+        let venue = ctx.accounts.pool_accounts.venue(&ctx.accounts.token_program.to_account_info());
+        verify_venue_accounts(config, &venue)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let (donut_amount, sol_amount) = dex::adapter(config.swap_venue, &venue, config.addresses.token_mint).reserves(now)?;
+        let price = price_guard::pool_price(donut_amount, sol_amount)
+            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
+
+        let tracker = &mut ctx.accounts.price_tracker;
+        tracker.update(price, now);
+
+        msg!("Price tracker updated: spot {}, EMA {}", price, tracker.ema_price);
+        Ok(())
+    }
+
+    // Permissionless crank recording a SOL/stable pool price for a PoolTwap oracle source
+    pub fn update_pool_oracle(ctx: Context<UpdatePoolOracle>) -> Result<()> {
+        let config = &ctx.accounts.protocol_config;
+        let accounts = &ctx.accounts;
+        let pool = oracle::SolPoolAccounts {
+            pool: accounts.pool.as_ref(),
+            a_vault: accounts.a_vault.as_ref(),
+            b_vault: accounts.b_vault.as_ref(),
+            a_vault_lp: accounts.a_vault_lp.as_ref(),
+            b_vault_lp: accounts.b_vault_lp.as_ref(),
+            a_vault_lp_mint: accounts.a_vault_lp_mint.as_ref(),
+            b_vault_lp_mint: accounts.b_vault_lp_mint.as_ref(),
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let (sol_amount, quote_amount) = pool.reserves(
+            &config.addresses.meteora_amm_program,
+            &config.addresses.meteora_vault_program,
+            now,
+        )?;
+        let price = price_guard::pool_price(quote_amount, sol_amount)
+            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))?;
+
+        let tracker = &mut ctx.accounts.pool_oracle;
+        tracker.update(price, now);
+
+        msg!("Pool oracle updated: spot {}, EMA {}", price, tracker.ema_price);
+        Ok(())
+    }
+
+    // View of the tracked DONUT per SOL prices, returned as instruction return data
+    pub fn get_price(ctx: Context<GetPrice>) -> Result<PriceSnapshot> {
+        let tracker = &ctx.accounts.price_tracker;
+        if !tracker.is_initialized() {
+            return Err(error!(ErrorCode::PriceTrackerNotInitialized));
         }
-        
-        // Calculate minimum DONUT expected
-        let minimum_donut_out = match calculate_swap_amount_out(
-            &ctx.accounts.pool.to_account_info(),
-            a_vault,
-            &ctx.accounts.b_vault.to_account_info(),
-            a_vault_lp,
-            &ctx.accounts.b_vault_lp.to_account_info(),
-            a_vault_lp_mint,
-            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-            deposit_amount,
-        ) {
-            Ok(amount) => amount,
-            Err(e) => {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
+        Ok(tracker.snapshot(Clock::get()?.unix_timestamp))
+    }
+
+    // Permissionless crank swapping the SOL queued in the burn vault for DONUT and burning it
+    pub fn execute_buyback_burn(ctx: Context<ExecuteBuybackBurn>, max_slippage_bps: u16) -> Result<()> {
+        let vault = QueuedVault {
+            account: ctx.accounts.burn_vault.to_account_info(),
+            wsol_account: ctx.accounts.burn_vault_wsol.to_account_info(),
+            seeds: &[b"burn_vault", &[ctx.bumps.burn_vault]],
+            minimum_amount: buyback::MIN_BUYBACK_AMOUNT,
+            below_minimum: ErrorCode::NothingToBuyBack,
+        };
+        // Only swap the batch near the tracked price
+        let now = Clock::get()?.unix_timestamp;
+        let (amount, donut_burned) = burn_queued_vault(
+            &mut ctx.accounts.burn,
+            ctx.bumps.burn.burn_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &vault,
+            max_slippage_bps,
+            now,
+        )?;
+
+        let buyback_state = &mut ctx.accounts.buyback_state;
+        buyback_state.record(amount, donut_burned, now)?;
+
+        emit!(DonutBurned {
+            sol_amount: amount,
+            donut_burned,
+            total_sol_spent: buyback_state.total_sol_spent,
+            total_donut_burned: buyback_state.total_donut_burned,
+            executions: buyback_state.executions,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    // Permissionless retry of the burns escrowed while the pool could not swap them
+    pub fn retry_pending_burn(ctx: Context<RetryPendingBurn>, max_slippage_bps: u16) -> Result<()> {
+        let vault = QueuedVault {
+            account: ctx.accounts.pending_burn_vault.to_account_info(),
+            wsol_account: ctx.accounts.pending_burn_vault_wsol.to_account_info(),
+            seeds: &[b"pending_burn_vault", &[ctx.bumps.pending_burn_vault]],
+            minimum_amount: 1,
+            below_minimum: ErrorCode::NoPendingBurn,
         };
+        // Fails like the registration did while the pool is still unavailable
+        let now = Clock::get()?.unix_timestamp;
+        let (amount, donut_burned) = burn_queued_vault(
+            &mut ctx.accounts.burn,
+            ctx.bumps.burn.burn_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &vault,
+            max_slippage_bps,
+            now,
+        )?;
+
+        emit!(PendingBurnRetried {
+            sol_amount: amount,
+            donut_burned,
+            timestamp: now,
+        });
+        Ok(())
+    }
 
-        // Execute swap and burn
-        if let Err(e) = process_swap_and_burn(
-            &ctx.accounts.pool.to_account_info(),
-            &ctx.accounts.user_wallet.to_account_info(),
-            &ctx.accounts.user_source_token.to_account_info(),
-            &ctx.accounts.user_donut_account.to_account_info(),
-            a_vault,
-            &ctx.accounts.b_vault.to_account_info(),
-            a_token_vault,
-            &ctx.accounts.b_token_vault.to_account_info(),
-            a_vault_lp_mint,
-            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-            a_vault_lp,
-            &ctx.accounts.b_vault_lp.to_account_info(),
-            &ctx.accounts.protocol_token_fee.to_account_info(),
-            &ctx.accounts.vault_program.to_account_info(),
+    // Permissionless release of the deposits held while the pool price was off its EMA,
+    // swapped and burned once the price is back inside the guard band
+    pub fn release_held_deposits(ctx: Context<ReleaseHeldDeposits>, max_slippage_bps: u16) -> Result<()> {
+        let vault = QueuedVault {
+            account: ctx.accounts.holding_vault.to_account_info(),
+            wsol_account: ctx.accounts.holding_vault_wsol.to_account_info(),
+            seeds: &[b"holding_vault", &[ctx.bumps.holding_vault]],
+            minimum_amount: 1,
+            below_minimum: ErrorCode::NoHeldDeposits,
+        };
+        // Refused like the registration was while the price is still outside the band
+        let now = Clock::get()?.unix_timestamp;
+        let (amount, donut_burned) = burn_queued_vault(
+            &mut ctx.accounts.burn,
+            ctx.bumps.burn.burn_account,
             &ctx.accounts.token_program.to_account_info(),
-            &ctx.accounts.amm_program.to_account_info(),
-            &ctx.accounts.token_mint.to_account_info(),
-            deposit_amount,
-            minimum_donut_out,
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
+            &vault,
+            max_slippage_bps,
+            now,
+        )?;
 
-        // PROTEÇÃO REENTRANCY - FIM
-        ctx.accounts.state.is_locked = false;
+        emit!(HeldDepositsReleased {
+            sol_amount: amount,
+            donut_burned,
+            timestamp: now,
+        });
         Ok(())
     }
 
-// Register with SOL deposit with swap and burn
-pub fn register_with_sol_deposit<'a, 'b, 'c, 'info>(
-    ctx: Context<'a, 'b, 'c, 'info, RegisterWithSolDeposit<'info>>, 
-    deposit_amount: u64
-) -> Result<()> {
-    // PROTEÇÃO REENTRANCY
-    if ctx.accounts.state.is_locked {
-        msg!("Transaction rejected: reentrancy protection active");
-        return Err(error!(ErrorCode::ReentrancyLock));
-    }
-    
-    // SETAR LOCK ANTES DE QUALQUER OPERAÇÃO EXTERNA
-    ctx.accounts.state.is_locked = true;
-    
-    // CRITICAL SECURITY VALIDATIONS
-    
-    // 1. VALIDATE METEORA VAULT PROGRAM
-    if let Err(e) = verify_address_strict(
-        &ctx.accounts.vault_program.key(), 
-        &verified_addresses::METEORA_VAULT_PROGRAM, 
-        ErrorCode::InvalidVaultProgram
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-    
-    // 2. Validate AMM program
-    if let Err(e) = verify_address_strict(
-        &ctx.accounts.amm_program.key(),
-        &verified_addresses::METEORA_AMM_PROGRAM,
-        ErrorCode::InvalidAmmProgram
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-    
-    // 3. Validate protocol fee account - using TOKEN_B_FEE since we're swapping WSOL
-    if let Err(e) = verify_address_strict(
-        &ctx.accounts.protocol_token_fee.key(),
-        &verified_addresses::PROTOCOL_TOKEN_B_FEE,
-        ErrorCode::InvalidProtocolFeeAccount
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-    
-    // 4. Check if referrer is registered
-    if !ctx.accounts.referrer.is_registered {
-        ctx.accounts.state.is_locked = false;
-        return Err(error!(ErrorCode::ReferrerNotRegistered));
-    }
-
-    // 5. DETERMINE ACTUAL SLOT FROM BLOCKCHAIN
-    let actual_slot_idx = ctx.accounts.referrer.chain.filled_slots as usize;
-    
-    // 6. DETECT BASE USER
-    let is_base_user = ctx.accounts.referrer.referrer.is_none() && 
-                       ctx.accounts.referrer.upline.upline.is_empty();
-    
-    msg!("Security Check - Slot: {}, Base User: {}, Referrer has {} uplines", 
-         actual_slot_idx, is_base_user, ctx.accounts.referrer.upline.upline.len());
-
-    // VALIDAÇÃO CRÍTICA SLOT 3
-    if actual_slot_idx == 2 {
-        if is_base_user {
-            msg!("SLOT 3 - Base user detected: will swap and burn");
-            
-            if ctx.remaining_accounts.len() < VAULT_A_ACCOUNTS_COUNT + 2 {
-                ctx.accounts.state.is_locked = false;
-                return Err(error!(ErrorCode::MissingVaultAAccounts));
+    // Permissionless crank walking the next uplines of a saved cascade with the deposit it holds.
+    // The upline PDAs are passed in remaining_accounts in the order `remaining_uplines` lists
+    // them; the ones past `next_uplines` are only unlocked when the cascade ends.
+    pub fn continue_cascade<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ContinueCascade<'info>>,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.state.pause_flags.ensure_not_paused(PauseFlag::Registration)?;
+        let max_slippage_bps = slippage::clamp_slippage_bps(max_slippage_bps, ctx.accounts.protocol_config.max_slippage_bps);
+        let pause_flags = ctx.accounts.state.pause_flags;
+
+        // Released on every exit path when the guard goes out of scope
+        let _reentrancy_guard = ReentrancyGuard::acquire(&ctx.accounts.state.to_account_info())?;
+
+        if !ctx.accounts.pending_cascade.active {
+            return Err(error!(ErrorCode::NoPendingCascade));
+        }
+
+        let config = &ctx.accounts.protocol_config;
+        verify_address_strict(&ctx.accounts.token_mint.key(), &config.addresses.token_mint, ErrorCode::InvalidTokenMintAddress)?;
+        verify_address_strict(&ctx.accounts.wsol_mint.key(), &config.addresses.wsol_mint, ErrorCode::InvalidTokenMintAddress)?;
+        verify_address_strict(&ctx.accounts.multisig_treasury.key(), &ctx.accounts.state.multisig_treasury, ErrorCode::InvalidTreasuryAccount)?;
+        verify_address_strict(
+            &ctx.accounts.program_token_vault.key(),
+            &program_token_vault_address(&ctx.accounts.token_mint.key()),
+            ErrorCode::InvalidProgramTokenVault
+        )?;
+
+        let venue = ctx.accounts.pool_accounts.venue(&ctx.accounts.token_program.to_account_info());
+        verify_venue_accounts(config, &venue)?;
+
+        let upline_accounts = ctx.remaining_accounts;
+        validate_upline_accounts(ctx.accounts.pending_cascade.remaining_uplines(), upline_accounts)?;
+        let (next_accounts, later_accounts) = upline_accounts.split_at(ctx.accounts.pending_cascade.next_uplines().len());
+
+        let now = Clock::get()?.unix_timestamp;
+        advance_airdrop_week(&mut ctx.accounts.airdrop_state, now)?;
+
+        let cascade_vault_bump = ctx.bumps.cascade_vault;
+        let registrant = ctx.accounts.pending_cascade.registrant;
+        let deposit = ctx.accounts.pending_cascade.deposit;
+        let slot_actions = ctx.accounts.protocol_config.slot_actions.clone();
+        let state = &mut ctx.accounts.state;
+
+        // The cascade vault pays for the actions in place of the registering wallet
+        let mut slot_accounts = SlotActionAccounts {
+            user: registrant,
+            payer: ctx.accounts.cascade_vault.to_account_info(),
+            cascade_vault_bump: Some(cascade_vault_bump),
+            payer_wsol_account: ctx.accounts.cascade_vault_wsol.to_account_info(),
+            wsol: WsolSession::open(SplWsolAccount {
+                wallet: ctx.accounts.cascade_vault.to_account_info(),
+                wallet_seeds: vec![b"cascade_vault".to_vec(), vec![cascade_vault_bump]],
+                account: ctx.accounts.cascade_vault_wsol.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            })?,
+            burn_account: ctx.accounts.burn_account.to_account_info(),
+            burn_account_bump: ctx.bumps.burn_account,
+            venue,
+            swap_venue: ctx.accounts.protocol_config.swap_venue,
+            token_mint: ctx.accounts.token_mint.to_account_info(),
+            program_sol_vault: ctx.accounts.program_sol_vault.to_account_info(),
+            program_token_vault: ctx.accounts.program_token_vault.to_account_info(),
+            token_mint_authority: ctx.accounts.token_mint_authority.to_account_info(),
+            token_mint_authority_bump: ctx.bumps.token_mint_authority,
+            multisig_treasury: ctx.accounts.multisig_treasury.to_account_info(),
+            token_program: &ctx.accounts.token_program,
+            pause_flags,
+            max_slippage_bps,
+            last_mint_amount: state.last_mint_amount,
+            current_time: now,
+            holding_vault: ctx.accounts.holding_vault.to_account_info(),
+            burn_mode: ctx.accounts.protocol_config.burn_mode,
+            burn_vault: ctx.accounts.burn_vault.to_account_info(),
+            swap_failure_policy: ctx.accounts.protocol_config.swap_failure_policy,
+            pending_burn_vault: ctx.accounts.pending_burn_vault.to_account_info(),
+            price_tracker: **ctx.accounts.price_tracker,
+            max_price_deviation_bps: ctx.accounts.protocol_config.max_price_deviation_bps,
+            terminal_sink_policy: ctx.accounts.protocol_config.terminal_sink_policy,
+            protocol_reserve: ctx.accounts.protocol_reserve.to_account_info(),
+        };
+
+        // Every lamport the cascade vault sends out must be the deposit
+        let mut deposit_ledger = DepositLedger::open(deposit, ctx.accounts.cascade_vault.lamports());
+
+        slot_accounts.record_pool_price();
+
+        let mut current_user = ctx.accounts.pending_cascade.current_user;
+        let progress = run_cascade(
+            &mut slot_accounts,
+            &mut ctx.accounts.airdrop_state,
+            &mut state.next_chain_id,
+            &slot_actions,
+            next_accounts,
+            &mut current_user,
+            deposit,
+        )?;
+
+        let pending_cascade = &mut ctx.accounts.pending_cascade;
+        pending_cascade.advance(next_accounts.len(), current_user)?;
+
+        let completed = match progress {
+            CascadeProgress::Used => true,
+            CascadeProgress::Forwarded if !pending_cascade.is_exhausted() => {
+                deposit_ledger.keep(deposit)?;
+                false
+            }
+            CascadeProgress::Stopped | CascadeProgress::Forwarded => {
+                msg!("Cascade ended without an upline using the deposit");
+                slot_accounts.sink_deposit(deposit)?;
+                true
             }
+        };
+
+        state.last_mint_amount = slot_accounts.last_mint_amount;
+        ctx.accounts.price_tracker.set_inner(slot_accounts.price_tracker);
+
+        // The session only wraps what the slots spend, so no deposit WSOL is left over
+        slot_accounts.wsol.settle()?;
+
+        // INVARIANT: the vault spent the deposit unless it holds it for the next step
+        deposit_ledger.check(ctx.accounts.cascade_vault.lamports())?;
+
+        if completed {
+            // Uplines the deposit never reached take new slots again
+            for upline_info in next_accounts.iter().chain(later_accounts) {
+                update_upline_account(upline_info, |upline| {
+                    upline.release_cascade_lock(&registrant);
+                    Ok(())
+                })?;
+            }
+            ctx.accounts.pending_cascade.close(ctx.accounts.registrant.to_account_info())?;
+            emit!(CascadeCompleted {
+                registrant,
+                deposit,
+                timestamp: now,
+            });
+            msg!("Cascade of {} completed", registrant);
         } else {
-            msg!("SLOT 3 - Normal user detected: validating ALL upline accounts");
-            
-            let base_accounts = VAULT_A_ACCOUNTS_COUNT + 2;
-            let referrer_uplines_count = ctx.accounts.referrer.upline.upline.len();
-            let required_upline_accounts = referrer_uplines_count * 2; // Agora só PDA e wallet
-            let total_required = base_accounts + required_upline_accounts;
-            
+            emit!(CascadeSaved {
+                registrant,
+                current_user: pending_cascade.current_user,
+                deposit,
+                next_upline_index: pending_cascade.next_upline_index,
+                remaining_uplines: pending_cascade.remaining_uplines().len() as u8,
+                timestamp: now,
+            });
+            msg!("Cascade continued: {} uplines left", pending_cascade.remaining_uplines().len());
+        }
+        Ok(())
+    }
+
+    // Register with SOL deposit with swap and burn
+    pub fn register_with_sol_deposit<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RegisterWithSolDeposit<'info>>, 
+        deposit_amount: u64,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.state.pause_flags.ensure_not_paused(PauseFlag::Registration)?;
+        slippage::check_slippage_bps(max_slippage_bps, ctx.accounts.protocol_config.max_slippage_bps)?;
+        let pause_flags = ctx.accounts.state.pause_flags;
+
+        // Released on every exit path when the guard goes out of scope
+        let _reentrancy_guard = ReentrancyGuard::acquire(&ctx.accounts.state.to_account_info())?;
+
+        // CRITICAL SECURITY VALIDATIONS
+
+        // 1. VALIDATE METEORA VAULT PROGRAM
+        verify_address_strict(
+            &ctx.accounts.vault_program.key(), 
+            &ctx.accounts.protocol_config.addresses.meteora_vault_program, 
+            ErrorCode::InvalidVaultProgram
+        )?;
+
+        // 2. Validate AMM program
+        verify_address_strict(
+            &ctx.accounts.amm_program.key(),
+            &ctx.accounts.protocol_config.addresses.meteora_amm_program,
+            ErrorCode::InvalidAmmProgram
+        )?;
+
+        // 3. Validate protocol fee account - using TOKEN_B_FEE since we're swapping WSOL
+        verify_address_strict(
+            &ctx.accounts.protocol_token_fee.key(),
+            &ctx.accounts.protocol_config.addresses.protocol_token_b_fee,
+            ErrorCode::InvalidProtocolFeeAccount
+        )?;
+
+        // 4. Check if referrer is registered
+        UserAccount::check_layout(&ctx.accounts.referrer.to_account_info())?;
+        if !ctx.accounts.referrer.is_registered {
+            return Err(error!(ErrorCode::ReferrerNotRegistered));
+        }
+
+        // A saved cascade of an earlier registration fills the referrer's matrix first
+        ctx.accounts.referrer.check_cascade_lock(&ctx.accounts.user_wallet.key())?;
+
+        // 5. Validate treasury account
+        verify_address_strict(
+            &ctx.accounts.multisig_treasury.key(),
+            &ctx.accounts.state.multisig_treasury,
+            ErrorCode::InvalidTreasuryAccount
+        )?;
+
+        // 6. DETERMINE ACTUAL SLOT AND ITS ACTION FROM BLOCKCHAIN
+        let slot_actions = ctx.accounts.protocol_config.slot_actions.clone();
+        let matrix_width = slot_actions.len();
+        let actual_slot_idx = ctx.accounts.referrer.chain.filled_slots as usize;
+        let slot_action = matrix::slot_action(&slot_actions, actual_slot_idx);
+
+        // The deposit moves up the upline when this slot completes the matrix without using it
+        let completes_matrix = actual_slot_idx + 1 >= matrix_width;
+        let forwards_deposit = completes_matrix && slot_action.forwards_deposit();
+
+        // 7. DETECT BASE USER
+        let is_base_user = ctx.accounts.referrer.referrer.is_none() && 
+                           ctx.accounts.referrer.upline.upline.is_empty();
+
+        msg!("Security Check - Slot: {}, Action: {:?}, Base User: {}, Referrer has {} uplines", 
+             actual_slot_idx, slot_action, is_base_user, ctx.accounts.referrer.upline.upline.len());
+
+        // 8. FIXED REMAINING ACCOUNTS: pool + vault A + configured oracle accounts
+        let base_accounts = POOL_AND_VAULT_A_ACCOUNTS_COUNT + ctx.accounts.oracle_config.accounts_len();
+
+        // Uplines the deposit can cascade through, nearest first
+        let max_upline_depth = ctx.accounts.protocol_config.max_upline_depth();
+        let cascade_uplines: Vec<Pubkey> = ctx.accounts.referrer.upline.upline.iter()
+            .rev()
+            .take(max_upline_depth)
+            .map(|entry| entry.pda)
+            .collect();
+        let inline_uplines = cascade::inline_steps(cascade_uplines.len());
+
+        // CRITICAL VALIDATION OF THE SLOT THAT COMPLETES THE MATRIX
+        // The uplines past the inline ones are only locked when the cascade is saved
+        if forwards_deposit && !is_base_user {
+            msg!("Completing slot - Normal user detected: validating {} upline accounts", cascade_uplines.len());
+
+            let total_required = base_accounts + cascade_uplines.len(); // Upline PDAs only
+
             msg!(
-                "SLOT 3 validation: Referrer has {} uplines, requiring {} accounts total (got {})",
-                referrer_uplines_count, 
+                "Completing slot validation: Referrer has {} uplines, requiring {} accounts total (got {})",
+                cascade_uplines.len(), 
                 total_required,
                 ctx.remaining_accounts.len()
             );
-            
+
             if ctx.remaining_accounts.len() != total_required {
                 msg!(
-                    "CRITICAL: SLOT 3 requires ALL {} uplines! Expected {} accounts, got {}", 
-                    referrer_uplines_count,
+                    "CRITICAL: Completing slot requires all {} uplines! Expected {} accounts, got {}", 
+                    cascade_uplines.len(),
                     total_required,
                     ctx.remaining_accounts.len()
                 );
-                ctx.accounts.state.is_locked = false;
                 return Err(error!(ErrorCode::Slot3RequiresUplineAccounts));
             }
-            
+
             let upline_accounts = &ctx.remaining_accounts[base_accounts..];
-            if let Err(e) = validate_upline_accounts(&ctx.accounts.referrer, upline_accounts) {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
-            
-            msg!("SLOT 3 validation passed: ALL {} uplines verified", referrer_uplines_count);
-        }
-    } else {
-        if ctx.remaining_accounts.len() < VAULT_A_ACCOUNTS_COUNT + 2 {
-            ctx.accounts.state.is_locked = false;
+            validate_upline_accounts(&cascade_uplines, upline_accounts)?;
+
+            msg!("Completing slot validation passed: {} uplines verified", cascade_uplines.len());
+        } else if ctx.remaining_accounts.len() < base_accounts {
             return Err(error!(ErrorCode::MissingVaultAAccounts));
         }
-    }
 
-    // VALIDAR TODAS AS CONTAS RESTANTES
-    if let Err(e) = validate_all_remaining_accounts(&ctx.remaining_accounts, VAULT_A_ACCOUNTS_COUNT + 2) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-
-    // Extrair contas já validadas
-    let pool = &ctx.remaining_accounts[0];
-    let a_vault = &ctx.remaining_accounts[1];
-    let a_vault_lp = &ctx.remaining_accounts[2];
-    let a_vault_lp_mint = &ctx.remaining_accounts[3];
-    let a_token_vault = &ctx.remaining_accounts[4];
-
-    // Verify Pool and Vault A addresses
-    if let Err(e) = verify_pool_and_vault_a_addresses(
-        &pool.key(),
-        &a_vault.key(),
-        &a_vault_lp.key(),
-        &a_vault_lp_mint.key()
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-    
-    if let Err(e) = verify_address_strict(&a_token_vault.key(), &verified_addresses::A_TOKEN_VAULT, ErrorCode::InvalidTokenAVaultAddress) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-
-    // Extract Chainlink accounts
-    let chainlink_feed = &ctx.remaining_accounts[5];
-    let chainlink_program = &ctx.remaining_accounts[6];
-
-    // Verify Chainlink addresses
-    if let Err(e) = verify_chainlink_addresses(
-        &chainlink_program.key(),
-        &chainlink_feed.key(),
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-
-    // VERIFY ALL FIXED ADDRESSES
-    if let Err(e) = verify_all_fixed_addresses(
-        &ctx.accounts.pool.key(),
-        &ctx.accounts.b_vault.key(),
-        &ctx.accounts.b_token_vault.key(),
-        &ctx.accounts.b_vault_lp_mint.key(),
-        &ctx.accounts.b_vault_lp.key(),
-        &ctx.accounts.token_mint.key(),
-        &ctx.accounts.wsol_mint.key(),
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-
-    // Get minimum deposit amount from Chainlink feed
-    let minimum_deposit = match calculate_minimum_sol_deposit(
-        chainlink_feed,
-        chainlink_program,
-    ) {
-        Ok(val) => val,
-        Err(e) => {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
+        // VALIDATE ALL REMAINING ACCOUNTS
+        validate_all_remaining_accounts(&ctx.accounts.protocol_config, ctx.remaining_accounts, base_accounts)?;
+
+        // Extract the validated accounts
+        let pool = &ctx.remaining_accounts[0];
+        let a_vault = &ctx.remaining_accounts[1];
+        let a_vault_lp = &ctx.remaining_accounts[2];
+        let a_vault_lp_mint = &ctx.remaining_accounts[3];
+        let a_token_vault = &ctx.remaining_accounts[4];
+
+        // Verify Pool and Vault A addresses
+        verify_pool_and_vault_a_addresses(
+            &ctx.accounts.protocol_config,
+            &pool.key(),
+            &a_vault.key(),
+            &a_vault_lp.key(),
+            &a_vault_lp_mint.key()
+        )?;
+
+        verify_address_strict(&a_token_vault.key(), &ctx.accounts.protocol_config.addresses.a_token_vault, ErrorCode::InvalidTokenAVaultAddress)?;
+
+        // Extract oracle accounts (verified against the oracle config when read)
+        let oracle_accounts = &ctx.remaining_accounts[POOL_AND_VAULT_A_ACCOUNTS_COUNT..base_accounts];
+
+        // VERIFY ALL FIXED ADDRESSES
+        verify_all_fixed_addresses(
+            &ctx.accounts.protocol_config,
+            &ctx.accounts.pool.key(),
+            &ctx.accounts.b_vault.key(),
+            &ctx.accounts.b_token_vault.key(),
+            &ctx.accounts.b_vault_lp_mint.key(),
+            &ctx.accounts.b_vault_lp.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.wsol_mint.key(),
+        )?;
+
+        verify_address_strict(
+            &ctx.accounts.program_token_vault.key(),
+            &program_token_vault_address(&ctx.accounts.token_mint.key()),
+            ErrorCode::InvalidProgramTokenVault
+        )?;
+
+        // Get minimum deposit amount from the configured oracles
+        let minimum_deposit = calculate_minimum_sol_deposit(
+            &ctx.accounts.state,
+            &ctx.accounts.protocol_config,
+            &ctx.accounts.oracle_config,
+            oracle_accounts,
+        )?;
+
+        // Verify deposit amount meets minimum requirement
+        if deposit_amount < minimum_deposit {
+            msg!("Deposit amount: {}, minimum required: {}", deposit_amount, minimum_deposit);
+            return Err(error!(ErrorCode::InsufficientDeposit));
         }
-    };
 
-    // Verify deposit amount meets minimum requirement
-    if deposit_amount < minimum_deposit {
-        msg!("Deposit amount: {}, minimum required: {}", deposit_amount, minimum_deposit);
-        ctx.accounts.state.is_locked = false;
-        return Err(error!(ErrorCode::InsufficientDeposit));
-    }
-    
-    // GESTÃO WSOL SIMPLIFICADA E SEGURA
-    let mut deposit_processed = false;
-    
-    // 1. WRAP SOL -> WSOL (apenas uma vez no início)
-    if let Err(e) = manage_wsol_operation(
-        &ctx.accounts.user_wallet.to_account_info(),
-        &ctx.accounts.user_wsol_account.to_account_info(),
-        &ctx.accounts.token_program,
-        "wrap",
-        Some(deposit_amount),
-    ) {
-        ctx.accounts.state.is_locked = false;
-        return Err(e);
-    }
-    
-    // 2. Create new UplineEntry for referrer
-    let referrer_entry = UplineEntry {
-        pda: ctx.accounts.referrer.key(),
-        wallet: ctx.accounts.referrer_wallet.key(),
-    };
-    
-    // 3. Create user's upline chain
-    let mut new_upline = Vec::new();
-    
-    if ctx.accounts.referrer.upline.upline.len() >= MAX_UPLINE_DEPTH {
-        new_upline.try_reserve(MAX_UPLINE_DEPTH).ok();
-        let start_idx = ctx.accounts.referrer.upline.upline.len() - (MAX_UPLINE_DEPTH - 1);
-        new_upline.extend_from_slice(&ctx.accounts.referrer.upline.upline[start_idx..]);
-    } else {
-        new_upline.try_reserve(ctx.accounts.referrer.upline.upline.len() + 1).ok();
-        new_upline.extend_from_slice(&ctx.accounts.referrer.upline.upline);
-    }
-    
-    new_upline.push(referrer_entry);
-    new_upline.shrink_to_fit();
+        // DEPOSIT TRACKING
+        let mut deposit_processed = false;
 
-    // 4. Setup user account
-    let state = &mut ctx.accounts.state;
-    let upline_id = state.next_upline_id;
-    let chain_id = state.next_chain_id;
+        // 1. WSOL the user already held is never spent; the WSOL session wraps only the deposit slots spend
 
-    state.next_chain_id += 1;
+        // 2. Create new UplineEntry for referrer
+        let referrer_entry = UplineEntry {
+            pda: ctx.accounts.referrer.key(),
+            wallet: ctx.accounts.referrer.owner_wallet,
+        };
 
-    let user = &mut ctx.accounts.user;
-    user.is_registered = true;
-    user.referrer = Some(ctx.accounts.referrer.key());
-    user.owner_wallet = ctx.accounts.user_wallet.key();
-    user.upline = ReferralUpline {
-        id: upline_id,
-        depth: ctx.accounts.referrer.upline.depth + 1,
-        upline: new_upline,
-    };
-    user.chain = ReferralChain {
-        id: chain_id,
-        slots: [None, None, None],
-        filled_slots: 0,
-    };
-    user.reserved_sol = 0;
+        // 3. Create user's upline chain
+        let mut new_upline = Vec::new();
 
-    // SLOT-BASED FINANCIAL LOGIC
-    let slot_idx = actual_slot_idx;
+        if ctx.accounts.referrer.upline.upline.len() >= max_upline_depth {
+            new_upline.try_reserve(max_upline_depth).ok();
+            let start_idx = ctx.accounts.referrer.upline.upline.len() - (max_upline_depth - 1);
+            new_upline.extend_from_slice(&ctx.accounts.referrer.upline.upline[start_idx..]);
+        } else {
+            new_upline.try_reserve(ctx.accounts.referrer.upline.upline.len() + 1).ok();
+            new_upline.extend_from_slice(&ctx.accounts.referrer.upline.upline);
+        }
 
-    if slot_idx == 0 {
-        // SLOT 1: Swap and burn (WSOL já preparado)
-        
-        // Calculate minimum DONUT expected
-        let minimum_donut_out = match calculate_swap_amount_out(
-            pool,
-            a_vault,
-            &ctx.accounts.b_vault.to_account_info(),
-            a_vault_lp,
-            &ctx.accounts.b_vault_lp.to_account_info(),
-            a_vault_lp_mint,
-            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-            deposit_amount,
-        ) {
-            Ok(amount) => amount,
-            Err(e) => {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
+        new_upline.push(referrer_entry);
+        new_upline.shrink_to_fit();
+
+        // 4. Setup user account
+        let state = &mut ctx.accounts.state;
+        let upline_id = state.next_upline_id;
+        let chain_id = state.next_chain_id;
+
+        state.next_chain_id += 1;
+
+        let user = &mut ctx.accounts.user;
+        user.is_registered = true;
+        user.referrer = Some(ctx.accounts.referrer.key());
+        user.owner_wallet = ctx.accounts.user_wallet.key();
+        user.upline = ReferralUpline {
+            id: upline_id,
+            depth: ctx.accounts.referrer.upline.depth + 1,
+            upline: new_upline,
         };
-        
-        if let Err(e) = process_swap_and_burn(
-            &ctx.accounts.pool.to_account_info(),
-            &ctx.accounts.user_wallet.to_account_info(),
-            &ctx.accounts.user_wsol_account.to_account_info(),
-            &ctx.accounts.user_donut_account.to_account_info(),
-            a_vault,
-            &ctx.accounts.b_vault.to_account_info(),
-            a_token_vault,
-            &ctx.accounts.b_token_vault.to_account_info(),
-            a_vault_lp_mint,
-            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-            a_vault_lp,
-            &ctx.accounts.b_vault_lp.to_account_info(),
-            &ctx.accounts.protocol_token_fee.to_account_info(),
-            &ctx.accounts.vault_program.to_account_info(),
-            &ctx.accounts.token_program.to_account_info(),
-            &ctx.accounts.amm_program.to_account_info(),
-            &ctx.accounts.token_mint.to_account_info(),
+        user.chain = ReferralChain::new(chain_id);
+        user.reserved_sol = 0;
+
+        // Close the running airdrop week before counting matrices in the new one
+        let now = Clock::get()?.unix_timestamp;
+        advance_airdrop_week(&mut ctx.accounts.airdrop_state, now)?;
+
+        // SLOT-BASED FINANCIAL LOGIC
+        let slot_idx = actual_slot_idx;
+        let mut slot_accounts = SlotActionAccounts {
+            user: ctx.accounts.user_wallet.key(),
+            payer: ctx.accounts.user_wallet.to_account_info(),
+            cascade_vault_bump: None,
+            payer_wsol_account: ctx.accounts.user_wsol_account.to_account_info(),
+            wsol: WsolSession::open(SplWsolAccount {
+                wallet: ctx.accounts.user_wallet.to_account_info(),
+                wallet_seeds: Vec::new(),
+                account: ctx.accounts.user_wsol_account.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            })?,
+            burn_account: ctx.accounts.burn_account.to_account_info(),
+            burn_account_bump: ctx.bumps.burn_account,
+            venue: registration_venue!(ctx),
+            swap_venue: ctx.accounts.protocol_config.swap_venue,
+            token_mint: ctx.accounts.token_mint.to_account_info(),
+            program_sol_vault: ctx.accounts.program_sol_vault.to_account_info(),
+            program_token_vault: ctx.accounts.program_token_vault.to_account_info(),
+            token_mint_authority: ctx.accounts.token_mint_authority.to_account_info(),
+            token_mint_authority_bump: ctx.bumps.token_mint_authority,
+            multisig_treasury: ctx.accounts.multisig_treasury.to_account_info(),
+            token_program: &ctx.accounts.token_program,
+            pause_flags,
+            max_slippage_bps,
+            last_mint_amount: state.last_mint_amount,
+            current_time: now,
+            holding_vault: ctx.accounts.holding_vault.to_account_info(),
+            burn_mode: ctx.accounts.protocol_config.burn_mode,
+            burn_vault: ctx.accounts.burn_vault.to_account_info(),
+            swap_failure_policy: ctx.accounts.protocol_config.swap_failure_policy,
+            pending_burn_vault: ctx.accounts.pending_burn_vault.to_account_info(),
+            price_tracker: *ctx.accounts.price_tracker,
+            max_price_deviation_bps: ctx.accounts.protocol_config.max_price_deviation_bps,
+            terminal_sink_policy: ctx.accounts.protocol_config.terminal_sink_policy,
+            protocol_reserve: ctx.accounts.protocol_reserve.to_account_info(),
+        };
+
+        // Every lamport the wallet sends out from here on must be the deposit
+        let mut deposit_ledger = DepositLedger::open(deposit_amount, ctx.accounts.user_wallet.lamports());
+
+        // Every registration moves the price history forward before any slot uses it
+        slot_accounts.record_pool_price();
+
+        if slot_accounts.apply_slot_action(
+            slot_action,
             deposit_amount,
-            minimum_donut_out,
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
-        deposit_processed = true;
-        msg!("SLOT 1: Swapped {} WSOL and burned DONUT tokens", deposit_amount);
-    } 
-    else if slot_idx == 1 {
-        // SLOT 2: Reserve SOL only (unwrap WSOL primeiro)
-        
-        // Unwrap WSOL para obter SOL de volta
-        if let Err(e) = manage_wsol_operation(
-            &ctx.accounts.user_wallet.to_account_info(),
-            &ctx.accounts.user_wsol_account.to_account_info(),
-            &ctx.accounts.token_program,
-            "unwrap",
-            None,
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
-        
-        // Reserve SOL for referrer
-        if let Err(e) = process_reserve_sol(
-            &ctx.accounts.user_wallet.to_account_info(),
-            &ctx.accounts.program_sol_vault.to_account_info(),
-            deposit_amount
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
-        
-        ctx.accounts.referrer.reserved_sol = deposit_amount;
-        deposit_processed = true;
-        msg!("SLOT 2: Reserved {} SOL", deposit_amount);
-    }
-    else if slot_idx == 2 {
-        // SLOT 3: Pay referrer (unwrap WSOL primeiro)
-        
-        // Unwrap WSOL para recuperar SOL
-        if let Err(e) = manage_wsol_operation(
-            &ctx.accounts.user_wallet.to_account_info(),
-            &ctx.accounts.user_wsol_account.to_account_info(),
-            &ctx.accounts.token_program,
-            "unwrap",
-            None,
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
-        }
-        
-        // Pay reserved SOL to referrer
-        if ctx.accounts.referrer.reserved_sol > 0 {
-            if let Err(e) = verify_wallet_is_system_account(&ctx.accounts.referrer_wallet.to_account_info()) {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
-            
-            if let Err(e) = process_pay_referrer(
-                &ctx.accounts.program_sol_vault.to_account_info(),
-                &ctx.accounts.referrer_wallet.to_account_info(),
-                ctx.accounts.referrer.reserved_sol,
-                &[&[
-                    b"program_sol_vault".as_ref(),
-                    &[ctx.bumps.program_sol_vault]
-                ]],
-            ) {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
-            
-            ctx.accounts.referrer.reserved_sol = 0;
+            &mut ctx.accounts.referrer,
+        )? {
+            deposit_processed = true;
         }
-        
-        msg!("SLOT 3: Paid referrer, preparing recursion with {} deposit", deposit_amount);
-    }
-    
-    // Process referrer's matrix
-    let (chain_completed, upline_pubkey) = match process_referrer_chain(
-        &ctx.accounts.user_wallet.key(),
-        &mut ctx.accounts.referrer,
-        state.next_chain_id,
-    ) {
-        Ok(result) => result,
-        Err(e) => {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
+        msg!("SLOT {}: {:?} applied to {} deposit", slot_idx + 1, slot_action, deposit_amount);
+
+        // Process referrer's matrix
+        let (chain_completed, upline_pubkey) = process_referrer_chain(
+            &ctx.accounts.user_wallet.key(),
+            &mut ctx.accounts.referrer,
+            matrix_width,
+            state.next_chain_id,
+        )?;
+
+        force_memory_cleanup();
+
+        if chain_completed {
+            state.next_chain_id += 1;
+            record_airdrop_matrix(&mut ctx.accounts.airdrop_state, &mut ctx.accounts.referrer)?;
         }
-    };
 
-    force_memory_cleanup();
-    
-    if chain_completed {
-        state.next_chain_id += 1;
-    }
+        // RECURSION PROCESSING
+        // A base user has no uplines, the deposit goes straight to the terminal sink
+        if chain_completed && !deposit_processed && !is_base_user && ctx.remaining_accounts.len() > base_accounts {
+            let mut current_user_pubkey = upline_pubkey;
+            let current_deposit = deposit_amount;
+
+            let upline_start_idx = base_accounts;
 
-    // RECURSION PROCESSING COM GESTÃO WSOL SEGURA
-    if chain_completed && slot_idx == 2 {
-        let mut current_user_pubkey = upline_pubkey;
-        let current_deposit = deposit_amount;
-
-        let upline_start_idx = VAULT_A_ACCOUNTS_COUNT + 2;
-
-        if is_base_user {
-            // BASE USER: No recursion, swap and burn
-            msg!("Base user matrix completed: swapping {} and burning", current_deposit);
-            
-            // Wrap SOL para WSOL para swap
-            if let Err(e) = manage_wsol_operation(
-                &ctx.accounts.user_wallet.to_account_info(),
-                &ctx.accounts.user_wsol_account.to_account_info(),
-                &ctx.accounts.token_program,
-                "wrap",
-                Some(current_deposit),
-            ) {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
-            
-            // Calculate minimum DONUT expected
-            let minimum_donut_out = match calculate_swap_amount_out(
-                pool,
-                a_vault,
-                &ctx.accounts.b_vault.to_account_info(),
-                a_vault_lp,
-                &ctx.accounts.b_vault_lp.to_account_info(),
-                a_vault_lp_mint,
-                &ctx.accounts.b_vault_lp_mint.to_account_info(),
-                current_deposit,
-            ) {
-                Ok(amount) => amount,
-                Err(e) => {
-                    ctx.accounts.state.is_locked = false;
-                    return Err(e);
-                }
-            };
-            
-            // Swap and burn
-            if let Err(e) = process_swap_and_burn(
-                &ctx.accounts.pool.to_account_info(),
-                &ctx.accounts.user_wallet.to_account_info(),
-                &ctx.accounts.user_wsol_account.to_account_info(),
-                &ctx.accounts.user_donut_account.to_account_info(),
-                a_vault,
-                &ctx.accounts.b_vault.to_account_info(),
-                a_token_vault,
-                &ctx.accounts.b_token_vault.to_account_info(),
-                a_vault_lp_mint,
-                &ctx.accounts.b_vault_lp_mint.to_account_info(),
-                a_vault_lp,
-                &ctx.accounts.b_vault_lp.to_account_info(),
-                &ctx.accounts.protocol_token_fee.to_account_info(),
-                &ctx.accounts.vault_program.to_account_info(),
-                &ctx.accounts.token_program.to_account_info(),
-                &ctx.accounts.amm_program.to_account_info(),
-                &ctx.accounts.token_mint.to_account_info(),
-                current_deposit,
-                minimum_donut_out,
-            ) {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
-            
-            deposit_processed = true;
-            msg!("Base user: {} swapped and burned", deposit_amount);
-            
-        } else if ctx.remaining_accounts.len() > upline_start_idx && current_deposit > 0 {
             // NORMAL USER: Process recursion
             let upline_accounts = &ctx.remaining_accounts[upline_start_idx..];
-            
-            if upline_accounts.len() % 2 != 0 {
-                msg!("ERROR: Upline accounts not in pairs: {}", upline_accounts.len());
-                ctx.accounts.state.is_locked = false;
-                return Err(error!(ErrorCode::MissingUplineAccount));
-            }
-            
-            let pair_count = upline_accounts.len() / 2;
-            msg!("Processing recursion with {} validated upline pairs", pair_count);
-            
-            const BATCH_SIZE: usize = 1;
-            let batch_count = (pair_count + BATCH_SIZE - 1) / BATCH_SIZE;
-            
-            for batch_idx in 0..batch_count {
-                let start_pair = batch_idx * BATCH_SIZE;
-                let end_pair = std::cmp::min(start_pair + BATCH_SIZE, pair_count);
-                
-                for pair_index in start_pair..end_pair {
-                    if pair_index >= MAX_UPLINE_DEPTH || current_deposit == 0 {
-                        break;
-                    }
+            let (inline_accounts, saved_accounts) = upline_accounts.split_at(inline_uplines);
+            msg!("Processing recursion with {} validated uplines", inline_accounts.len());
 
-                    let base_idx = pair_index * 2;
-                    let upline_info = &upline_accounts[base_idx];
-                    let upline_wallet = &upline_accounts[base_idx + 1];
-                    
-                    if upline_wallet.owner != &solana_program::system_program::ID {
-                        ctx.accounts.state.is_locked = false;
-                        return Err(error!(ErrorCode::PaymentWalletInvalid));
-                    }
-                    
-                    if !upline_info.owner.eq(&crate::ID) {
-                        ctx.accounts.state.is_locked = false;
-                        return Err(error!(ErrorCode::InvalidSlotOwner));
-                    }
+            let progress = run_cascade(
+                &mut slot_accounts,
+                &mut ctx.accounts.airdrop_state,
+                &mut state.next_chain_id,
+                &slot_actions,
+                inline_accounts,
+                &mut current_user_pubkey,
+                current_deposit,
+            )?;
 
-                    let mut upline_account_data;
-                    {
-                        let data = match upline_info.try_borrow_data() {
-                            Ok(data) => data,
-                            Err(_) => {
-                                ctx.accounts.state.is_locked = false;
-                                return Err(ProgramError::InvalidAccountData.into());
-                            }
-                        };
-                        if data.len() <= 8 {
-                            ctx.accounts.state.is_locked = false;
-                            return Err(ProgramError::InvalidAccountData.into());
-                        }
-
-                        let mut account_slice = &data[8..];
-                        upline_account_data = match UserAccount::deserialize(&mut account_slice) {
-                            Ok(data) => data,
-                            Err(_) => {
-                                ctx.accounts.state.is_locked = false;
-                                return Err(ProgramError::InvalidAccountData.into());
-                            }
-                        };
-
-                        if !upline_account_data.is_registered {
-                            ctx.accounts.state.is_locked = false;
-                            return Err(error!(ErrorCode::SlotNotRegistered));
-                        }
-                    }
+            match progress {
+                CascadeProgress::Used => deposit_processed = true,
+                CascadeProgress::Forwarded if !saved_accounts.is_empty() => {
+                    // Too deep for one transaction: park the deposit and let `continue_cascade` walk the rest
+                    let cascade_vault = ctx.accounts.cascade_vault.to_account_info();
+                    let rent_top_up = Rent::get()?.minimum_balance(0).saturating_sub(cascade_vault.lamports());
+                    let vault_amount = current_deposit
+                        .checked_add(rent_top_up)
+                        .ok_or(ErrorCode::ArithmeticOverflow)?;
+                    slot_accounts.transfer_from_payer(&cascade_vault, vault_amount).map_err(|e| {
+                        msg!("Cascade vault transfer failed: {:?}", e);
+                        error!(ErrorCode::TokenTransferFailed)
+                    })?;
+                    deposit_ledger.add_overhead(rent_top_up)?;
 
-                    force_memory_cleanup();
-
-                    let upline_slot_idx = upline_account_data.chain.filled_slots as usize;
-                    let upline_key = *upline_info.key;
-                    
-                    upline_account_data.chain.slots[upline_slot_idx] = Some(current_user_pubkey);
-                    
-                    emit!(SlotFilled {
-                        slot_idx: upline_slot_idx as u8,
-                        chain_id: upline_account_data.chain.id,
-                        user: current_user_pubkey,
-                        owner: upline_key,
-                    });
-                    
-                    upline_account_data.chain.filled_slots += 1;
-                    
-                    // Apply slot logic
-                    if upline_slot_idx == 0 {
-                        // FOUND SLOT 1: Swap and burn
-                        msg!("Recursion: Found SLOT 1 - preparing swap and burn");
-                        
-                        // Wrap SOL para WSOL para swap
-                        if let Err(e) = manage_wsol_operation(
-                            &ctx.accounts.user_wallet.to_account_info(),
-                            &ctx.accounts.user_wsol_account.to_account_info(),
-                            &ctx.accounts.token_program,
-                            "wrap",
-                            Some(current_deposit),
-                        ) {
-                            ctx.accounts.state.is_locked = false;
-                            return Err(e);
-                        }
-                        
-                        // Calculate minimum DONUT expected
-                        let minimum_donut_out = match calculate_swap_amount_out(
-                            pool,
-                            a_vault,
-                            &ctx.accounts.b_vault.to_account_info(),
-                            a_vault_lp,
-                            &ctx.accounts.b_vault_lp.to_account_info(),
-                            a_vault_lp_mint,
-                            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-                            current_deposit,
-                        ) {
-                            Ok(amount) => amount,
-                            Err(e) => {
-                                ctx.accounts.state.is_locked = false;
-                                return Err(e);
-                            }
-                        };
-                        
-                        if let Err(e) = process_swap_and_burn(
-                            &ctx.accounts.pool.to_account_info(),
-                            &ctx.accounts.user_wallet.to_account_info(),
-                            &ctx.accounts.user_wsol_account.to_account_info(),
-                            &ctx.accounts.user_donut_account.to_account_info(),
-                            a_vault,
-                            &ctx.accounts.b_vault.to_account_info(),
-                            a_token_vault,
-                            &ctx.accounts.b_token_vault.to_account_info(),
-                            a_vault_lp_mint,
-                            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-                            a_vault_lp,
-                            &ctx.accounts.b_vault_lp.to_account_info(),
-                            &ctx.accounts.protocol_token_fee.to_account_info(),
-                            &ctx.accounts.vault_program.to_account_info(),
-                            &ctx.accounts.token_program.to_account_info(),
-                            &ctx.accounts.amm_program.to_account_info(),
-                            &ctx.accounts.token_mint.to_account_info(),
-                            current_deposit,
-                            minimum_donut_out,
-                        ) {
-                            ctx.accounts.state.is_locked = false;
-                            return Err(e);
-                        }
-                        
-                        deposit_processed = true;
-                        msg!("Recursion: Found SLOT 1, swapped {} and burned", current_deposit);
-                    } 
-                    else if upline_slot_idx == 1 {
-                        // FOUND SLOT 2: Reserve SOL only
-                        msg!("Recursion: Found SLOT 2 - processing");
-                        
-                        if let Err(e) = process_reserve_sol(
-                            &ctx.accounts.user_wallet.to_account_info(),
-                            &ctx.accounts.program_sol_vault.to_account_info(),
-                            current_deposit
-                        ) {
-                            ctx.accounts.state.is_locked = false;
-                            return Err(e);
-                        }
-                        
-                        upline_account_data.reserved_sol = current_deposit;
-                        deposit_processed = true;
-                        msg!("Recursion: Found SLOT 2, reserved {} SOL", current_deposit);
-                    }
-                    else if upline_slot_idx == 2 {
-                        // SLOT 3: Pay upline and continue recursion
-                        if upline_account_data.reserved_sol > 0 {
-                            let reserved_sol = upline_account_data.reserved_sol;
-                            
-                            if upline_wallet.owner != &solana_program::system_program::ID {
-                                ctx.accounts.state.is_locked = false;
-                                return Err(error!(ErrorCode::PaymentWalletInvalid));
-                            }
-                            
-                            let ix = solana_program::system_instruction::transfer(
-                                &ctx.accounts.program_sol_vault.key(),
-                                &upline_wallet.key(),
-                                reserved_sol
-                            );
-                            
-                            let mut accounts = Vec::with_capacity(2);
-                            accounts.push(ctx.accounts.program_sol_vault.to_account_info());
-                            accounts.push(upline_wallet.clone());
-                            
-                            if let Err(_) = solana_program::program::invoke_signed(
-                                &ix,
-                                &accounts,
-                                &[&[
-                                    b"program_sol_vault".as_ref(),
-                                    &[ctx.bumps.program_sol_vault]
-                                ]],
-                            ) {
-                                ctx.accounts.state.is_locked = false;
-                                return Err(error!(ErrorCode::ReferrerPaymentFailed));
-                            }
-                            
-                            upline_account_data.reserved_sol = 0;
-                        }
-                        
-                        msg!("Recursion: Paid upline {}, continuing...", upline_key);
-                    }
-                    
-                    let chain_completed = upline_account_data.chain.filled_slots == 3;
-                    
-                    if chain_completed {
-                        let next_chain_id_value = state.next_chain_id;
-                        state.next_chain_id += 1;
-                        
-                        upline_account_data.chain.id = next_chain_id_value;
-                        upline_account_data.chain.slots = [None, None, None];
-                        upline_account_data.chain.filled_slots = 0;
-                        
-                        current_user_pubkey = upline_key;
-                    }
-                    
-                    // Serializar as mudanças
-                    {
-                        let mut data = match upline_info.try_borrow_mut_data() {
-                            Ok(data) => data,
-                            Err(_) => {
-                                ctx.accounts.state.is_locked = false;
-                                return Err(ProgramError::InvalidAccountData.into());
-                            }
-                        };
-                        let mut write_data = &mut data[8..];
-                        if let Err(_) = upline_account_data.serialize(&mut write_data) {
-                            ctx.accounts.state.is_locked = false;
-                            return Err(ProgramError::InvalidAccountData.into());
-                        }
+                    // Hold the uplines left so no later registration fills their slots first
+                    let registrant = ctx.accounts.user_wallet.key();
+                    for upline_info in saved_accounts {
+                        update_upline_account(upline_info, |upline| upline.lock_for_cascade(registrant))?;
                     }
 
-                    force_memory_cleanup();
-                    
-                    if !chain_completed || current_deposit == 0 {
-                        break;
-                    }
-                    
-                    if pair_index >= MAX_UPLINE_DEPTH - 1 {
-                        break;
-                    }
-                }
-                
-                if current_deposit == 0 {
-                    break;
-                }
-            }
+                    let mut pending_cascade = PendingCascade::default();
+                    pending_cascade.save(
+                        registrant,
+                        current_user_pubkey,
+                        current_deposit,
+                        cascade_uplines,
+                        inline_accounts.len(),
+                        now,
+                    )?;
+
+                    // The PDA only exists while a cascade is saved, so registrations that finish inline pay no rent for it
+                    let pending_cascade_info = ctx.accounts.pending_cascade.to_account_info();
+                    let pending_cascade_rent = create_program_account(
+                        &ctx.accounts.user_wallet.to_account_info(),
+                        &pending_cascade_info,
+                        &ctx.accounts.system_program.to_account_info(),
+                        8 + PendingCascade::SIZE,
+                        &[b"pending_cascade", registrant.as_ref(), &[ctx.bumps.pending_cascade]],
+                    )?;
+                    deposit_ledger.add_overhead(pending_cascade_rent)?;
+                    pending_cascade.try_serialize(&mut &mut pending_cascade_info.try_borrow_mut_data()?[..])?;
+
+                    emit!(CascadeSaved {
+                        registrant: pending_cascade.registrant,
+                        current_user: pending_cascade.current_user,
+                        deposit: pending_cascade.deposit,
+                        next_upline_index: pending_cascade.next_upline_index,
+                        remaining_uplines: pending_cascade.remaining_uplines().len() as u8,
+                        timestamp: now,
+                    });
 
-            // FALLBACK: Se a recursão processou todos os uplines sem encontrar slot 1 ou 2
-            if current_deposit > 0 && !deposit_processed {
-                msg!("Recursion fallback: No slot 1/2 found, swapping {} and burning", current_deposit);
-                
-                // Wrap SOL para WSOL para swap
-                if let Err(e) = manage_wsol_operation(
-                    &ctx.accounts.user_wallet.to_account_info(),
-                    &ctx.accounts.user_wsol_account.to_account_info(),
-                    &ctx.accounts.token_program,
-                    "wrap",
-                    Some(current_deposit),
-                ) {
-                    ctx.accounts.state.is_locked = false;
-                    return Err(e);
+                    deposit_processed = true;
+                    msg!("Cascade saved after {} uplines: {} held for continue_cascade", inline_accounts.len(), current_deposit);
                 }
-                
-                // Calculate minimum DONUT expected
-                let minimum_donut_out = match calculate_swap_amount_out(
-                    pool,
-                    a_vault,
-                    &ctx.accounts.b_vault.to_account_info(),
-                    a_vault_lp,
-                    &ctx.accounts.b_vault_lp.to_account_info(),
-                    a_vault_lp_mint,
-                    &ctx.accounts.b_vault_lp_mint.to_account_info(),
-                    current_deposit,
-                ) {
-                    Ok(amount) => amount,
-                    Err(e) => {
-                        ctx.accounts.state.is_locked = false;
-                        return Err(e);
-                    }
-                };
-                
-                if let Err(e) = process_swap_and_burn(
-                    &ctx.accounts.pool.to_account_info(),
-                    &ctx.accounts.user_wallet.to_account_info(),
-                    &ctx.accounts.user_wsol_account.to_account_info(),
-                    &ctx.accounts.user_donut_account.to_account_info(),
-                    a_vault,
-                    &ctx.accounts.b_vault.to_account_info(),
-                    a_token_vault,
-                    &ctx.accounts.b_token_vault.to_account_info(),
-                    a_vault_lp_mint,
-                    &ctx.accounts.b_vault_lp_mint.to_account_info(),
-                    a_vault_lp,
-                    &ctx.accounts.b_vault_lp.to_account_info(),
-                    &ctx.accounts.protocol_token_fee.to_account_info(),
-                    &ctx.accounts.vault_program.to_account_info(),
-                    &ctx.accounts.token_program.to_account_info(),
-                    &ctx.accounts.amm_program.to_account_info(),
-                    &ctx.accounts.token_mint.to_account_info(),
-                    current_deposit,
-                    minimum_donut_out,
-                ) {
-                    ctx.accounts.state.is_locked = false;
-                    return Err(e);
+                CascadeProgress::Stopped | CascadeProgress::Forwarded => {
+                    msg!("Recursion ended without an upline using the deposit");
                 }
-                
-                deposit_processed = true;
-                msg!("Recursion fallback: Swapped {} and burned", current_deposit);
             }
         }
-    }
 
-    // FINAL VALIDATION
-    if !deposit_processed {
-        msg!("CRITICAL ERROR: Deposit not processed - this should NEVER happen!");
-        ctx.accounts.state.is_locked = false;
-        return Err(error!(ErrorCode::DepositNotProcessed));
-    }
-    
-    // EMERGENCY FALLBACK: Handle any remaining WSOL balance
-    let final_wsol_balance = ctx.accounts.user_wsol_account.amount;
-    if final_wsol_balance > 0 {
-        msg!("EMERGENCY: Found remaining WSOL balance: {}, forcing swap and burn", final_wsol_balance);
-        
-        // Calculate minimum DONUT expected
-        let minimum_donut_out = match calculate_swap_amount_out(
-            pool,
-            a_vault,
-            &ctx.accounts.b_vault.to_account_info(),
-            a_vault_lp,
-            &ctx.accounts.b_vault_lp.to_account_info(),
-            a_vault_lp_mint,
-            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-            final_wsol_balance,
-        ) {
-            Ok(amount) => amount,
-            Err(e) => {
-                ctx.accounts.state.is_locked = false;
-                return Err(e);
-            }
-        };
-        
-        if let Err(e) = process_swap_and_burn(
-            &ctx.accounts.pool.to_account_info(),
-            &ctx.accounts.user_wallet.to_account_info(),
-            &ctx.accounts.user_wsol_account.to_account_info(),
-            &ctx.accounts.user_donut_account.to_account_info(),
-            a_vault,
-            &ctx.accounts.b_vault.to_account_info(),
-            a_token_vault,
-            &ctx.accounts.b_token_vault.to_account_info(),
-            a_vault_lp_mint,
-            &ctx.accounts.b_vault_lp_mint.to_account_info(),
-            a_vault_lp,
-            &ctx.accounts.b_vault_lp.to_account_info(),
-            &ctx.accounts.protocol_token_fee.to_account_info(),
-            &ctx.accounts.vault_program.to_account_info(),
-            &ctx.accounts.token_program.to_account_info(),
-            &ctx.accounts.amm_program.to_account_info(),
-            &ctx.accounts.token_mint.to_account_info(),
-            final_wsol_balance,
-            minimum_donut_out,
-        ) {
-            ctx.accounts.state.is_locked = false;
-            return Err(e);
+        // TERMINAL SINK: a base user's completed matrix, a cascade that ran out of uplines or
+        // stopped at an open matrix, and a slot that passed it on without completing its matrix
+        if !deposit_processed {
+            slot_accounts.sink_deposit(deposit_amount)?;
         }
-        
-        msg!("Emergency swap and burn completed: {}", final_wsol_balance);
-    }
 
-    msg!("Registration completed successfully: slot={}, base_user={}, deposit_processed=true", 
-         slot_idx + 1, is_base_user);
-    
-    // PROTEÇÃO REENTRANCY - REMOVER LOCK NO FINAL
-    ctx.accounts.state.is_locked = false;
-    Ok(())
-}
+        state.last_mint_amount = slot_accounts.last_mint_amount;
+        ctx.accounts.price_tracker.set_inner(slot_accounts.price_tracker);
+
+        // The session only wraps what the slots spend, so no deposit WSOL is left over
+        slot_accounts.wsol.settle()?;
+
+        // INVARIANT: the wallet spent the deposit, no more and no less
+        deposit_ledger.check(ctx.accounts.user_wallet.lamports())?;
+
+        msg!("Registration completed successfully: slot={}, base_user={}, deposit_processed=true", 
+             slot_idx + 1, is_base_user);
+
+        Ok(())
+    }
 }
\ No newline at end of file
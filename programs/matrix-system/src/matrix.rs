@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Maximum matrix width a user account has room for
+pub const MAX_MATRIX_SLOTS: usize = 4;
+
+// What happens to a deposit when it fills a given slot of a matrix
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotAction {
+    Burn,          // Swap the deposit for DONUT and burn it
+    Reserve,       // Hold the deposit in the program SOL vault for the matrix owner
+    PayReserved,   // Pay the owner's reserved SOL, release reserved DONUT and pass the deposit up the upline
+    PayTreasury,   // Send the deposit to the multisig treasury
+    DepositToPool, // Deposit the SOL into the pool's vault B
+    Mint,          // Reserve the deposit and mint DONUT worth it for the matrix owner
+}
+
+impl SlotAction {
+    // Whether the deposit moves on to the upline instead of being used by the slot
+    pub fn forwards_deposit(&self) -> bool {
+        matches!(self, SlotAction::PayReserved)
+    }
+
+    // Whether the action spends the deposit as WSOL (the others spend native SOL)
+    pub fn uses_wsol(&self) -> bool {
+        matches!(self, SlotAction::Burn | SlotAction::DepositToPool)
+    }
+}
+
+// Slot 1 swaps the deposit for DONUT and burns it
+pub const SWAP_AND_BURN_SLOT_ACTIONS: [SlotAction; 3] = [SlotAction::Burn, SlotAction::Reserve, SlotAction::PayReserved];
+
+// Slot 1 deposits into the pool, slot 2 mints DONUT that slot 3 releases with the SOL
+pub const POOL_DEPOSIT_AND_MINT_SLOT_ACTIONS: [SlotAction; 3] = [SlotAction::DepositToPool, SlotAction::Mint, SlotAction::PayReserved];
+
+pub const DEFAULT_SLOT_ACTIONS: [SlotAction; 3] = SWAP_AND_BURN_SLOT_ACTIONS;
+
+// Action for a slot index; a matrix left wider than the table by a config change uses the last entry
+pub fn slot_action(slot_actions: &[SlotAction], slot_idx: usize) -> SlotAction {
+    slot_actions[slot_idx.min(slot_actions.len() - 1)]
+}
+
+// Check a slot action table before it is written to the config
+pub fn validate_slot_actions(slot_actions: &[SlotAction]) -> Result<()> {
+    if slot_actions.is_empty() || slot_actions.len() > MAX_MATRIX_SLOTS {
+        msg!("Matrix width must be between 1 and {}: {}", MAX_MATRIX_SLOTS, slot_actions.len());
+        return Err(error!(ErrorCode::InvalidSlotActions));
+    }
+
+    let last_idx = slot_actions.len() - 1;
+    // The deposit can only move up once the matrix completes
+    if let Some(idx) = slot_actions[..last_idx].iter().position(|action| action.forwards_deposit()) {
+        msg!("PayReserved must be the last slot, found at slot {}", idx);
+        return Err(error!(ErrorCode::InvalidSlotActions));
+    }
+
+    // Reserved SOL and tokens must have a slot that pays them out
+    let reserves = slot_actions.iter().any(|action| matches!(action, SlotAction::Reserve | SlotAction::Mint));
+    if reserves && slot_actions[last_idx] != SlotAction::PayReserved {
+        msg!("Reserve and Mint slots need a PayReserved last slot");
+        return Err(error!(ErrorCode::InvalidSlotActions));
+    }
+
+    Ok(())
+}
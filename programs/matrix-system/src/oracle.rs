@@ -0,0 +1,479 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token;
+
+use crate::layout::PoolView;
+use crate::price_guard::PRICE_SCALE;
+use crate::price_tracker::{PriceTracker, OBSERVATION_INTERVAL};
+use crate::{chainlink, get_vault_token_amount, ErrorCode};
+
+// Maximum number of price sources in the oracle config (primary + fallbacks)
+pub const MAX_ORACLE_SOURCES: usize = 3;
+
+// Exponent used for median and admin prices (same precision as Chainlink)
+pub const PRICE_EXPO: i32 = -8;
+
+// Lamport decimals of SOL
+const SOL_DECIMALS: u32 = 9;
+
+// Shortest history a pool TWAP is trusted over, so one crank cannot set the price on its own
+pub const MIN_POOL_TWAP_WINDOW: i64 = OBSERVATION_INTERVAL;
+
+// Pyth price account layout
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_PRICE_ACCOUNT_MIN_SIZE: usize = 240;
+
+// Kind of price source backing an oracle entry
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleKind {
+    Chainlink,
+    Pyth,
+    PoolTwap, // TWAP of a SOL/stable pool kept in a `pool_oracle` price tracker
+}
+
+impl OracleKind {
+    // Number of remaining accounts consumed by this kind of source
+    pub fn accounts_len(&self) -> usize {
+        match self {
+            OracleKind::Chainlink => 2, // feed + store program
+            OracleKind::Pyth => 1,      // price account
+            OracleKind::PoolTwap => 1,  // price tracker
+        }
+    }
+}
+
+// A configured price source
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OracleSource {
+    pub kind: OracleKind,
+    pub program_id: Pubkey, // Expected owner of the price account (store program, Pyth program or this program)
+    pub account: Pubkey,    // Feed, price account or price tracker address
+    pub quote_decimals: u8, // Decimals of the USD stable token (PoolTwap only)
+}
+
+impl OracleSource {
+    pub const SIZE: usize = 1 + 32 + 32 + 1;
+}
+
+// Oracle configuration: the first source is the primary, the rest are fallbacks in order
+#[account]
+pub struct OracleConfig {
+    pub sources: Vec<OracleSource>,
+}
+
+impl OracleConfig {
+    pub const SIZE: usize = 4 + (MAX_ORACLE_SOURCES * OracleSource::SIZE);
+
+    // Total number of remaining accounts consumed by all configured sources
+    pub fn accounts_len(&self) -> usize {
+        self.sources.iter().map(|source| source.kind.accounts_len()).sum()
+    }
+
+    pub fn validate_sources(sources: &[OracleSource]) -> Result<()> {
+        if sources.is_empty() || sources.len() > MAX_ORACLE_SOURCES {
+            msg!("Oracle config must have between 1 and {} sources, got {}", MAX_ORACLE_SOURCES, sources.len());
+            return Err(error!(ErrorCode::InvalidOracleConfig));
+        }
+
+        for source in sources {
+            if source.kind == OracleKind::PoolTwap && source.quote_decimals > 18 {
+                msg!("Pool TWAP quote decimals out of range: {}", source.quote_decimals);
+                return Err(error!(ErrorCode::InvalidOracleConfig));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Price reported by an oracle: `price * 10^expo` USD per SOL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i128,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+// Common interface of all price backends
+pub trait PriceOracle {
+    fn kind(&self) -> OracleKind;
+
+    fn get_price(&self, current_timestamp: i64) -> Result<OraclePrice>;
+}
+
+// Chainlink OCR2 store feed
+pub struct ChainlinkOracle<'a, 'info> {
+    pub feed: &'a AccountInfo<'info>,
+    pub program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> PriceOracle for ChainlinkOracle<'a, 'info> {
+    fn kind(&self) -> OracleKind {
+        OracleKind::Chainlink
+    }
+
+    fn get_price(&self, _current_timestamp: i64) -> Result<OraclePrice> {
+        let round = chainlink::read_latest_round(self.feed, &self.program.key())?;
+
+        Ok(OraclePrice {
+            price: round.answer,
+            expo: -i32::from(round.decimals),
+            publish_time: round.timestamp,
+        })
+    }
+}
+
+// Pyth price account (aggregate price)
+pub struct PythOracle<'a, 'info> {
+    pub price_account: &'a AccountInfo<'info>,
+    pub program_id: Pubkey,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    i64::from_le_bytes(bytes)
+}
+
+// Decode the aggregate price from raw Pyth price account data
+pub fn decode_pyth_price(data: &[u8]) -> Result<OraclePrice> {
+    if data.len() < PYTH_PRICE_ACCOUNT_MIN_SIZE {
+        return Err(error!(ErrorCode::PriceFeedReadFailed));
+    }
+
+    if read_u32(data, 0) != PYTH_MAGIC || read_u32(data, 8) != PYTH_PRICE_ACCOUNT_TYPE {
+        msg!("Account is not a Pyth price account");
+        return Err(error!(ErrorCode::InvalidPriceFeed));
+    }
+
+    let status = read_u32(data, 224);
+    if status != PYTH_STATUS_TRADING {
+        msg!("Pyth price is not trading (status {})", status);
+        return Err(error!(ErrorCode::PriceFeedReadFailed));
+    }
+
+    let price = read_i64(data, 208);
+    if price <= 0 {
+        return Err(error!(ErrorCode::PriceFeedReadFailed));
+    }
+
+    Ok(OraclePrice {
+        price: i128::from(price),
+        expo: read_u32(data, 20) as i32,
+        publish_time: read_i64(data, 96),
+    })
+}
+
+impl<'a, 'info> PriceOracle for PythOracle<'a, 'info> {
+    fn kind(&self) -> OracleKind {
+        OracleKind::Pyth
+    }
+
+    fn get_price(&self, _current_timestamp: i64) -> Result<OraclePrice> {
+        if self.price_account.owner != &self.program_id {
+            msg!("Pyth price account owner mismatch: {}", self.price_account.owner);
+            return Err(error!(ErrorCode::InvalidPriceFeed));
+        }
+
+        let data = self.price_account.try_borrow_data()?;
+        decode_pyth_price(&data)
+    }
+}
+
+// TWAP of a SOL/stable pool, read from the price tracker the `update_pool_oracle` crank keeps
+pub struct PoolTwapOracle<'a, 'info> {
+    pub tracker: &'a AccountInfo<'info>,
+    pub program_id: Pubkey,
+    pub quote_decimals: u8,
+}
+
+// USD per SOL with PRICE_EXPO decimals for a tracked price of quote units per lamport,
+// scaled by PRICE_SCALE
+pub fn usd_price_from_pool_price(pool_price: u128, quote_decimals: u8) -> Option<i128> {
+    let numerator = pool_price.checked_mul(10u128.pow((-PRICE_EXPO) as u32 + SOL_DECIMALS))?;
+    let denominator = PRICE_SCALE.checked_mul(10u128.checked_pow(u32::from(quote_decimals))?)?;
+    i128::try_from(numerator / denominator).ok().filter(|price| *price > 0)
+}
+
+// Price of a pool TWAP oracle. It is published at the tracker's last update, so a crank
+// that stops running makes it stale like any other feed.
+pub fn pool_twap_price(tracker: &PriceTracker, quote_decimals: u8, current_timestamp: i64) -> Result<OraclePrice> {
+    let window = tracker.twap_window(current_timestamp);
+    if window < MIN_POOL_TWAP_WINDOW {
+        msg!("Pool TWAP window too short: {}s, need {}s", window, MIN_POOL_TWAP_WINDOW);
+        return Err(error!(ErrorCode::PriceFeedReadFailed));
+    }
+
+    let price = tracker
+        .twap(current_timestamp)
+        .and_then(|twap| usd_price_from_pool_price(twap, quote_decimals))
+        .ok_or(error!(ErrorCode::PriceFeedReadFailed))?;
+
+    Ok(OraclePrice {
+        price,
+        expo: PRICE_EXPO,
+        publish_time: tracker.last_update,
+    })
+}
+
+impl<'a, 'info> PriceOracle for PoolTwapOracle<'a, 'info> {
+    fn kind(&self) -> OracleKind {
+        OracleKind::PoolTwap
+    }
+
+    fn get_price(&self, current_timestamp: i64) -> Result<OraclePrice> {
+        if self.tracker.owner != &self.program_id {
+            msg!("Pool TWAP tracker owner mismatch: {}", self.tracker.owner);
+            return Err(error!(ErrorCode::InvalidPriceFeed));
+        }
+
+        let data = self.tracker.try_borrow_data()?;
+        let tracker = PriceTracker::try_deserialize(&mut &data[..]).map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+        pool_twap_price(&tracker, self.quote_decimals, current_timestamp)
+    }
+}
+
+// Accounts of a Meteora SOL/stable pool feeding a pool TWAP oracle
+pub struct SolPoolAccounts<'a, 'info> {
+    pub pool: &'a AccountInfo<'info>,
+    pub a_vault: &'a AccountInfo<'info>,
+    pub b_vault: &'a AccountInfo<'info>,
+    pub a_vault_lp: &'a AccountInfo<'info>,
+    pub b_vault_lp: &'a AccountInfo<'info>,
+    pub a_vault_lp_mint: &'a AccountInfo<'info>,
+    pub b_vault_lp_mint: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> SolPoolAccounts<'a, 'info> {
+    // SOL and quote token behind the pool, read through its vaults
+    pub fn reserves(&self, amm_program: &Pubkey, vault_program: &Pubkey, current_timestamp: i64) -> Result<(u64, u64)> {
+        crate::layout::check_owner(self.pool, amm_program)?;
+        let pool_data = self.pool.try_borrow_data()?;
+        let pool = PoolView::decode(&pool_data)?;
+
+        if !pool.enabled() {
+            msg!("Oracle pool is disabled");
+            return Err(error!(ErrorCode::PriceMeteoraReadFailed));
+        }
+
+        let expected = [
+            (self.a_vault.key(), pool.a_vault()),
+            (self.b_vault.key(), pool.b_vault()),
+            (self.a_vault_lp.key(), pool.a_vault_lp()),
+            (self.b_vault_lp.key(), pool.b_vault_lp()),
+        ];
+        for (key, pool_key) in expected {
+            if key != pool_key {
+                msg!("Oracle pool account mismatch: {}", key);
+                return Err(error!(ErrorCode::InvalidPriceFeed));
+            }
+        }
+
+        let token_a_amount = get_vault_token_amount(self.a_vault, self.a_vault_lp, self.a_vault_lp_mint, vault_program, current_timestamp)?;
+        let token_b_amount = get_vault_token_amount(self.b_vault, self.b_vault_lp, self.b_vault_lp_mint, vault_program, current_timestamp)?;
+
+        if pool.token_a_mint() == spl_token::native_mint::ID {
+            Ok((token_a_amount, token_b_amount))
+        } else if pool.token_b_mint() == spl_token::native_mint::ID {
+            Ok((token_b_amount, token_a_amount))
+        } else {
+            msg!("Oracle pool has no SOL side");
+            Err(error!(ErrorCode::InvalidPriceFeed))
+        }
+    }
+}
+
+// Build the backend for a source from its slice of remaining accounts
+pub fn load_oracle<'a, 'info>(
+    source: &OracleSource,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<Box<dyn PriceOracle + 'a>> {
+    if accounts.len() < source.kind.accounts_len() {
+        return Err(error!(ErrorCode::MissingOracleAccounts));
+    }
+
+    if accounts[0].key() != source.account {
+        msg!("Oracle account mismatch: provided={}, expected={}", accounts[0].key(), source.account);
+        return Err(error!(ErrorCode::InvalidPriceFeed));
+    }
+
+    match source.kind {
+        OracleKind::Chainlink => {
+            if accounts[1].key() != source.program_id {
+                return Err(error!(ErrorCode::InvalidChainlinkProgram));
+            }
+            Ok(Box::new(ChainlinkOracle {
+                feed: &accounts[0],
+                program: &accounts[1],
+            }))
+        }
+        OracleKind::Pyth => Ok(Box::new(PythOracle {
+            price_account: &accounts[0],
+            program_id: source.program_id,
+        })),
+        OracleKind::PoolTwap => Ok(Box::new(PoolTwapOracle {
+            tracker: &accounts[0],
+            program_id: source.program_id,
+            quote_decimals: source.quote_decimals,
+        })),
+    }
+}
+
+// What to do when the primary oracle is stale or unreadable
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StalePricePolicy {
+    Reject,           // Refuse the registration
+    SecondaryMedian,  // Use the median of the fresh secondary sources
+    AdminPrice,       // Use the admin-set price until it expires
+}
+
+// Admin-set SOL price used by `StalePricePolicy::AdminPrice`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdminPrice {
+    pub price: u64,       // USD per SOL with PRICE_EXPO decimals
+    pub expires_at: i64,
+}
+
+// How the price used for a registration was obtained
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceResolution {
+    Primary,
+    SecondaryMedian { sources: u8 },
+    AdminPrice,
+}
+
+impl OraclePrice {
+    // Express the price with another exponent (truncating when precision is dropped)
+    pub fn rescale(&self, expo: i32) -> Option<i128> {
+        if self.expo == expo {
+            Some(self.price)
+        } else if self.expo > expo {
+            let factor = 10i128.checked_pow((self.expo - expo) as u32)?;
+            self.price.checked_mul(factor)
+        } else {
+            let factor = 10i128.checked_pow((expo - self.expo) as u32)?;
+            self.price.checked_div(factor)
+        }
+    }
+}
+
+// Median of the given prices, expressed with PRICE_EXPO
+pub fn median_price(prices: &[OraclePrice]) -> Option<OraclePrice> {
+    let mut values = prices
+        .iter()
+        .map(|price| price.rescale(PRICE_EXPO))
+        .collect::<Option<Vec<i128>>>()?;
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+
+    let mid = values.len() / 2;
+    let price = if values.len() % 2 == 1 {
+        values[mid]
+    } else {
+        (values[mid - 1] + values[mid]) / 2
+    };
+
+    Some(OraclePrice {
+        price,
+        expo: PRICE_EXPO,
+        publish_time: prices.iter().map(|price| price.publish_time).min()?,
+    })
+}
+
+// Read every configured source in order. Unreadable sources yield `None`;
+// only missing accounts are an error.
+pub fn read_prices<'info>(
+    config: &OracleConfig,
+    accounts: &[AccountInfo<'info>],
+    current_timestamp: i64,
+) -> Result<Vec<Option<OraclePrice>>> {
+    let mut offset = 0;
+    let mut prices = Vec::with_capacity(config.sources.len());
+
+    for (idx, source) in config.sources.iter().enumerate() {
+        let len = source.kind.accounts_len();
+        if accounts.len() < offset + len {
+            return Err(error!(ErrorCode::MissingOracleAccounts));
+        }
+        let source_accounts = &accounts[offset..offset + len];
+        offset += len;
+
+        let reading = load_oracle(source, source_accounts)
+            .and_then(|oracle| oracle.get_price(current_timestamp));
+
+        match reading {
+            Ok(price) => prices.push(Some(price)),
+            Err(e) => {
+                msg!("Oracle source {} ({:?}) failed: {:?}", idx, source.kind, e);
+                prices.push(None);
+            }
+        }
+    }
+
+    Ok(prices)
+}
+
+// Pick the price for a registration: the primary source when fresh, otherwise
+// whatever the stale price policy allows.
+pub fn resolve_price(
+    prices: &[Option<OraclePrice>],
+    current_timestamp: i64,
+    max_age: i64,
+    policy: StalePricePolicy,
+    admin_price: AdminPrice,
+) -> Result<(OraclePrice, PriceResolution)> {
+    let is_fresh = |price: &OraclePrice| current_timestamp - price.publish_time <= max_age;
+
+    if let Some(Some(primary)) = prices.first() {
+        if is_fresh(primary) {
+            return Ok((*primary, PriceResolution::Primary));
+        }
+    }
+
+    match policy {
+        StalePricePolicy::Reject => {
+            msg!("Primary oracle stale or unavailable: rejecting");
+            Err(error!(ErrorCode::PriceFeedTooOld))
+        }
+        StalePricePolicy::SecondaryMedian => {
+            let secondaries: Vec<OraclePrice> = prices
+                .iter()
+                .skip(1)
+                .flatten()
+                .filter(|price| is_fresh(price))
+                .copied()
+                .collect();
+
+            let median = median_price(&secondaries).ok_or_else(|| {
+                msg!("Primary oracle stale and no fresh secondary oracle");
+                error!(ErrorCode::PriceFeedTooOld)
+            })?;
+
+            Ok((median, PriceResolution::SecondaryMedian { sources: secondaries.len() as u8 }))
+        }
+        StalePricePolicy::AdminPrice => {
+            if admin_price.price == 0 || current_timestamp >= admin_price.expires_at {
+                msg!("Primary oracle stale and admin price missing or expired");
+                return Err(error!(ErrorCode::PriceFeedTooOld));
+            }
+
+            Ok((
+                OraclePrice {
+                    price: i128::from(admin_price.price),
+                    expo: PRICE_EXPO,
+                    publish_time: current_timestamp,
+                },
+                PriceResolution::AdminPrice,
+            ))
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Operations the owner can pause independently
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseFlag {
+    Registration,
+    SwapAndBurn,
+    ReservePayouts,
+    AirdropClaims,
+}
+
+// Operator pause switches, independent of the reentrancy lock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PauseFlags {
+    pub registration: bool,
+    pub swap_and_burn: bool,
+    pub reserve_payouts: bool,
+    pub airdrop_claims: bool,
+}
+
+impl PauseFlags {
+    pub const SIZE: usize = 4;
+
+    pub fn is_paused(&self, flag: PauseFlag) -> bool {
+        match flag {
+            PauseFlag::Registration => self.registration,
+            PauseFlag::SwapAndBurn => self.swap_and_burn,
+            PauseFlag::ReservePayouts => self.reserve_payouts,
+            PauseFlag::AirdropClaims => self.airdrop_claims,
+        }
+    }
+
+    pub fn set(&mut self, flag: PauseFlag, paused: bool) {
+        match flag {
+            PauseFlag::Registration => self.registration = paused,
+            PauseFlag::SwapAndBurn => self.swap_and_burn = paused,
+            PauseFlag::ReservePayouts => self.reserve_payouts = paused,
+            PauseFlag::AirdropClaims => self.airdrop_claims = paused,
+        }
+    }
+
+    // Fail with the flag's own error code when the operation is paused
+    pub fn ensure_not_paused(&self, flag: PauseFlag) -> Result<()> {
+        if !self.is_paused(flag) {
+            return Ok(());
+        }
+
+        msg!("Operation paused: {:?}", flag);
+        Err(match flag {
+            PauseFlag::Registration => error!(ErrorCode::RegistrationPaused),
+            PauseFlag::SwapAndBurn => error!(ErrorCode::SwapAndBurnPaused),
+            PauseFlag::ReservePayouts => error!(ErrorCode::ReservePayoutsPaused),
+            PauseFlag::AirdropClaims => error!(ErrorCode::AirdropClaimsPaused),
+        })
+    }
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+// What a registration does when the burn swap cannot be carried out
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SwapFailurePolicy {
+    #[default]
+    Revert, // Fail the registration
+    Escrow, // Hold the deposit in the pending burn vault for `retry_pending_burn`
+}
+
+impl SwapFailurePolicy {
+    pub const SIZE: usize = 1;
+}
+
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::slippage::BPS_DENOMINATOR;
+use crate::ErrorCode;
+
+// Fixed-point scale of the DONUT per SOL pool price
+pub const PRICE_SCALE: u128 = 1_000_000_000;
+
+// Pool price in DONUT per SOL, scaled by PRICE_SCALE
+pub fn pool_price(donut_amount: u64, sol_amount: u64) -> Option<u128> {
+    if sol_amount == 0 {
+        return None;
+    }
+    (donut_amount as u128).checked_mul(PRICE_SCALE)?.checked_div(sol_amount as u128)
+}
+
+// Reserves holding the same constant-product liquidity as `donut_amount` and `sol_amount`
+// at `price` DONUT per SOL, scaled by PRICE_SCALE
+pub fn reserves_at_price(donut_amount: u64, sol_amount: u64, price: u128) -> Option<(u64, u64)> {
+    if price == 0 {
+        return None;
+    }
+    let invariant = (donut_amount as u128).checked_mul(sol_amount as u128)?;
+    let sol_squared = match invariant.checked_mul(PRICE_SCALE) {
+        Some(scaled) => scaled / price,
+        None => (invariant / price).checked_mul(PRICE_SCALE)?,
+    };
+    let sol_at_price = integer_sqrt(sol_squared);
+    if sol_at_price == 0 {
+        return None;
+    }
+    let donut_at_price = invariant / sol_at_price;
+    Some((u64::try_from(donut_at_price).ok()?, u64::try_from(sol_at_price).ok()?))
+}
+
+// Largest integer whose square is at most `value`
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut root = value;
+    let mut next = (root + 1) / 2;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+    root
+}
+
+// Distance between `price` and `reference` in bps of the reference
+pub fn deviation_bps(price: u128, reference: u128) -> Option<u64> {
+    if reference == 0 {
+        return None;
+    }
+    let deviation = price.abs_diff(reference).checked_mul(BPS_DENOMINATOR as u128)? / reference;
+    Some(u64::try_from(deviation).unwrap_or(u64::MAX))
+}
+
+// 0 turns the guard off
+pub fn validate_max_price_deviation_bps(max_price_deviation_bps: u16) -> Result<()> {
+    if max_price_deviation_bps as u64 > BPS_DENOMINATOR {
+        msg!("Max price deviation must be at most {} bps: {}", BPS_DENOMINATOR, max_price_deviation_bps);
+        return Err(error!(ErrorCode::InvalidProtocolConfig));
+    }
+    Ok(())
+}
+
+// Deviation of `price` from `reference` when it is beyond `max_deviation_bps`.
+// Nothing trips without a reference price or with the guard turned off.
+pub fn check_deviation(price: u128, reference: Option<u128>, max_deviation_bps: u16) -> Option<u64> {
+    if max_deviation_bps == 0 {
+        return None;
+    }
+    deviation_bps(price, reference?).filter(|deviation| *deviation > max_deviation_bps as u64)
+}
@@ -0,0 +1,155 @@
+use anchor_lang::prelude::*;
+
+use crate::price_guard::{self, PRICE_SCALE};
+
+// Seconds for the EMA to move all the way to a price that held that long
+pub const EMA_PERIOD: i64 = 60 * 60;
+
+// Minimum spacing between two TWAP observations
+pub const OBSERVATION_INTERVAL: i64 = 5 * 60;
+
+// Observations kept for the TWAP; the window is up to OBSERVATION_INTERVAL * PRICE_OBSERVATIONS
+pub const PRICE_OBSERVATIONS: usize = 12;
+
+// Cumulative price at a point in time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceObservation {
+    pub timestamp: i64,
+    pub cumulative_price: u128,
+}
+
+impl PriceObservation {
+    pub const SIZE: usize = 8 + 16;
+}
+
+// DONUT per SOL pool price history, stored in the `price_tracker` PDA.
+// Each interval is credited to the price that held during it, so a price set
+// in the current transaction only affects the averages from then on.
+#[account]
+#[derive(Default, Debug, Copy)]
+pub struct PriceTracker {
+    pub last_price: u128,       // Spot price at the last update, scaled by PRICE_SCALE
+    pub last_update: i64,
+    pub ema_price: u128,
+    pub cumulative_price: u128, // Sum of price * seconds since the first update (wrapping)
+    pub observations: [PriceObservation; PRICE_OBSERVATIONS],
+    pub observation_index: u8,  // Slot of the newest observation
+    pub observation_count: u8,
+}
+
+impl PriceTracker {
+    pub const SIZE: usize = 16 + 8 + 16 + 16 + (PRICE_OBSERVATIONS * PriceObservation::SIZE) + 1 + 1;
+
+    pub fn is_initialized(&self) -> bool {
+        self.observation_count > 0
+    }
+
+    // Record the spot `price` seen at `now`
+    pub fn update(&mut self, price: u128, now: i64) {
+        if !self.is_initialized() {
+            self.last_price = price;
+            self.last_update = now;
+            self.ema_price = price;
+            self.cumulative_price = 0;
+            self.observations[0] = PriceObservation { timestamp: now, cumulative_price: 0 };
+            self.observation_index = 0;
+            self.observation_count = 1;
+            return;
+        }
+
+        // A clock behind the last update only replaces the spot price
+        let elapsed = now.saturating_sub(self.last_update).max(0);
+        if elapsed > 0 {
+            self.cumulative_price = self.cumulative_price_at(now);
+
+            let weight = elapsed.min(EMA_PERIOD) as u128;
+            let remaining = (EMA_PERIOD as u128) - weight;
+            self.ema_price = (self.last_price.saturating_mul(weight)
+                .saturating_add(self.ema_price.saturating_mul(remaining)))
+                / EMA_PERIOD as u128;
+
+            self.last_update = now;
+
+            let newest = self.observations[self.observation_index as usize];
+            if now - newest.timestamp >= OBSERVATION_INTERVAL {
+                self.observation_index = ((self.observation_index as usize + 1) % PRICE_OBSERVATIONS) as u8;
+                self.observations[self.observation_index as usize] = PriceObservation {
+                    timestamp: now,
+                    cumulative_price: self.cumulative_price,
+                };
+                self.observation_count = (self.observation_count as usize + 1).min(PRICE_OBSERVATIONS) as u8;
+            }
+        }
+
+        self.last_price = price;
+    }
+
+    // Cumulative price extended from the last update to `now`
+    fn cumulative_price_at(&self, now: i64) -> u128 {
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u128;
+        self.cumulative_price.wrapping_add(self.last_price.wrapping_mul(elapsed))
+    }
+
+    fn oldest_observation(&self) -> PriceObservation {
+        let count = self.observation_count as usize;
+        let oldest = (self.observation_index as usize + PRICE_OBSERVATIONS + 1 - count) % PRICE_OBSERVATIONS;
+        self.observations[oldest]
+    }
+
+    // Seconds the TWAP at `now` averages over
+    pub fn twap_window(&self, now: i64) -> i64 {
+        if !self.is_initialized() {
+            return 0;
+        }
+        now.saturating_sub(self.oldest_observation().timestamp).max(0)
+    }
+
+    // Time-weighted average price from the oldest observation to `now`
+    pub fn twap(&self, now: i64) -> Option<u128> {
+        if !self.is_initialized() {
+            return None;
+        }
+        let oldest = self.oldest_observation();
+        let window = now.checked_sub(oldest.timestamp).filter(|window| *window > 0)?;
+
+        let cumulative = self.cumulative_price_at(now).wrapping_sub(oldest.cumulative_price);
+        Some(cumulative / window as u128)
+    }
+
+    pub fn ema(&self) -> Option<u128> {
+        self.is_initialized().then_some(self.ema_price)
+    }
+
+    // Deviation of `price` from the EMA when it is beyond `max_deviation_bps`
+    pub fn check(&self, price: u128, max_deviation_bps: u16) -> Option<u64> {
+        price_guard::check_deviation(price, self.ema(), max_deviation_bps)
+    }
+
+    // DONUT worth `sol_amount` lamports at the TWAP
+    pub fn donut_value(&self, sol_amount: u64, now: i64) -> Option<u64> {
+        let value = (sol_amount as u128).checked_mul(self.twap(now)?)? / PRICE_SCALE;
+        u64::try_from(value).ok()
+    }
+}
+
+// Prices returned by the `get_price` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceSnapshot {
+    pub spot_price: u128,
+    pub ema_price: u128,
+    pub twap_price: u128,
+    pub twap_window: i64,
+    pub last_update: i64,
+}
+
+impl PriceTracker {
+    pub fn snapshot(&self, now: i64) -> PriceSnapshot {
+        PriceSnapshot {
+            spot_price: self.last_price,
+            ema_price: self.ema_price,
+            twap_price: self.twap(now).unwrap_or(0),
+            twap_window: self.twap_window(now),
+            last_update: self.last_update,
+        }
+    }
+}
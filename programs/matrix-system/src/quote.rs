@@ -0,0 +1,373 @@
+// Swap quotes for a Meteora dynamic AMM pool.
+// Mirrors the pool's own swap: trade fee on the input, then the constant-product
+// or stable-swap curve on the vault token amounts.
+
+use anchor_lang::prelude::*;
+
+use crate::layout::{PoolView, DISCRIMINATOR_LEN};
+use crate::ErrorCode;
+
+// Iteration limit for the stable-swap Newton solvers
+const MAX_STABLE_ITERATIONS: usize = 256;
+
+// Number of tokens in a stable pool
+const N_COINS: u128 = 2;
+
+// Precision of `Depeg::base_virtual_price`
+pub const DEPEG_PRECISION: u128 = 1_000_000;
+
+// ===== METEORA DYNAMIC AMM STRUCTURES =====
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PoolType {
+    #[default]
+    Permissioned,
+    Permissionless,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolFees {
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub protocol_trade_fee_numerator: u64,
+    pub protocol_trade_fee_denominator: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct PartnerInfo {
+    pub fee_numerator: u64,
+    pub partner_authority: Pubkey,
+    pub pending_fee_a: u64,
+    pub pending_fee_b: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct Bootstrapping {
+    pub activation_point: u64,
+    pub whitelisted_vault: Pubkey,
+    pub pool_creator: Pubkey, // Deprecated in the pool program
+    pub activation_type: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CurveType {
+    #[default]
+    ConstantProduct,
+    Stable {
+        amp: u64,
+        token_multiplier: TokenMultiplier,
+        depeg: Depeg,
+        last_amp_updated_timestamp: u64,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenMultiplier {
+    pub token_a_multiplier: u64,
+    pub token_b_multiplier: u64,
+    pub precision_factor: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Depeg {
+    pub base_virtual_price: u64,
+    pub base_cache_updated: u64,
+    pub depeg_type: DepegType,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepegType {
+    #[default]
+    None,
+    Marinade,
+    Lido,
+    SplStake,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct Padding {
+    pub padding_0: [u8; 6],
+    pub padding_1: [u64; 21],
+    pub padding_2: [u64; 21],
+}
+
+// Meteora dynamic AMM pool account, without the 8 byte discriminator
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct Pool {
+    pub lp_mint: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub a_vault_lp_bump: u8,
+    pub enabled: bool,
+    pub protocol_token_a_fee: Pubkey,
+    pub protocol_token_b_fee: Pubkey,
+    pub fee_last_updated_at: u64,
+    pub _padding0: [u8; 24],
+    pub fees: PoolFees,
+    pub pool_type: PoolType,
+    pub stake: Pubkey,
+    pub total_locked_lp: u64,
+    pub bootstrapping: Bootstrapping,
+    pub partner_info: PartnerInfo,
+    pub padding: Padding,
+    pub curve_type: CurveType,
+}
+
+// Which vault receives the input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeDirection {
+    AtoB,
+    BtoA,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_out: u64,
+    pub trade_fee: u64,    // Taken from the input, includes the protocol fee
+    pub protocol_fee: u64, // Part of the trade fee sent to the protocol fee account
+}
+
+impl Pool {
+    // Decode the pool from its account data (discriminator included)
+    pub fn from_account_data(data: &[u8]) -> Result<Pool> {
+        PoolView::decode(data)?;
+        let mut pool_data = &data[DISCRIMINATOR_LEN..];
+        Pool::deserialize(&mut pool_data).map_err(|_| error!(ErrorCode::PriceMeteoraReadFailed))
+    }
+
+    // Quote a swap of `amount_in` against the pool holding `token_a_amount` and `token_b_amount`
+    pub fn quote(
+        &self,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        amount_in: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<SwapQuote> {
+        if !self.enabled {
+            msg!("Pool is disabled");
+            return Err(error!(ErrorCode::PriceMeteoraReadFailed));
+        }
+
+        let (in_reserve, out_reserve) = match trade_direction {
+            TradeDirection::AtoB => (token_a_amount, token_b_amount),
+            TradeDirection::BtoA => (token_b_amount, token_a_amount),
+        };
+
+        quote_swap(&self.fees, &self.curve_type, in_reserve, out_reserve, amount_in, trade_direction)
+            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))
+    }
+}
+
+// Fee on `amount`, at least 1 when the rate and amount are non-zero
+fn calculate_fee(amount: u128, numerator: u64, denominator: u64) -> Option<u128> {
+    if numerator == 0 || amount == 0 {
+        return Some(0);
+    }
+    let fee = amount.checked_mul(numerator as u128)?.checked_div(denominator as u128)?;
+    Some(fee.max(1))
+}
+
+impl PoolFees {
+    pub fn trading_fee(&self, amount: u128) -> Option<u128> {
+        calculate_fee(amount, self.trade_fee_numerator, self.trade_fee_denominator)
+    }
+
+    pub fn protocol_trading_fee(&self, trade_fee: u128) -> Option<u128> {
+        calculate_fee(trade_fee, self.protocol_trade_fee_numerator, self.protocol_trade_fee_denominator)
+    }
+}
+
+// SPL token-swap fees; the trade and owner fees are both taken from the input
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenSwapFees {
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub owner_trade_fee_numerator: u64,
+    pub owner_trade_fee_denominator: u64,
+}
+
+impl TokenSwapFees {
+    // Quote a constant-product swap of `amount_in` on an SPL token-swap pool
+    pub fn quote(&self, in_reserve: u64, out_reserve: u64, amount_in: u64) -> Option<SwapQuote> {
+        let trade_fee = calculate_fee(amount_in as u128, self.trade_fee_numerator, self.trade_fee_denominator)?;
+        let owner_fee = calculate_fee(amount_in as u128, self.owner_trade_fee_numerator, self.owner_trade_fee_denominator)?;
+        let amount_after_fee = (amount_in as u128).checked_sub(trade_fee)?.checked_sub(owner_fee)?;
+
+        let amount_out = constant_product_swap(amount_after_fee, in_reserve as u128, out_reserve as u128)?;
+
+        Some(SwapQuote {
+            amount_out: u64::try_from(amount_out).ok()?,
+            trade_fee: u64::try_from(trade_fee.checked_add(owner_fee)?).ok()?,
+            protocol_fee: u64::try_from(owner_fee).ok()?,
+        })
+    }
+}
+
+// Quote a swap of `amount_in` given the reserves on the input and output side
+pub fn quote_swap(
+    fees: &PoolFees,
+    curve: &CurveType,
+    in_reserve: u64,
+    out_reserve: u64,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+) -> Option<SwapQuote> {
+    let trade_fee = fees.trading_fee(amount_in as u128)?;
+    let protocol_fee = fees.protocol_trading_fee(trade_fee)?;
+    let amount_after_fee = (amount_in as u128).checked_sub(trade_fee)?;
+
+    let amount_out = curve.swap(amount_after_fee, in_reserve as u128, out_reserve as u128, trade_direction)?;
+
+    Some(SwapQuote {
+        amount_out: u64::try_from(amount_out).ok()?,
+        trade_fee: u64::try_from(trade_fee).ok()?,
+        protocol_fee: u64::try_from(protocol_fee).ok()?,
+    })
+}
+
+impl CurveType {
+    // Output of swapping `amount_in` (fees already taken) into the pool
+    pub fn swap(
+        &self,
+        amount_in: u128,
+        in_reserve: u128,
+        out_reserve: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        match self {
+            CurveType::ConstantProduct => constant_product_swap(amount_in, in_reserve, out_reserve),
+            CurveType::Stable { amp, token_multiplier, depeg, .. } => {
+                let stable = StableCurve { amp: *amp, token_multiplier: *token_multiplier, depeg: *depeg };
+                stable.swap(amount_in, in_reserve, out_reserve, trade_direction)
+            }
+        }
+    }
+}
+
+// x * y = k, rounding the remaining output reserve up
+fn constant_product_swap(amount_in: u128, in_reserve: u128, out_reserve: u128) -> Option<u128> {
+    if in_reserve == 0 || out_reserve == 0 {
+        return None;
+    }
+    let invariant = in_reserve.checked_mul(out_reserve)?;
+    let new_in_reserve = in_reserve.checked_add(amount_in)?;
+    let new_out_reserve = invariant.checked_add(new_in_reserve - 1)? / new_in_reserve;
+    out_reserve.checked_sub(new_out_reserve)
+}
+
+struct StableCurve {
+    amp: u64,
+    token_multiplier: TokenMultiplier,
+    depeg: Depeg,
+}
+
+impl StableCurve {
+    // Token B is the depeg token, its amounts are scaled by the cached virtual price
+    fn depeg_price(&self) -> Option<u128> {
+        match self.depeg.depeg_type {
+            DepegType::None => None,
+            _ => Some(self.depeg.base_virtual_price as u128),
+        }
+    }
+
+    fn upscale(&self, amount: u128, is_token_a: bool) -> Option<u128> {
+        if is_token_a {
+            return amount.checked_mul(self.token_multiplier.token_a_multiplier as u128);
+        }
+        let upscaled = amount.checked_mul(self.token_multiplier.token_b_multiplier as u128)?;
+        match self.depeg_price() {
+            Some(price) => upscaled.checked_mul(price)?.checked_div(DEPEG_PRECISION),
+            None => Some(upscaled),
+        }
+    }
+
+    fn downscale(&self, amount: u128, is_token_a: bool) -> Option<u128> {
+        if is_token_a {
+            return amount.checked_div(self.token_multiplier.token_a_multiplier as u128);
+        }
+        let amount = match self.depeg_price() {
+            Some(price) => amount.checked_mul(DEPEG_PRECISION)?.checked_div(price)?,
+            None => amount,
+        };
+        amount.checked_div(self.token_multiplier.token_b_multiplier as u128)
+    }
+
+    fn swap(&self, amount_in: u128, in_reserve: u128, out_reserve: u128, trade_direction: TradeDirection) -> Option<u128> {
+        let in_is_a = trade_direction == TradeDirection::AtoB;
+
+        let amount_in = self.upscale(amount_in, in_is_a)?;
+        let in_reserve = self.upscale(in_reserve, in_is_a)?;
+        let out_reserve = self.upscale(out_reserve, !in_is_a)?;
+
+        let d = self.compute_d(in_reserve, out_reserve)?;
+        let new_out_reserve = self.compute_y(in_reserve.checked_add(amount_in)?, d)?;
+        let amount_out = out_reserve.checked_sub(new_out_reserve)?;
+
+        self.downscale(amount_out, !in_is_a)
+    }
+
+    // StableSwap invariant D for the two upscaled reserves
+    fn compute_d(&self, amount_a: u128, amount_b: u128) -> Option<u128> {
+        let sum = amount_a.checked_add(amount_b)?;
+        if sum == 0 {
+            return Some(0);
+        }
+        if amount_a == 0 || amount_b == 0 {
+            return None;
+        }
+
+        let leverage = (self.amp as u128).checked_mul(N_COINS)?;
+        let mut d = sum;
+        for _ in 0..MAX_STABLE_ITERATIONS {
+            let d_p = d
+                .checked_mul(d)?
+                .checked_div(amount_a.checked_mul(N_COINS)?)?
+                .checked_mul(d)?
+                .checked_div(amount_b.checked_mul(N_COINS)?)?;
+            let d_prev = d;
+
+            let numerator = leverage
+                .checked_mul(sum)?
+                .checked_add(d_p.checked_mul(N_COINS)?)?
+                .checked_mul(d)?;
+            let denominator = leverage
+                .checked_sub(1)?
+                .checked_mul(d)?
+                .checked_add(d_p.checked_mul(N_COINS + 1)?)?;
+            d = numerator.checked_div(denominator)?;
+
+            if d.abs_diff(d_prev) <= 1 {
+                break;
+            }
+        }
+        Some(d)
+    }
+
+    // Reserve on the other side that keeps the invariant at `d` when one side holds `x`
+    fn compute_y(&self, x: u128, d: u128) -> Option<u128> {
+        let leverage = (self.amp as u128).checked_mul(N_COINS)?;
+        let c = d
+            .checked_mul(d)?
+            .checked_div(x.checked_mul(N_COINS)?)?
+            .checked_mul(d)?
+            .checked_div(leverage.checked_mul(N_COINS)?)?;
+        let b = x.checked_add(d.checked_div(leverage)?)?;
+
+        let mut y = d;
+        for _ in 0..MAX_STABLE_ITERATIONS {
+            let y_prev = y;
+            let numerator = y.checked_mul(y)?.checked_add(c)?;
+            let denominator = y.checked_mul(2)?.checked_add(b)?.checked_sub(d)?;
+            y = numerator.checked_div(denominator)?;
+
+            if y.abs_diff(y_prev) <= 1 {
+                break;
+            }
+        }
+        Some(y)
+    }
+}
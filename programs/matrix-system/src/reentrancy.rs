@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Why the lock lives in the account data and not across transactions:
+// a failed transaction reverts every account write, so a lock can only be
+// left set by a successful instruction, and the runtime already rejects
+// indirect reentrancy (A -> B -> A). What remains is a nested invocation
+// within the same transaction. That one deserializes the state account from
+// its data buffer, while Anchor only writes `ctx.accounts.state` back at
+// exit, so the flag has to be written straight into the buffer to be seen.
+
+// Offset of `ProgramState.is_locked`: discriminator, owner, multisig_treasury, next_upline_id, next_chain_id
+pub const IS_LOCKED_OFFSET: usize = 8 + 32 + 32 + 4 + 4;
+
+// Holds the reentrancy lock on the program state for the lifetime of the guard.
+// The lock is released when the guard is dropped, on every exit path.
+pub struct ReentrancyGuard<'info> {
+    state: AccountInfo<'info>,
+}
+
+impl<'info> ReentrancyGuard<'info> {
+    pub fn acquire(state: &AccountInfo<'info>) -> Result<Self> {
+        let mut data = state.try_borrow_mut_data()?;
+        if data.len() <= IS_LOCKED_OFFSET {
+            return Err(ProgramError::AccountDataTooSmall.into());
+        }
+
+        if data[IS_LOCKED_OFFSET] != 0 {
+            msg!("Transaction rejected: reentrancy protection active");
+            return Err(error!(ErrorCode::ReentrancyLock));
+        }
+        data[IS_LOCKED_OFFSET] = 1;
+
+        Ok(ReentrancyGuard { state: state.clone() })
+    }
+}
+
+impl Drop for ReentrancyGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut data) = self.state.try_borrow_mut_data() {
+            data[IS_LOCKED_OFFSET] = 0;
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Where a deposit goes when no slot used it: a base user's matrix completing, the
+// cascade running out of uplines or a matrix staying open without using it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerminalSinkPolicy {
+    #[default]
+    Burn,     // Swap and burn it like a Burn slot
+    Treasury, // Send it to the multisig treasury
+    Reserve,  // Hold it in the protocol reserve vault
+}
+
+impl TerminalSinkPolicy {
+    pub const SIZE: usize = 1;
+}
+
+// A reserve withdrawal must move something and leave the reserve rent exempt
+pub fn check_reserve_withdrawal(amount: u64, reserve_lamports: u64, rent_exempt_minimum: u64) -> Result<()> {
+    if amount == 0 || amount > reserve_lamports.saturating_sub(rent_exempt_minimum) {
+        msg!(
+            "Invalid reserve withdrawal: amount={}, reserve={}, rent minimum={}",
+            amount, reserve_lamports, rent_exempt_minimum
+        );
+        return Err(error!(ErrorCode::InvalidReserveWithdrawal));
+    }
+    Ok(())
+}
+
+// Lamports a deposit's payer must have spent by the end of the instruction.
+// Everything the payer sends out is the deposit, plus any overhead recorded on top of it,
+// less what it keeps for a later step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositLedger {
+    deposit: u64,
+    payer_lamports_before: u64,
+    overhead: u64,
+    kept: u64,
+}
+
+impl DepositLedger {
+    pub fn open(deposit: u64, payer_lamports_before: u64) -> Self {
+        Self { deposit, payer_lamports_before, overhead: 0, kept: 0 }
+    }
+
+    // Payer lamports spent beyond the deposit, such as rent for a vault the deposit is parked in
+    pub fn add_overhead(&mut self, amount: u64) -> Result<()> {
+        self.overhead = self.overhead.checked_add(amount).ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // Deposit lamports the payer holds on to, such as a saved cascade's vault between steps
+    pub fn keep(&mut self, amount: u64) -> Result<()> {
+        self.kept = self.kept.checked_add(amount).ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // Reject the instruction unless the payer spent exactly the deposit and its overhead
+    pub fn check(&self, payer_lamports_after: u64) -> Result<()> {
+        let expected = self.deposit
+            .checked_add(self.overhead)
+            .and_then(|total| total.checked_sub(self.kept))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        let spent = self.payer_lamports_before.checked_sub(payer_lamports_after);
+        if spent != Some(expected) {
+            msg!(
+                "Deposit invariant broken: payer went from {} to {} lamports, expected to spend {}",
+                self.payer_lamports_before, payer_lamports_after, expected
+            );
+            return Err(error!(ErrorCode::DepositUnaccounted));
+        }
+        Ok(())
+    }
+}
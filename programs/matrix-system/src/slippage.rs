@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Ceiling set at initialize (5%)
+pub const DEFAULT_MAX_SLIPPAGE_BPS: u16 = 500;
+
+// Check a protocol-wide slippage ceiling before it is written to the config
+pub fn validate_max_slippage_bps(max_slippage_bps: u16) -> Result<()> {
+    if max_slippage_bps as u64 >= BPS_DENOMINATOR {
+        msg!("Max slippage must be below {} bps: {}", BPS_DENOMINATOR, max_slippage_bps);
+        return Err(error!(ErrorCode::InvalidProtocolConfig));
+    }
+    Ok(())
+}
+
+// Fail when a caller asks for more slippage than the protocol allows
+pub fn check_slippage_bps(requested_bps: u16, max_slippage_bps: u16) -> Result<()> {
+    if requested_bps > max_slippage_bps {
+        msg!("Requested slippage {} bps exceeds the protocol maximum of {} bps", requested_bps, max_slippage_bps);
+        return Err(error!(ErrorCode::SlippageAboveMaximum));
+    }
+    Ok(())
+}
+
+// Slippage a permissionless crank runs with: whatever the caller asked for, capped at the protocol maximum
+pub fn clamp_slippage_bps(requested_bps: u16, max_slippage_bps: u16) -> u16 {
+    requested_bps.min(max_slippage_bps)
+}
+
+// Least output accepted for `expected_out` with `slippage_bps` tolerance, rounded up
+pub fn minimum_amount_out(expected_out: u64, slippage_bps: u16) -> u64 {
+    let keep_bps = BPS_DENOMINATOR.saturating_sub(slippage_bps as u64);
+    let denominator = BPS_DENOMINATOR as u128;
+    let minimum = (expected_out as u128 * keep_bps as u128 + denominator - 1) / denominator;
+    // Never accept an empty swap
+    (minimum as u64).max(1)
+}
+
+// Least output accepted for a swap quoted at `quoted_out` from the current reserves.
+// `fair_out` quotes the same input with the pool's curve and fees at the tracked TWAP, so
+// reserves pushed around earlier in the transaction cannot drag the bound down.
+// Without a TWAP only the quote is used.
+pub fn bounded_minimum_amount_out(quoted_out: u64, fair_out: Option<u64>, slippage_bps: u16) -> u64 {
+    minimum_amount_out(quoted_out.max(fair_out.unwrap_or(0)), slippage_bps)
+}
+
+// Fail with a slippage error when a swap returned less than its minimum
+pub fn check_amount_out(received: u64, minimum_out: u64) -> Result<()> {
+    if received < minimum_out {
+        msg!("Slippage exceeded: received {}, minimum {}", received, minimum_out);
+        return Err(error!(ErrorCode::SlippageExceeded));
+    }
+    Ok(())
+}
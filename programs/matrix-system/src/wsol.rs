@@ -0,0 +1,112 @@
+// WSOL handling for registrations.
+// A session wraps the deposit into the user's WSOL account only as slots need it
+// and settles once at the end, never spending the WSOL the user held before.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program;
+use anchor_spl::token::spl_token;
+
+use crate::{layout, ErrorCode};
+
+// Native token account operations a session relies on
+pub trait NativeTokenAccount {
+    // Move `amount` lamports from the owner's wallet into the account
+    fn transfer_lamports(&mut self, amount: u64) -> Result<()>;
+
+    // Bring the token amount in line with the account's lamports (SPL `SyncNative`)
+    fn sync_native(&mut self) -> Result<()>;
+
+    // Current token amount
+    fn amount(&self) -> Result<u64>;
+}
+
+// A wallet's WSOL account, operated through the system and SPL token programs
+pub struct SplWsolAccount<'info> {
+    pub wallet: AccountInfo<'info>,
+    pub wallet_seeds: Vec<Vec<u8>>, // Sign for a program address wallet, empty when the wallet signs
+    pub account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+impl<'info> NativeTokenAccount for SplWsolAccount<'info> {
+    fn transfer_lamports(&mut self, amount: u64) -> Result<()> {
+        let transfer_ix = solana_program::system_instruction::transfer(&self.wallet.key(), &self.account.key(), amount);
+        let wallet_seeds: Vec<&[u8]> = self.wallet_seeds.iter().map(Vec::as_slice).collect();
+        let signer_seeds: &[&[&[u8]]] = if wallet_seeds.is_empty() { &[] } else { &[&wallet_seeds] };
+        solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[self.wallet.clone(), self.account.clone()],
+            signer_seeds,
+        ).map_err(|e| {
+            msg!("Transfer to WSOL failed: {:?}", e);
+            error!(ErrorCode::WrapSolFailed)
+        })
+    }
+
+    fn sync_native(&mut self) -> Result<()> {
+        let sync_native_ix = spl_token::instruction::sync_native(&self.token_program.key(), &self.account.key())
+            .map_err(|_| error!(ErrorCode::WrapSolFailed))?;
+        solana_program::program::invoke(&sync_native_ix, std::slice::from_ref(&self.account)).map_err(|e| {
+            msg!("Sync native failed: {:?}", e);
+            error!(ErrorCode::WrapSolFailed)
+        })
+    }
+
+    fn amount(&self) -> Result<u64> {
+        layout::read_token_amount(&self.account)
+    }
+}
+
+// Deposit WSOL wrapped during one registration
+pub struct WsolSession<A: NativeTokenAccount> {
+    account: A,
+    held_before: u64, // WSOL the user held when the session opened
+    wrapped: u64,     // Lamports wrapped by the session so far
+}
+
+impl<A: NativeTokenAccount> WsolSession<A> {
+    // Sync first so lamports sent straight to an existing account count as held WSOL
+    pub fn open(mut account: A) -> Result<Self> {
+        account.sync_native()?;
+        let held_before = account.amount()?;
+        Ok(Self { account, held_before, wrapped: 0 })
+    }
+
+    pub fn held_before(&self) -> u64 {
+        self.held_before
+    }
+
+    pub fn wrapped(&self) -> u64 {
+        self.wrapped
+    }
+
+    // Deposit WSOL in the account that has not been spent yet
+    pub fn available(&self) -> Result<u64> {
+        let amount = self.account.amount()?;
+        amount.checked_sub(self.held_before).ok_or_else(|| {
+            msg!("WSOL balance {} fell below the {} held before registering", amount, self.held_before);
+            error!(ErrorCode::UserWsolBalanceChanged)
+        })
+    }
+
+    // Make sure `amount` deposit WSOL is available, wrapping only what is missing
+    pub fn ensure_wrapped(&mut self, amount: u64) -> Result<()> {
+        let missing = amount.saturating_sub(self.available()?);
+        if missing == 0 {
+            return Ok(());
+        }
+
+        self.account.transfer_lamports(missing)?;
+        self.account.sync_native()?;
+        self.wrapped = self.wrapped.checked_add(missing).ok_or(ErrorCode::ArithmeticOverflow)?;
+        msg!("WSOL wrapped successfully: {}", missing);
+        Ok(())
+    }
+
+    // Settle the session at the end of the registration, returning the deposit WSOL left unspent
+    pub fn settle(&self) -> Result<u64> {
+        let remaining = self.available()?;
+        msg!("WSOL session settled: {} wrapped, {} left", self.wrapped, remaining);
+        Ok(remaining)
+    }
+}
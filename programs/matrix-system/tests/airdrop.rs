@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use matrix_system::airdrop::{week_at, week_distribution, AirdropState, WeekSnapshot, AIRDROP_WEEKS};
+use matrix_system::UserAccount;
+
+const START: i64 = 1_700_000_000;
+const WEEK: i64 = 7 * 24 * 60 * 60;
+
+fn started() -> AirdropState {
+    let mut airdrop = AirdropState::default();
+    airdrop.start(START);
+    airdrop
+}
+
+#[test]
+fn weeks_run_from_start_for_36_weeks() {
+    assert_eq!(week_at(START, START - 1), 0);
+    assert_eq!(week_at(START, START), 1);
+    assert_eq!(week_at(START, START + WEEK - 1), 1);
+    assert_eq!(week_at(START, START + WEEK), 2);
+    assert_eq!(week_at(START, START + 35 * WEEK), 36);
+    assert_eq!(week_at(START, START + 36 * WEEK), 0);
+
+    assert_eq!(week_distribution(0), 0);
+    assert_eq!(week_distribution(1), 240_081);
+    assert_eq!(week_distribution(36), 1_891_317);
+    assert_eq!(week_distribution(37), 0);
+}
+
+#[test]
+fn nothing_is_recorded_before_the_airdrop_starts() {
+    let mut airdrop = AirdropState::default();
+    let mut user = UserAccount::default();
+
+    assert_eq!(airdrop.record_matrix_completion(&mut user).unwrap(), None);
+    assert_eq!(airdrop.advance_week(START).unwrap(), None);
+    assert_eq!(user.completed_matrices_total, 0);
+    assert!(user.weekly_matrices.is_empty());
+}
+
+#[test]
+fn closing_a_week_splits_its_distribution_per_matrix() {
+    let mut airdrop = started();
+    let mut alice = UserAccount::default();
+    let mut bob = UserAccount::default();
+
+    airdrop.record_matrix_completion(&mut alice).unwrap();
+    airdrop.record_matrix_completion(&mut alice).unwrap();
+    airdrop.record_matrix_completion(&mut bob).unwrap();
+    assert_eq!(airdrop.advance_week(START + WEEK - 1).unwrap(), None);
+
+    let closed = airdrop.advance_week(START + WEEK).unwrap().unwrap();
+    assert_eq!(
+        closed,
+        WeekSnapshot {
+            week_number: 1,
+            total_matrices: 3,
+            donut_distributed: 240_081,
+            donut_per_matrix: 80_027,
+            week_end_timestamp: START + WEEK,
+        }
+    );
+    assert_eq!(airdrop.current_week, 2);
+    assert_eq!(airdrop.total_matrices_this_week, 0);
+
+    airdrop.settle_user(&mut alice).unwrap();
+    airdrop.settle_user(&mut bob).unwrap();
+    assert_eq!(alice.claimable_donut(), 2 * 80_027);
+    assert_eq!(bob.claimable_donut(), 80_027);
+}
+
+#[test]
+fn empty_weeks_are_not_snapshotted() {
+    let mut airdrop = started();
+
+    assert_eq!(airdrop.advance_week(START + 3 * WEEK).unwrap(), None);
+    assert_eq!(airdrop.current_week, 4);
+    assert!(airdrop.closed_weeks.is_empty());
+}
+
+#[test]
+fn settling_mid_week_keeps_the_running_week_for_later() {
+    let mut airdrop = started();
+    let mut user = UserAccount::default();
+
+    airdrop.record_matrix_completion(&mut user).unwrap();
+    airdrop.advance_week(START + WEEK).unwrap();
+    airdrop.record_matrix_completion(&mut user).unwrap();
+
+    // Week 1 is paid, week 2 is still running
+    airdrop.settle_user(&mut user).unwrap();
+    assert_eq!(user.total_donut_earned, 240_081);
+    assert_eq!(user.last_processed_week, 1);
+
+    // Week 2 closes after the claim and is paid on the next one
+    airdrop.advance_week(START + 2 * WEEK).unwrap();
+    airdrop.settle_user(&mut user).unwrap();
+    assert_eq!(user.total_donut_earned, 240_081 + 259_617);
+
+    // Settling again pays nothing new
+    airdrop.settle_user(&mut user).unwrap();
+    assert_eq!(user.total_donut_earned, 240_081 + 259_617);
+}
+
+#[test]
+fn airdrop_ends_after_the_last_week() {
+    let mut airdrop = started();
+    let mut user = UserAccount::default();
+
+    airdrop.advance_week(START + 35 * WEEK).unwrap();
+    airdrop.record_matrix_completion(&mut user).unwrap();
+
+    let closed = airdrop.advance_week(START + 36 * WEEK).unwrap().unwrap();
+    assert_eq!(closed.week_number, AIRDROP_WEEKS as u8);
+    assert!(!airdrop.active);
+    assert_eq!(airdrop.record_matrix_completion(&mut user).unwrap(), None);
+}
+
+#[test]
+fn airdrop_state_size_fits_every_week() {
+    let mut airdrop = started();
+    airdrop.closed_weeks = vec![WeekSnapshot::default(); AIRDROP_WEEKS];
+
+    assert_eq!(airdrop.try_to_vec().unwrap().len(), AirdropState::SIZE);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{install_token_runtime, settled_tracker, vault_burn_accounts, MockDex, VAULT_RENT};
+use matrix_system::buyback::{queued_amount, BurnMode, BuybackState, MIN_BUYBACK_AMOUNT};
+use matrix_system::burn_vault_lamports;
+use matrix_system::dex::DexAdapter;
+use matrix_system::layout::read_token_amount;
+use matrix_system::price_guard::pool_price;
+use matrix_system::quote::TokenSwapFees;
+use matrix_system::slippage::{clamp_slippage_bps, DEFAULT_MAX_SLIPPAGE_BPS};
+
+const NOW: i64 = 1_700_000_000;
+const BATCH: u64 = 1_000_000_000;
+const MAX_DEVIATION_BPS: u16 = 500;
+const DONUT_RESERVE: u64 = 1_000_000_000_000;
+const SOL_RESERVE: u64 = 100_000_000_000;
+const FEES: TokenSwapFees = TokenSwapFees {
+    trade_fee_numerator: 25,
+    trade_fee_denominator: 10_000,
+    owner_trade_fee_numerator: 5,
+    owner_trade_fee_denominator: 10_000,
+};
+const SEEDS: &[&[&[u8]]] = &[&[b"burn_vault", &[255]]];
+
+#[test]
+fn burns_are_immediate_by_default() {
+    assert_eq!(BurnMode::default(), BurnMode::Immediate);
+    assert_eq!(BurnMode::Batched.try_to_vec().unwrap().len(), BurnMode::SIZE);
+}
+
+#[test]
+fn record_accumulates_totals() {
+    let mut state = BuybackState::default();
+
+    state.record(50_000_000, 1_000_000_000, NOW).unwrap();
+    state.record(30_000_000, 500_000_000, NOW + 60).unwrap();
+
+    assert_eq!(state.total_sol_spent, 80_000_000);
+    assert_eq!(state.total_donut_burned, 1_500_000_000);
+    assert_eq!(state.executions, 2);
+    assert_eq!(state.last_execution, NOW + 60);
+}
+
+#[test]
+fn record_rejects_overflowing_totals() {
+    let mut state = BuybackState { total_donut_burned: u64::MAX, ..Default::default() };
+
+    let err = state.record(MIN_BUYBACK_AMOUNT, 1, NOW).unwrap_err();
+    assert!(err.to_string().contains("ArithmeticOverflow"));
+}
+
+#[test]
+fn queued_amount_keeps_the_vault_rent_exempt() {
+    assert_eq!(queued_amount(1_000_890_880, 890_880), 1_000_000_000);
+    assert_eq!(queued_amount(890_880, 890_880), 0);
+    assert_eq!(queued_amount(0, 890_880), 0);
+}
+
+#[test]
+fn buyback_state_size_matches_serialization() {
+    let state = BuybackState::default();
+    assert_eq!(state.try_to_vec().unwrap().len(), BuybackState::SIZE);
+}
+
+#[test]
+fn crank_refuses_to_swap_outside_the_guard_band() {
+    install_token_runtime();
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let mut tracker = settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW);
+    let accounts = vault_burn_accounts(BATCH);
+
+    // Pushed either way past the 5% band
+    for (donut_reserve, sol_reserve) in [(DONUT_RESERVE, SOL_RESERVE * 11 / 10), (DONUT_RESERVE * 11 / 10, SOL_RESERVE)] {
+        dex.donut_reserve.set(donut_reserve);
+        dex.sol_reserve.set(sol_reserve);
+
+        let err = burn_vault_lamports(&dex, &accounts, &mut tracker, BATCH, MAX_DEVIATION_BPS, DEFAULT_MAX_SLIPPAGE_BPS, SEEDS, NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("PriceDeviationTooHigh"));
+        assert_eq!(accounts.vault.lamports(), VAULT_RENT + BATCH);
+        assert_eq!(dex.sol_reserve.get(), sol_reserve);
+    }
+}
+
+#[test]
+fn crank_enforces_the_configured_slippage() {
+    install_token_runtime();
+    let price = pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap();
+
+    // Asking for 100% still runs with the protocol maximum
+    let max_slippage_bps = clamp_slippage_bps(10_000, DEFAULT_MAX_SLIPPAGE_BPS);
+    assert_eq!(max_slippage_bps, DEFAULT_MAX_SLIPPAGE_BPS);
+
+    // A venue delivering 6% short is caught
+    let mut dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let expected = dex.quote_donut_out(BATCH, NOW).unwrap();
+    dex.shortfall = expected * 6 / 100;
+    dex.ignores_minimum = true;
+    let err = burn_vault_lamports(
+        &dex,
+        &vault_burn_accounts(BATCH),
+        &mut settled_tracker(price, NOW),
+        BATCH,
+        MAX_DEVIATION_BPS,
+        max_slippage_bps,
+        SEEDS,
+        NOW,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("SlippageExceeded"));
+
+    // 2% short stays above the bound, which also holds against the TWAP value, and is burned
+    let mut dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    dex.shortfall = expected * 2 / 100;
+    let accounts = vault_burn_accounts(BATCH);
+    let burned = burn_vault_lamports(
+        &dex,
+        &accounts,
+        &mut settled_tracker(price, NOW),
+        BATCH,
+        MAX_DEVIATION_BPS,
+        max_slippage_bps,
+        SEEDS,
+        NOW,
+    )
+    .unwrap();
+    assert_eq!(burned, expected - dex.shortfall);
+    assert_eq!(accounts.vault.lamports(), VAULT_RENT);
+    assert_eq!(read_token_amount(&accounts.burn_account).unwrap(), 0);
+}
+
+#[test]
+fn crank_burns_at_a_stable_price_with_slippage_below_the_pool_fee() {
+    install_token_runtime();
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let expected = dex.quote_donut_out(BATCH, NOW).unwrap();
+    let accounts = vault_burn_accounts(BATCH);
+
+    // 0.1% tolerance is under the 0.3% fee, but the TWAP bound is quoted with the same fees
+    let burned = burn_vault_lamports(
+        &dex,
+        &accounts,
+        &mut settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW),
+        BATCH,
+        MAX_DEVIATION_BPS,
+        10,
+        SEEDS,
+        NOW,
+    )
+    .unwrap();
+    assert_eq!(burned, expected);
+    assert_eq!(accounts.vault.lamports(), VAULT_RENT);
+}
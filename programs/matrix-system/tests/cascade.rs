@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use matrix_system::cascade::{inline_steps, PendingCascade, MAX_CASCADE_STEPS};
+use matrix_system::{ReferralUpline, UplineEntry, UserAccount, MAX_UPLINE_DEPTH};
+
+const NOW: i64 = 1_700_000_000;
+const DEPOSIT: u64 = 100_000_000;
+
+fn uplines(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+#[test]
+fn registrations_walk_at_most_the_step_limit() {
+    assert_eq!(inline_steps(0), 0);
+    assert_eq!(inline_steps(2), 2);
+    assert_eq!(inline_steps(MAX_UPLINE_DEPTH), MAX_CASCADE_STEPS);
+}
+
+#[test]
+fn saved_cascade_resumes_where_the_registration_stopped() {
+    let uplines = uplines(MAX_UPLINE_DEPTH);
+    let registrant = Pubkey::new_unique();
+    let mut cascade = PendingCascade::default();
+
+    cascade.save(registrant, uplines[MAX_CASCADE_STEPS - 1], DEPOSIT, uplines.clone(), MAX_CASCADE_STEPS, NOW).unwrap();
+
+    assert!(cascade.active);
+    assert_eq!(cascade.registrant, registrant);
+    assert_eq!(cascade.next_uplines(), &uplines[MAX_CASCADE_STEPS..]);
+    assert_eq!(cascade.started_at, NOW);
+
+    cascade.advance(MAX_CASCADE_STEPS, uplines[MAX_UPLINE_DEPTH - 1]).unwrap();
+    assert!(cascade.is_exhausted());
+    assert!(cascade.next_uplines().is_empty());
+    assert_eq!(cascade.current_user, uplines[MAX_UPLINE_DEPTH - 1]);
+}
+
+#[test]
+fn next_uplines_are_bounded_by_the_step_limit() {
+    let uplines = uplines(MAX_UPLINE_DEPTH);
+    let mut cascade = PendingCascade::default();
+
+    cascade.save(Pubkey::new_unique(), Pubkey::new_unique(), DEPOSIT, uplines.clone(), 1, NOW).unwrap();
+
+    assert_eq!(cascade.next_uplines(), &uplines[1..1 + MAX_CASCADE_STEPS]);
+    assert_eq!(cascade.remaining_uplines().len(), MAX_UPLINE_DEPTH - 1);
+}
+
+#[test]
+fn a_registrant_has_one_cascade_pending_at_a_time() {
+    let mut cascade = PendingCascade::default();
+    cascade.ensure_idle().unwrap();
+
+    cascade.save(Pubkey::new_unique(), Pubkey::new_unique(), DEPOSIT, uplines(4), 3, NOW).unwrap();
+    assert!(cascade.ensure_idle().unwrap_err().to_string().contains("CascadePending"));
+
+    let err = cascade.save(Pubkey::new_unique(), Pubkey::new_unique(), DEPOSIT, uplines(4), 3, NOW).unwrap_err();
+    assert!(err.to_string().contains("CascadePending"));
+
+    cascade.finish();
+    assert!(!cascade.active);
+    cascade.ensure_idle().unwrap();
+}
+
+#[test]
+fn save_rejects_a_cascade_with_nothing_left() {
+    let mut cascade = PendingCascade::default();
+
+    let err = cascade.save(Pubkey::new_unique(), Pubkey::new_unique(), DEPOSIT, uplines(3), 3, NOW).unwrap_err();
+    assert!(err.to_string().contains("InvalidUplineAccount"));
+
+    let err = cascade
+        .save(Pubkey::new_unique(), Pubkey::new_unique(), DEPOSIT, uplines(MAX_UPLINE_DEPTH + 1), 1, NOW)
+        .unwrap_err();
+    assert!(err.to_string().contains("InvalidUplineAccount"));
+    assert!(!cascade.active);
+}
+
+#[test]
+fn advance_cannot_pass_the_last_upline() {
+    let mut cascade = PendingCascade::default();
+    cascade.save(Pubkey::new_unique(), Pubkey::new_unique(), DEPOSIT, uplines(4), 2, NOW).unwrap();
+
+    let err = cascade.advance(3, Pubkey::new_unique()).unwrap_err();
+    assert!(err.to_string().contains("InvalidUplineAccount"));
+    assert_eq!(cascade.next_upline_index, 2);
+}
+
+#[test]
+fn pending_cascade_size_matches_serialization() {
+    let cascade = PendingCascade { uplines: uplines(MAX_UPLINE_DEPTH), ..Default::default() };
+    assert_eq!(cascade.try_to_vec().unwrap().len(), PendingCascade::SIZE);
+}
+
+const WIDTH: usize = 3;
+
+// Users of a referral tree, filled the way registrations and `continue_cascade` fill them:
+// every completed matrix forwards the deposit to the next upline. `fills` records the
+// `SlotFilled` events as (owner, user).
+#[derive(Clone, Default)]
+struct Tree {
+    users: HashMap<Pubkey, UserAccount>,
+    fills: Vec<(Pubkey, Pubkey)>,
+    next_chain_id: u32,
+}
+
+impl Tree {
+    // Registered user under `referrer` whose matrix has `filled` slots taken
+    fn add(&mut self, key: Pubkey, referrer: Option<Pubkey>, filled: u8) -> Pubkey {
+        let mut upline = Vec::new();
+        if let Some(referrer) = referrer {
+            let above = &self.users[&referrer].upline.upline;
+            upline.extend_from_slice(&above[above.len().saturating_sub(MAX_UPLINE_DEPTH - 1)..]);
+            upline.push(UplineEntry { pda: referrer, wallet: referrer });
+        }
+
+        let mut user = UserAccount {
+            is_registered: true,
+            referrer,
+            owner_wallet: key,
+            upline: ReferralUpline { id: 0, depth: upline.len() as u8, upline },
+            ..Default::default()
+        };
+        user.chain.filled_slots = filled;
+        self.users.insert(key, user);
+        key
+    }
+
+    // Fill the next slot of `owner` with `user` on behalf of `registrant`'s deposit
+    fn fill(&mut self, owner: Pubkey, user: Pubkey, registrant: Pubkey) -> Result<bool> {
+        let account = self.users.get_mut(&owner).unwrap();
+        account.check_cascade_lock(&registrant)?;
+        account.release_cascade_lock(&registrant);
+
+        self.next_chain_id += 1;
+        let (_, completed) = account.chain.fill_next_slot(user, WIDTH, self.next_chain_id)?;
+        self.fills.push((owner, user));
+        Ok(completed)
+    }
+
+    // Walk `uplines` until a matrix stays open; returns the user the next upline would get
+    fn walk(&mut self, uplines: &[Pubkey], mut current_user: Pubkey, registrant: Pubkey) -> Result<Option<Pubkey>> {
+        for upline in uplines {
+            if !self.fill(*upline, current_user, registrant)? {
+                return Ok(None);
+            }
+            current_user = *upline;
+        }
+        Ok(Some(current_user))
+    }
+
+    // Register `wallet` under `referrer`, walking at most `inline_limit` uplines before saving
+    // the cascade. A failing registration leaves the tree untouched, like a failed transaction.
+    fn register(&mut self, wallet: Pubkey, referrer: Pubkey, inline_limit: usize) -> Result<Option<PendingCascade>> {
+        let mut tree = self.clone();
+        let cascade = tree.register_in_place(wallet, referrer, inline_limit)?;
+        *self = tree;
+        Ok(cascade)
+    }
+
+    fn register_in_place(&mut self, wallet: Pubkey, referrer: Pubkey, inline_limit: usize) -> Result<Option<PendingCascade>> {
+        self.add(wallet, Some(referrer), 0);
+        self.users[&referrer].check_cascade_lock(&wallet)?;
+
+        let cascade_uplines: Vec<Pubkey> = self.users[&referrer].upline.upline.iter().rev().map(|entry| entry.pda).collect();
+        if !self.fill(referrer, wallet, wallet)? {
+            return Ok(None);
+        }
+
+        let inline = cascade_uplines.len().min(inline_limit);
+        let Some(current_user) = self.walk(&cascade_uplines[..inline], referrer, wallet)? else {
+            return Ok(None);
+        };
+        if inline == cascade_uplines.len() {
+            return Ok(None);
+        }
+
+        for upline in &cascade_uplines[inline..] {
+            self.users.get_mut(upline).unwrap().lock_for_cascade(wallet)?;
+        }
+        let mut cascade = PendingCascade::default();
+        cascade.save(wallet, current_user, DEPOSIT, cascade_uplines, inline, NOW)?;
+        Ok(Some(cascade))
+    }
+
+    // `continue_cascade`: walk the next uplines, unlocking the rest when the cascade ends
+    fn continue_cascade(&mut self, cascade: &mut PendingCascade) -> Result<()> {
+        let next = cascade.next_uplines().to_vec();
+        let remaining = cascade.remaining_uplines().to_vec();
+        let walked = self.walk(&next, cascade.current_user, cascade.registrant)?;
+
+        cascade.advance(next.len(), walked.unwrap_or_default())?;
+        if walked.is_none() || cascade.is_exhausted() {
+            for upline in &remaining {
+                self.users.get_mut(upline).unwrap().release_cascade_lock(&cascade.registrant);
+            }
+            cascade.finish();
+        }
+        Ok(())
+    }
+}
+
+// A chain of MAX_UPLINE_DEPTH + 1 users one slot short of completing their matrices, a
+// second user under its third upline, and an unrelated tree. Returns (chain, side, other).
+fn build_trees() -> (Tree, Vec<Pubkey>, Pubkey, Pubkey) {
+    let mut tree = Tree::default();
+    let mut chain = vec![tree.add(Pubkey::new_unique(), None, (WIDTH - 1) as u8)];
+    for _ in 0..MAX_UPLINE_DEPTH {
+        let last = *chain.last().unwrap();
+        chain.push(tree.add(Pubkey::new_unique(), Some(last), (WIDTH - 1) as u8));
+    }
+    let side = tree.add(Pubkey::new_unique(), Some(chain[2]), (WIDTH - 1) as u8);
+
+    let other_root = tree.add(Pubkey::new_unique(), None, 0);
+    let other = tree.add(Pubkey::new_unique(), Some(other_root), 0);
+    (tree, chain, side, other)
+}
+
+#[test]
+fn saved_cascade_only_holds_back_registrations_reaching_its_uplines() {
+    let (mut tree, chain, side, other) = build_trees();
+    let deepest = chain[MAX_UPLINE_DEPTH];
+
+    // Completes every matrix up the chain and saves what one transaction cannot walk
+    let mut cascade = tree.register(Pubkey::new_unique(), deepest, MAX_CASCADE_STEPS).unwrap().unwrap();
+    assert!(cascade.active);
+    assert_eq!(cascade.remaining_uplines(), &[chain[2], chain[1], chain[0]]);
+
+    // A registration in another part of the tree goes through
+    assert!(tree.register(Pubkey::new_unique(), other, MAX_CASCADE_STEPS).unwrap().is_none());
+
+    // One whose deposit reaches an upline the cascade still has to fill waits, with nothing applied
+    let fills = tree.fills.len();
+    let held_back = Pubkey::new_unique();
+    let err = tree.register(held_back, side, MAX_CASCADE_STEPS).unwrap_err();
+    assert!(err.to_string().contains("CascadePending"));
+    let err = tree.register(Pubkey::new_unique(), chain[1], MAX_CASCADE_STEPS).unwrap_err();
+    assert!(err.to_string().contains("CascadePending"));
+    assert_eq!(tree.fills.len(), fills);
+
+    // Once the cascade is continued it goes through as well
+    tree.continue_cascade(&mut cascade).unwrap();
+    assert!(!cascade.active);
+    assert!(chain.iter().all(|upline| tree.users[upline].cascade_lock.is_none()));
+    assert!(tree.register(held_back, side, MAX_CASCADE_STEPS).unwrap().is_none());
+}
+
+#[test]
+fn slots_fill_in_single_transaction_order() {
+    let (mut split, chain, side, other) = build_trees();
+    let mut single = split.clone();
+    let deepest = chain[MAX_UPLINE_DEPTH];
+    let wallets = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+
+    // Split across transactions, with the held back registration retried after the crank
+    let mut cascade = split.register(wallets[0], deepest, MAX_CASCADE_STEPS).unwrap().unwrap();
+    split.register(wallets[1], other, MAX_CASCADE_STEPS).unwrap();
+    assert!(split.register(wallets[2], side, MAX_CASCADE_STEPS).is_err());
+    split.continue_cascade(&mut cascade).unwrap();
+    split.register(wallets[2], side, MAX_CASCADE_STEPS).unwrap();
+
+    // Every registration walking its whole cascade in one transaction
+    for (wallet, referrer) in wallets.into_iter().zip([deepest, other, side]) {
+        assert!(single.register(wallet, referrer, usize::MAX).unwrap().is_none());
+    }
+
+    // Only the unrelated registration's slot moved, between the cascade's two transactions
+    let unrelated = (other, wallets[1]);
+    assert_eq!(split.fills.len(), single.fills.len());
+    assert_eq!(split.fills[MAX_CASCADE_STEPS + 1], unrelated);
+    assert_eq!(single.fills[MAX_UPLINE_DEPTH + 1], unrelated);
+
+    let without = |tree: &Tree| -> Vec<(Pubkey, Pubkey)> {
+        tree.fills.iter().copied().filter(|fill| *fill != unrelated).collect()
+    };
+    assert_eq!(without(&split), without(&single));
+    assert_eq!(without(&split)[MAX_UPLINE_DEPTH + 1..], [(side, wallets[2]), (chain[2], side)]);
+}
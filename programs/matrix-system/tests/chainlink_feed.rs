@@ -0,0 +1,77 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{build_feed, CHAINLINK_PROGRAM, SOL_USD_FEED, TRANSMISSIONS_OFFSET};
+use matrix_system::chainlink::{read_latest_round, RoundData};
+
+fn read(mut data: Vec<u8>, owner: Pubkey) -> Result<RoundData> {
+    let key = SOL_USD_FEED;
+    let mut lamports = 1_000_000;
+    let feed = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+    read_latest_round(&feed, &CHAINLINK_PROGRAM)
+}
+
+#[test]
+fn reads_latest_round() {
+    let data = build_feed(8, 1, &[(1_700_000_000, 150_00000000), (1_700_000_060, 152_12345678)]);
+
+    let round = read(data, CHAINLINK_PROGRAM).unwrap();
+    assert_eq!(
+        round,
+        RoundData {
+            round_id: 2,
+            answer: 152_12345678,
+            timestamp: 1_700_000_060,
+            decimals: 8,
+        }
+    );
+}
+
+#[test]
+fn rejects_multi_transmission_feeds() {
+    // The v2 SDK only supports single-transmission feeds
+    let data = build_feed(8, 3, &[(1_700_000_000, 150_00000000)]);
+
+    assert!(read(data, CHAINLINK_PROGRAM).is_err());
+}
+
+#[test]
+fn rejects_wrong_discriminator() {
+    let mut data = build_feed(8, 1, &[(1_700_000_000, 150_00000000)]);
+    data[0] ^= 0xff;
+
+    let err = read(data, CHAINLINK_PROGRAM).unwrap_err();
+    assert!(err.to_string().contains("InvalidPriceFeed"));
+}
+
+#[test]
+fn rejects_feed_without_rounds() {
+    let data = build_feed(8, 1, &[]);
+
+    assert!(read(data, CHAINLINK_PROGRAM).is_err());
+}
+
+#[test]
+fn rejects_non_positive_answer() {
+    let data = build_feed(8, 1, &[(1_700_000_000, 0)]);
+    assert!(read(data, CHAINLINK_PROGRAM).is_err());
+
+    let data = build_feed(8, 1, &[(1_700_000_000, -5)]);
+    assert!(read(data, CHAINLINK_PROGRAM).is_err());
+}
+
+#[test]
+fn rejects_truncated_account() {
+    let data = build_feed(8, 1, &[(1_700_000_000, 150_00000000)]);
+
+    assert!(read(data[..TRANSMISSIONS_OFFSET - 1].to_vec(), CHAINLINK_PROGRAM).is_err());
+    assert!(read(data[..TRANSMISSIONS_OFFSET + 10].to_vec(), CHAINLINK_PROGRAM).is_err());
+}
+
+#[test]
+fn rejects_feed_account_with_foreign_owner() {
+    let data = build_feed(8, 1, &[(1_700_000_000, 98_50000000)]);
+
+    let err = read(data, Pubkey::new_unique()).unwrap_err();
+    assert!(err.to_string().contains("InvalidPriceFeed"));
+}
@@ -0,0 +1,412 @@
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::pubkey;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::instruction::TokenInstruction;
+use matrix_system::buyback::BurnMode;
+use matrix_system::config::{ProtocolAddresses, ProtocolConfig};
+use matrix_system::dex::{DexAdapter, SwapVenue};
+use matrix_system::layout::{read_token_amount, TOKEN_ACCOUNT_LEN, TOKEN_SWAP_LEN, TOKEN_SWAP_VERSION};
+use matrix_system::matrix::DEFAULT_SLOT_ACTIONS;
+use matrix_system::pending_burn::SwapFailurePolicy;
+use matrix_system::price_tracker::{PriceTracker, EMA_PERIOD};
+use matrix_system::quote::TokenSwapFees;
+use matrix_system::sink::TerminalSinkPolicy;
+use matrix_system::slippage::DEFAULT_MAX_SLIPPAGE_BPS;
+use matrix_system::wsol::NativeTokenAccount;
+use matrix_system::{ErrorCode, VaultBurnAccounts};
+
+// Chainlink devnet store program and SOL/USD feed
+pub const CHAINLINK_PROGRAM: Pubkey = pubkey!("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny");
+pub const SOL_USD_FEED: Pubkey = pubkey!("99B2bTijsU6f1GCT73HmdR7HCFFjGMBcPZY6jZ96ynrR");
+
+// Protocol config with fresh addresses and the default limits
+pub fn build_protocol_config() -> ProtocolConfig {
+    ProtocolConfig {
+        addresses: ProtocolAddresses {
+            pool: Pubkey::new_unique(),
+            a_vault: Pubkey::new_unique(),
+            a_vault_lp: Pubkey::new_unique(),
+            a_vault_lp_mint: Pubkey::new_unique(),
+            a_token_vault: Pubkey::new_unique(),
+            b_vault: Pubkey::new_unique(),
+            b_vault_lp: Pubkey::new_unique(),
+            b_token_vault: Pubkey::new_unique(),
+            b_vault_lp_mint: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            wsol_mint: Pubkey::new_unique(),
+            meteora_vault_program: Pubkey::new_unique(),
+            meteora_amm_program: Pubkey::new_unique(),
+            protocol_token_a_fee: Pubkey::new_unique(),
+            protocol_token_b_fee: Pubkey::new_unique(),
+        },
+        minimum_usd_deposit: 10_00000000,
+        max_price_feed_age: 86400,
+        max_upline_depth: 6,
+        slot_actions: DEFAULT_SLOT_ACTIONS.to_vec(),
+        max_slippage_bps: DEFAULT_MAX_SLIPPAGE_BPS,
+        max_price_deviation_bps: 1_000,
+        swap_venue: SwapVenue::MeteoraDynamicAmm,
+        burn_mode: BurnMode::Immediate,
+        swap_failure_policy: SwapFailurePolicy::Revert,
+        terminal_sink_policy: TerminalSinkPolicy::Burn,
+    }
+}
+
+// Chainlink store `Transmissions` layout: discriminator, 192 byte header, then
+// the live transmission ring buffer (single entry for v2 feeds)
+pub const TRANSMISSIONS_OFFSET: usize = 8 + 192;
+pub const TRANSMISSION_SIZE: usize = 48;
+
+// Stand-in for a Chainlink store feed account: `rounds` are written in order
+// into a ring buffer of `live_length` entries, like the store program does.
+pub fn build_feed(decimals: u8, live_length: u32, rounds: &[(u32, i128)]) -> Vec<u8> {
+    let mut data = vec![0u8; TRANSMISSIONS_OFFSET + live_length as usize * TRANSMISSION_SIZE];
+    data[..8].copy_from_slice(&hash(b"account:Transmissions").to_bytes()[..8]);
+    data[8 + 130] = decimals;
+    data[8 + 140..8 + 144].copy_from_slice(&live_length.to_le_bytes());
+
+    let mut cursor = 0u32;
+    for (round_id, (timestamp, answer)) in rounds.iter().enumerate() {
+        let start = TRANSMISSIONS_OFFSET + cursor as usize * TRANSMISSION_SIZE;
+        data[start + 8..start + 12].copy_from_slice(&timestamp.to_le_bytes());
+        data[start + 16..start + 32].copy_from_slice(&answer.to_le_bytes());
+        cursor = (cursor + 1) % live_length;
+        data[8 + 135..8 + 139].copy_from_slice(&(round_id as u32 + 1).to_le_bytes());
+    }
+    data[8 + 144..8 + 148].copy_from_slice(&cursor.to_le_bytes());
+
+    data
+}
+
+// Stand-in for a Pyth price account with an aggregate price
+pub fn build_pyth_price(price: i64, expo: i32, publish_time: i64, status: u32) -> Vec<u8> {
+    let mut data = vec![0u8; 240];
+    data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    data[4..8].copy_from_slice(&2u32.to_le_bytes());
+    data[8..12].copy_from_slice(&3u32.to_le_bytes());
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[96..104].copy_from_slice(&publish_time.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[224..228].copy_from_slice(&status.to_le_bytes());
+    data
+}
+
+// Initialized SPL token account holding `amount` of `mint`
+pub fn build_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+    data[0..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data[108] = 1;
+    data
+}
+
+fn set_token_amount(account: &AccountInfo, amount: u64) -> Result<()> {
+    account.try_borrow_mut_data()?[64..72].copy_from_slice(&amount.to_le_bytes());
+    Ok(())
+}
+
+// Account for driving the program entrypoint. `entry` borrows its accounts for
+// `'info`, so the key, lamports and data are leaked for the test's lifetime.
+pub fn program_account(key: Pubkey, owner: Pubkey, is_signer: bool, is_writable: bool, data: Vec<u8>) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        is_signer,
+        is_writable,
+        Box::leak(Box::new(1_000_000_000)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+pub fn set_lamports(account: &AccountInfo, lamports: u64) {
+    **account.try_borrow_mut_lamports().unwrap() = lamports;
+}
+
+// Executable program account, as `Program<T>` expects
+pub fn executable_account(program_id: Pubkey) -> AccountInfo<'static> {
+    let mut account = program_account(program_id, Pubkey::default(), false, false, vec![]);
+    account.executable = true;
+    account
+}
+
+// Serialized Anchor account zero-padded to its allocated `space`
+pub fn anchor_account_data<T: AccountSerialize>(account: &T, space: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    data
+}
+
+// Run one instruction through the program entrypoint
+pub fn run_instruction<T: InstructionData>(accounts: Vec<AccountInfo<'static>>, ix: T) -> std::result::Result<(), ProgramError> {
+    let accounts: &'static [AccountInfo<'static>] = Box::leak(accounts.into_boxed_slice());
+    matrix_system::entry(&matrix_system::ID, accounts, &ix.data())
+}
+
+// Stand-in for an SPL token-swap `SwapV1` account
+#[allow(clippy::too_many_arguments)]
+pub fn build_token_swap(
+    token_a: &Pubkey,
+    token_b: &Pubkey,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    pool_mint: &Pubkey,
+    pool_fee_account: &Pubkey,
+    bump_seed: u8,
+    fees: TokenSwapFees,
+    curve_type: u8,
+) -> Vec<u8> {
+    let mut data = vec![0u8; TOKEN_SWAP_LEN];
+    data[0] = TOKEN_SWAP_VERSION;
+    data[1] = 1;
+    data[2] = bump_seed;
+    data[3..35].copy_from_slice(spl_token::ID.as_ref());
+    data[35..67].copy_from_slice(token_a.as_ref());
+    data[67..99].copy_from_slice(token_b.as_ref());
+    data[99..131].copy_from_slice(pool_mint.as_ref());
+    data[131..163].copy_from_slice(token_a_mint.as_ref());
+    data[163..195].copy_from_slice(token_b_mint.as_ref());
+    data[195..227].copy_from_slice(pool_fee_account.as_ref());
+    data[227..235].copy_from_slice(&fees.trade_fee_numerator.to_le_bytes());
+    data[235..243].copy_from_slice(&fees.trade_fee_denominator.to_le_bytes());
+    data[243..251].copy_from_slice(&fees.owner_trade_fee_numerator.to_le_bytes());
+    data[251..259].copy_from_slice(&fees.owner_trade_fee_denominator.to_le_bytes());
+    data[291] = curve_type;
+    data
+}
+
+// In-memory constant-product venue. Swaps move token balances directly in the
+// source and destination account data instead of invoking a program.
+pub struct MockDex {
+    pub donut_reserve: Cell<u64>,
+    pub sol_reserve: Cell<u64>,
+    pub fees: TokenSwapFees,
+    pub shortfall: u64,          // DONUT held back from every swap
+    pub ignores_minimum: bool,   // Deliver short swaps instead of failing them
+}
+
+impl MockDex {
+    pub fn new(donut_reserve: u64, sol_reserve: u64, fees: TokenSwapFees) -> Self {
+        MockDex {
+            donut_reserve: Cell::new(donut_reserve),
+            sol_reserve: Cell::new(sol_reserve),
+            fees,
+            shortfall: 0,
+            ignores_minimum: false,
+        }
+    }
+}
+
+impl<'info> DexAdapter<'info> for MockDex {
+    fn reserves(&self, _current_time: i64) -> Result<(u64, u64)> {
+        Ok((self.donut_reserve.get(), self.sol_reserve.get()))
+    }
+
+    fn quote_donut_out_at(&self, amount_in: u64, donut_reserve: u64, sol_reserve: u64) -> Result<u64> {
+        self.fees
+            .quote(sol_reserve, donut_reserve, amount_in)
+            .map(|quote| quote.amount_out)
+            .ok_or(error!(ErrorCode::MeteoraCalculationOverflow))
+    }
+
+    fn swap(
+        &self,
+        _authority: &AccountInfo<'info>,
+        source: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        _signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let amount_out = self.quote_donut_out(amount_in, 0)?.saturating_sub(self.shortfall);
+        if amount_out < minimum_amount_out && !self.ignores_minimum {
+            return Err(error!(ErrorCode::SwapFailed));
+        }
+
+        let source_amount = read_token_amount(source)?
+            .checked_sub(amount_in)
+            .ok_or(error!(ErrorCode::SwapFailed))?;
+        set_token_amount(source, source_amount)?;
+        set_token_amount(destination, read_token_amount(destination)? + amount_out)?;
+
+        self.sol_reserve.set(self.sol_reserve.get() + amount_in);
+        self.donut_reserve.set(self.donut_reserve.get() - amount_out);
+        Ok(())
+    }
+}
+
+// Rent-exempt reserve of a 165 byte token account
+pub const TOKEN_ACCOUNT_RENT: u64 = 2_039_280;
+
+// Wallet and WSOL account balances behind a `SimulatedWsolAccount`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NativeAccountState {
+    pub wallet_lamports: u64,
+    pub lamports: u64, // Lamports of the WSOL account, rent included
+    pub amount: u64,   // Token amount, only updated by transfers and `sync_native`
+    pub transfers: u32,
+    pub syncs: u32,
+}
+
+// In-memory native token account following the SPL token program: lamport
+// transfers leave the token amount alone until `sync_native`, which sets it to
+// the lamports above the rent-exempt reserve. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct SimulatedWsolAccount(pub Rc<RefCell<NativeAccountState>>);
+
+impl SimulatedWsolAccount {
+    pub fn new(wallet_lamports: u64, held: u64) -> Self {
+        SimulatedWsolAccount(Rc::new(RefCell::new(NativeAccountState {
+            wallet_lamports,
+            lamports: TOKEN_ACCOUNT_RENT + held,
+            amount: held,
+            ..Default::default()
+        })))
+    }
+
+    pub fn state(&self) -> NativeAccountState {
+        *self.0.borrow()
+    }
+
+    // Lamports sent straight to the account without syncing
+    pub fn airdrop(&self, lamports: u64) {
+        self.0.borrow_mut().lamports += lamports;
+    }
+
+    // Token transfer out of the account, like a swap or vault deposit spending WSOL
+    pub fn spend(&self, amount: u64) -> Result<()> {
+        let mut state = self.0.borrow_mut();
+        state.amount = state.amount.checked_sub(amount).ok_or(error!(ErrorCode::SwapFailed))?;
+        state.lamports -= amount;
+        Ok(())
+    }
+}
+
+impl NativeTokenAccount for SimulatedWsolAccount {
+    fn transfer_lamports(&mut self, amount: u64) -> Result<()> {
+        let mut state = self.0.borrow_mut();
+        state.wallet_lamports = state.wallet_lamports.checked_sub(amount).ok_or(error!(ErrorCode::WrapSolFailed))?;
+        state.lamports += amount;
+        state.transfers += 1;
+        Ok(())
+    }
+
+    fn sync_native(&mut self) -> Result<()> {
+        let mut state = self.0.borrow_mut();
+        let amount = state.lamports - TOKEN_ACCOUNT_RENT;
+        // The token program never lowers the amount on a sync
+        if amount < state.amount {
+            return Err(error!(ErrorCode::WrapSolFailed));
+        }
+        state.amount = amount;
+        state.syncs += 1;
+        Ok(())
+    }
+
+    fn amount(&self) -> Result<u64> {
+        Ok(self.0.borrow().amount)
+    }
+}
+
+// Rent-exempt reserve of a data-less system account, what program vaults keep
+pub const VAULT_RENT: u64 = 890_880;
+
+// Syscall stubs carrying out the system transfers, WSOL syncs and burns the program
+// invokes, directly on the accounts passed along. Other CPIs succeed untouched.
+struct TokenRuntime;
+
+impl SyscallStubs for TokenRuntime {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        let account = |index: usize| {
+            account_infos
+                .iter()
+                .find(|info| *info.key == instruction.accounts[index].pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+
+        if instruction.program_id == System::id() && instruction.data[..4] == 2u32.to_le_bytes() {
+            let lamports = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let (from, to) = (account(0)?, account(1)?);
+            let from_lamports = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+            let to_lamports = to.lamports() + lamports;
+            set_lamports(from, from_lamports);
+            set_lamports(to, to_lamports);
+        } else if instruction.program_id == spl_token::ID {
+            match TokenInstruction::unpack(&instruction.data)? {
+                TokenInstruction::SyncNative => {
+                    let native = account(0)?;
+                    set_token_amount(native, native.lamports() - TOKEN_ACCOUNT_RENT)?;
+                }
+                TokenInstruction::Burn { amount } => {
+                    let source = account(0)?;
+                    let balance = read_token_amount(source)?.checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+                    set_token_amount(source, balance)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// Route the program's CPIs through `TokenRuntime` for the rest of the test binary
+pub fn install_token_runtime() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(TokenRuntime));
+    });
+}
+
+// Program vault holding `held` lamports above its rent, with an empty WSOL account
+// and the program burn account. Needs `install_token_runtime` to move anything.
+pub fn vault_burn_accounts(held: u64) -> VaultBurnAccounts<'static> {
+    let vault = Pubkey::new_unique();
+    let token_mint = Pubkey::new_unique();
+    let burn_account = Pubkey::new_unique();
+
+    let vault_info = program_account(vault, System::id(), false, true, vec![]);
+    set_lamports(&vault_info, VAULT_RENT + held);
+    let vault_wsol_account = program_account(
+        Pubkey::new_unique(),
+        spl_token::ID,
+        false,
+        true,
+        build_token_account(&spl_token::native_mint::ID, &vault, 0),
+    );
+    set_lamports(&vault_wsol_account, TOKEN_ACCOUNT_RENT);
+
+    VaultBurnAccounts {
+        vault: vault_info,
+        vault_wsol_account,
+        burn_account: program_account(burn_account, spl_token::ID, false, true, build_token_account(&token_mint, &burn_account, 0)),
+        burn_account_bump: 255,
+        token_program: executable_account(spl_token::ID),
+        token_mint: program_account(token_mint, spl_token::ID, false, true, vec![0u8; 82]),
+    }
+}
+
+// Tracker whose EMA and TWAP settled on `price` over the two periods before `now`
+pub fn settled_tracker(price: u128, now: i64) -> PriceTracker {
+    let mut tracker = PriceTracker::default();
+    tracker.update(price, now - 2 * EMA_PERIOD);
+    tracker.update(price, now - EMA_PERIOD);
+    tracker
+}
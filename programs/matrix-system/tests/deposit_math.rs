@@ -0,0 +1,103 @@
+use matrix_system::decimal::{usd_to_lamports, Decimal, Rounding};
+use matrix_system::minimum_deposit_lamports;
+use matrix_system::oracle::OraclePrice;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use proptest::prelude::*;
+
+const MINIMUM_USD_DEPOSIT: u64 = 10_00000000;
+
+fn to_rational(decimal: &Decimal) -> BigRational {
+    BigRational::new(BigInt::from(decimal.value), BigInt::from(10).pow(decimal.decimals))
+}
+
+// Reference conversion: ceil(usd / price * 10^9) computed with exact rationals
+fn reference_lamports(usd: &Decimal, price: &Decimal) -> BigInt {
+    let lamports = to_rational(usd) / to_rational(price) * BigRational::from_integer(BigInt::from(1_000_000_000u64));
+    lamports.ceil().to_integer()
+}
+
+fn price(price: i128, expo: i32) -> OraclePrice {
+    OraclePrice { price, expo, publish_time: 0 }
+}
+
+#[test]
+fn converts_minimum_deposit_at_round_prices() {
+    // $10 at $100/SOL is exactly 0.1 SOL
+    assert_eq!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(100_00000000, -8)).unwrap(), 100_000_000);
+    // Same price with a different exponent
+    assert_eq!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(100_000, -3)).unwrap(), 100_000_000);
+    assert_eq!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(1, 2)).unwrap(), 100_000_000);
+}
+
+#[test]
+fn rounds_minimum_deposit_up() {
+    // $10 at $3/SOL is 3.333... SOL
+    assert_eq!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(3_00000000, -8)).unwrap(), 3_333_333_334);
+}
+
+#[test]
+fn rejects_non_positive_price() {
+    assert!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(0, -8)).is_err());
+    assert!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(-1, -8)).is_err());
+}
+
+#[test]
+fn rejects_result_that_does_not_fit_in_lamports() {
+    // $10 at 10^-30 $/SOL does not fit in a u64
+    assert!(minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &price(1, -30)).is_err());
+}
+
+#[test]
+fn div_rounds_in_requested_direction() {
+    let one = Decimal::new(1, 0);
+    let three = Decimal::new(3, 0);
+
+    assert_eq!(one.checked_div(&three, 4, Rounding::Down), Some(Decimal::new(3333, 4)));
+    assert_eq!(one.checked_div(&three, 4, Rounding::Up), Some(Decimal::new(3334, 4)));
+    assert_eq!(Decimal::new(6, 0).checked_div(&three, 4, Rounding::Up), Some(Decimal::new(20000, 4)));
+}
+
+#[test]
+fn displays_decimal() {
+    assert_eq!(Decimal::new(15012345678, 8).to_string(), "150.12345678");
+    assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+}
+
+proptest! {
+    #[test]
+    fn usd_to_lamports_matches_rational_reference(
+        usd_value in 1i128..100_000_000_000_000,
+        usd_decimals in 0u32..=12,
+        price_value in 1i128..10_000_000_000_000_000,
+        price_decimals in 0u32..=12,
+    ) {
+        let usd = Decimal::new(usd_value, usd_decimals);
+        let sol_price = Decimal::new(price_value, price_decimals);
+        let expected = reference_lamports(&usd, &sol_price);
+
+        match usd_to_lamports(&usd, &sol_price) {
+            Some(lamports) => prop_assert_eq!(BigInt::from(lamports), expected),
+            None => prop_assert!(expected > BigInt::from(u64::MAX)),
+        }
+    }
+
+    #[test]
+    fn minimum_deposit_never_undershoots_usd_minimum(
+        price_value in 1i128..100_000_000_000_000,
+        expo in -12i32..=0,
+    ) {
+        let oracle_price = price(price_value, expo);
+        let sol_price = Decimal::from_price(price_value, expo).unwrap();
+        let minimum_usd = Decimal::new(i128::from(MINIMUM_USD_DEPOSIT), 8);
+
+        if let Ok(lamports) = minimum_deposit_lamports(MINIMUM_USD_DEPOSIT, &oracle_price) {
+            // Value of the deposit in USD is at least the minimum and less than one lamport above it
+            let deposit_usd = BigRational::from_integer(BigInt::from(lamports)) * to_rational(&sol_price)
+                / BigRational::from_integer(BigInt::from(1_000_000_000u64));
+            let one_lamport_usd = to_rational(&sol_price) / BigRational::from_integer(BigInt::from(1_000_000_000u64));
+            prop_assert!(deposit_usd >= to_rational(&minimum_usd));
+            prop_assert!(deposit_usd < to_rational(&minimum_usd) + one_lamport_usd);
+        }
+    }
+}
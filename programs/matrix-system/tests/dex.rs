@@ -0,0 +1,302 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token;
+use common::{build_token_account, build_token_swap, settled_tracker, MockDex};
+use matrix_system::dex::{
+    adapter, fair_donut_out, swap_for_donut, ConstantProductPool, DexAdapter, MeteoraDynamicAmm, SwapVenue, VenueAccounts,
+    METEORA_SWAP_DISCRIMINATOR, TOKEN_SWAP_SWAP_TAG,
+};
+use matrix_system::layout::TOKEN_SWAP_CONSTANT_PRODUCT_CURVE;
+use matrix_system::price_guard::pool_price;
+use matrix_system::price_tracker::PriceTracker;
+use matrix_system::quote::{SwapQuote, TokenSwapFees};
+
+const NOW: i64 = 1_700_000_000;
+
+// SPL token-swap defaults: 0.25% to liquidity providers and 0.05% to the owner
+const FEES: TokenSwapFees = TokenSwapFees {
+    trade_fee_numerator: 25,
+    trade_fee_denominator: 10_000,
+    owner_trade_fee_numerator: 5,
+    owner_trade_fee_denominator: 10_000,
+};
+
+// 250_000 DONUT against 100 SOL
+const DONUT_RESERVE: u64 = 250_000_000_000_000;
+const SOL_RESERVE: u64 = 100_000_000_000;
+const DONUT_MINT: Pubkey = Pubkey::new_from_array([7; 32]);
+const WSOL_MINT: Pubkey = spl_token::native_mint::ID;
+
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        TestAccount { key, owner, lamports: 1, data }
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+fn venue<'info>(infos: &[AccountInfo<'info>]) -> VenueAccounts<'info> {
+    VenueAccounts {
+        pool: infos[0].clone(),
+        a_vault: infos[1].clone(),
+        a_vault_lp: infos[2].clone(),
+        a_vault_lp_mint: infos[3].clone(),
+        a_token_vault: infos[4].clone(),
+        b_vault: infos[5].clone(),
+        b_vault_lp: infos[6].clone(),
+        b_vault_lp_mint: infos[7].clone(),
+        b_token_vault: infos[8].clone(),
+        protocol_token_fee: infos[9].clone(),
+        vault_program: infos[10].clone(),
+        amm_program: infos[11].clone(),
+        token_program: infos[12].clone(),
+    }
+}
+
+// Token-swap pool laid out in the venue slots, with its swap state built by `swap_state`
+fn constant_product_accounts(swap_state: impl Fn(&Pubkey, &Pubkey, &Pubkey, &Pubkey, u8) -> Vec<u8>) -> Vec<TestAccount> {
+    let swap_program = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let (authority, bump) = Pubkey::find_program_address(&[pool.as_ref()], &swap_program);
+    let (donut_reserve, sol_reserve) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (pool_mint, fee_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let unused = || TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]);
+
+    vec![
+        TestAccount::new(pool, swap_program, swap_state(&donut_reserve, &sol_reserve, &pool_mint, &fee_account, bump)),
+        TestAccount::new(authority, Pubkey::default(), vec![]),
+        unused(),
+        TestAccount::new(pool_mint, spl_token::ID, vec![]),
+        TestAccount::new(donut_reserve, spl_token::ID, build_token_account(&DONUT_MINT, &authority, DONUT_RESERVE)),
+        unused(),
+        unused(),
+        unused(),
+        TestAccount::new(sol_reserve, spl_token::ID, build_token_account(&WSOL_MINT, &authority, SOL_RESERVE)),
+        TestAccount::new(fee_account, spl_token::ID, vec![]),
+        unused(),
+        TestAccount::new(swap_program, Pubkey::default(), vec![]),
+        TestAccount::new(spl_token::ID, Pubkey::default(), vec![]),
+    ]
+}
+
+type SwapStateBuilder = Box<dyn Fn(&Pubkey, &Pubkey, &Pubkey, &Pubkey, u8) -> Vec<u8>>;
+
+fn token_swap_state(curve_type: u8) -> impl Fn(&Pubkey, &Pubkey, &Pubkey, &Pubkey, u8) -> Vec<u8> {
+    move |donut_reserve, sol_reserve, pool_mint, fee_account, bump| {
+        build_token_swap(donut_reserve, sol_reserve, &DONUT_MINT, &WSOL_MINT, pool_mint, fee_account, bump, FEES, curve_type)
+    }
+}
+
+#[test]
+fn token_swap_fees_are_both_taken_from_the_input() {
+    let quote = FEES.quote(SOL_RESERVE, DONUT_RESERVE, 1_000_000_000).unwrap();
+    assert_eq!(quote, SwapQuote { amount_out: 2_467_895_085_992, trade_fee: 3_000_000, protocol_fee: 500_000 });
+
+    // Zero fee rates charge nothing
+    let quote = TokenSwapFees::default().quote(SOL_RESERVE, DONUT_RESERVE, 1_000).unwrap();
+    assert_eq!((quote.trade_fee, quote.protocol_fee), (0, 0));
+
+    // A fee rate without a denominator cannot be quoted
+    let broken = TokenSwapFees { trade_fee_denominator: 0, ..FEES };
+    assert!(broken.quote(SOL_RESERVE, DONUT_RESERVE, 1_000).is_none());
+}
+
+#[test]
+fn constant_product_pool_quotes_from_its_reserves() {
+    let mut accounts = constant_product_accounts(token_swap_state(TOKEN_SWAP_CONSTANT_PRODUCT_CURVE));
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    let venue = venue(&infos);
+    let dex = adapter(SwapVenue::ConstantProduct, &venue, DONUT_MINT);
+
+    assert_eq!(dex.reserves(NOW).unwrap(), (DONUT_RESERVE, SOL_RESERVE));
+    assert_eq!(dex.quote_donut_out(1_000_000_000, NOW).unwrap(), 2_467_895_085_992);
+
+    // The same slots do not hold a Meteora pool
+    assert!(adapter(SwapVenue::MeteoraDynamicAmm, &venue, DONUT_MINT).reserves(NOW).is_err());
+}
+
+#[test]
+fn fair_quote_prices_the_pool_liquidity_at_the_twap() {
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let quoted = dex.quote_donut_out(1_000_000_000, NOW).unwrap();
+    let tracker = settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW);
+
+    // At the TWAP price the fair quote pays the same fees and impact as the live one
+    assert_eq!(fair_donut_out(&dex, &tracker, 1_000_000_000, NOW), Some(quoted));
+
+    // A buy earlier in the transaction lowers the live quote but not the fair one
+    dex.donut_reserve.set(DONUT_RESERVE * 4 / 5);
+    dex.sol_reserve.set(SOL_RESERVE * 5 / 4);
+    assert!(dex.quote_donut_out(1_000_000_000, NOW).unwrap() < quoted * 2 / 3);
+    let fair = fair_donut_out(&dex, &tracker, 1_000_000_000, NOW).unwrap();
+    assert!(fair.abs_diff(quoted) <= 1);
+
+    assert_eq!(fair_donut_out(&dex, &PriceTracker::default(), 1_000_000_000, NOW), None);
+}
+
+#[test]
+fn constant_product_pool_rejects_a_swap_state_for_other_accounts() {
+    let other = Pubkey::new_unique();
+    let cases: Vec<SwapStateBuilder> = vec![
+        // DONUT and WSOL on the wrong sides
+        Box::new(|donut_reserve, sol_reserve, pool_mint, fee_account, bump| {
+            build_token_swap(sol_reserve, donut_reserve, &WSOL_MINT, &DONUT_MINT, pool_mint, fee_account, bump, FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+        // A pair of other tokens
+        Box::new(move |donut_reserve, sol_reserve, pool_mint, fee_account, bump| {
+            build_token_swap(donut_reserve, sol_reserve, &other, &WSOL_MINT, pool_mint, fee_account, bump, FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+        Box::new(move |donut_reserve, sol_reserve, pool_mint, fee_account, bump| {
+            build_token_swap(donut_reserve, sol_reserve, &DONUT_MINT, &other, pool_mint, fee_account, bump, FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+        // Not on the constant-product curve
+        Box::new(token_swap_state(1)),
+        // Another pool's reserve
+        Box::new(move |donut_reserve, _, pool_mint, fee_account, bump| {
+            build_token_swap(donut_reserve, &other, &DONUT_MINT, &WSOL_MINT, pool_mint, fee_account, bump, FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+        // Another pool mint or fee account
+        Box::new(move |donut_reserve, sol_reserve, _, fee_account, bump| {
+            build_token_swap(donut_reserve, sol_reserve, &DONUT_MINT, &WSOL_MINT, &other, fee_account, bump, FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+        Box::new(move |donut_reserve, sol_reserve, pool_mint, _, bump| {
+            build_token_swap(donut_reserve, sol_reserve, &DONUT_MINT, &WSOL_MINT, pool_mint, &other, bump, FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+        // Authority derived with another bump
+        Box::new(|donut_reserve, sol_reserve, pool_mint, fee_account, bump| {
+            build_token_swap(donut_reserve, sol_reserve, &DONUT_MINT, &WSOL_MINT, pool_mint, fee_account, bump.wrapping_sub(1), FEES, TOKEN_SWAP_CONSTANT_PRODUCT_CURVE)
+        }),
+    ];
+
+    for swap_state in cases {
+        let mut accounts = constant_product_accounts(swap_state);
+        let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+        let venue = venue(&infos);
+        assert!(adapter(SwapVenue::ConstantProduct, &venue, DONUT_MINT).quote_donut_out(1_000_000_000, NOW).is_err());
+    }
+
+    // A DONUT reserve holding another token, or a pool for another DONUT mint
+    let mut accounts = constant_product_accounts(token_swap_state(TOKEN_SWAP_CONSTANT_PRODUCT_CURVE));
+    accounts[4].data = build_token_account(&other, &accounts[1].key, DONUT_RESERVE);
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    let err = adapter(SwapVenue::ConstantProduct, &venue(&infos), DONUT_MINT).reserves(NOW).unwrap_err();
+    assert!(err.to_string().contains("InvalidSwapVenueAccount"));
+
+    let mut accounts = constant_product_accounts(token_swap_state(TOKEN_SWAP_CONSTANT_PRODUCT_CURVE));
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    assert!(adapter(SwapVenue::ConstantProduct, &venue(&infos), other).reserves(NOW).is_err());
+
+    // Swap state owned by another program
+    let mut accounts = constant_product_accounts(token_swap_state(TOKEN_SWAP_CONSTANT_PRODUCT_CURVE));
+    accounts[0].owner = Pubkey::new_unique();
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    assert!(adapter(SwapVenue::ConstantProduct, &venue(&infos), DONUT_MINT).reserves(NOW).is_err());
+}
+
+#[test]
+fn constant_product_swap_instruction_matches_token_swap() {
+    let mut accounts = constant_product_accounts(token_swap_state(TOKEN_SWAP_CONSTANT_PRODUCT_CURVE));
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    let venue = venue(&infos);
+    let (user, source, destination) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let ix = ConstantProductPool { accounts: &venue, donut_mint: DONUT_MINT }.swap_instruction(&user, &source, &destination, 1_000, 990);
+
+    assert_eq!(ix.program_id, *venue.amm_program.key);
+    assert_eq!(ix.data[0], TOKEN_SWAP_SWAP_TAG);
+    assert_eq!(ix.data[1..9], 1_000u64.to_le_bytes());
+    assert_eq!(ix.data[9..17], 990u64.to_le_bytes());
+
+    let keys: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        keys,
+        vec![
+            *venue.pool.key,
+            *venue.a_vault.key,
+            user,
+            source,
+            *venue.b_token_vault.key,
+            *venue.a_token_vault.key,
+            destination,
+            *venue.a_vault_lp_mint.key,
+            *venue.protocol_token_fee.key,
+            spl_token::ID,
+        ]
+    );
+    assert!(ix.accounts[2].is_signer);
+    assert!(!ix.accounts[0].is_writable && !ix.accounts[1].is_writable);
+}
+
+#[test]
+fn meteora_swap_instruction_matches_the_amm_layout() {
+    let mut accounts: Vec<_> = (0..13).map(|_| TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![])).collect();
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    let venue = venue(&infos);
+    let (user, source, destination) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let ix = MeteoraDynamicAmm { accounts: &venue }.swap_instruction(&user, &source, &destination, 1_000, 990);
+
+    assert_eq!(ix.program_id, *venue.amm_program.key);
+    assert_eq!(ix.data[..8], METEORA_SWAP_DISCRIMINATOR);
+    assert_eq!(ix.data[8..16], 1_000u64.to_le_bytes());
+    assert_eq!(ix.data[16..24], 990u64.to_le_bytes());
+
+    assert_eq!(ix.accounts.len(), 15);
+    assert_eq!(ix.accounts[0].pubkey, *venue.pool.key);
+    assert_eq!(ix.accounts[1].pubkey, source);
+    assert_eq!(ix.accounts[2].pubkey, destination);
+    assert_eq!(ix.accounts[11].pubkey, *venue.protocol_token_fee.key);
+    assert_eq!(ix.accounts[12].pubkey, user);
+    assert!(ix.accounts[12].is_signer);
+    assert_eq!(ix.accounts[14].pubkey, *venue.token_program.key);
+}
+
+fn user_accounts(wsol_amount: u64, donut_amount: u64) -> [TestAccount; 3] {
+    let user = Pubkey::new_unique();
+    [
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::new(Pubkey::new_unique(), spl_token::ID, build_token_account(&spl_token::native_mint::ID, &user, wsol_amount)),
+        TestAccount::new(Pubkey::new_unique(), spl_token::ID, build_token_account(&Pubkey::new_unique(), &user, donut_amount)),
+    ]
+}
+
+#[test]
+fn swap_returns_the_donut_that_reached_the_destination() {
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let expected = dex.quote_donut_out(1_000_000_000, NOW).unwrap();
+
+    // DONUT already in the account is not counted as swap output
+    let [mut user, mut wsol, mut donut] = user_accounts(1_000_000_000, 7);
+    let received = swap_for_donut(&dex, &user.info(), &wsol.info(), &donut.info(), 1_000_000_000, expected, &[]).unwrap();
+
+    assert_eq!(received, expected);
+    assert_eq!(dex.reserves(NOW).unwrap(), (DONUT_RESERVE - expected, SOL_RESERVE + 1_000_000_000));
+}
+
+#[test]
+fn short_swap_fails_whichever_side_catches_it() {
+    let mut dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    dex.shortfall = 1;
+    let expected = dex.quote_donut_out(1_000_000_000, NOW).unwrap();
+
+    // The venue enforces the minimum itself
+    let [mut user, mut wsol, mut donut] = user_accounts(1_000_000_000, 0);
+    let err = swap_for_donut(&dex, &user.info(), &wsol.info(), &donut.info(), 1_000_000_000, expected, &[]).unwrap_err();
+    assert!(err.to_string().contains("SwapFailed"));
+
+    // A venue that lets a short swap through is caught by the balance check
+    dex.ignores_minimum = true;
+    let [mut user, mut wsol, mut donut] = user_accounts(1_000_000_000, 0);
+    let err = swap_for_donut(&dex, &user.info(), &wsol.info(), &donut.info(), 1_000_000_000, expected, &[]).unwrap_err();
+    assert!(err.to_string().contains("SlippageExceeded"));
+}
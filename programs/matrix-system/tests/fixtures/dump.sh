@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+# Dumps the live accounts decoded by tests/layout.rs, tests/oracle.rs and
+# tests/chainlink_feed.rs. Needs the Solana CLI and RPC access to the cluster.
+set -euo pipefail
+cd "$(dirname "$0")"
+
+URL="${SOLANA_URL:-https://api.devnet.solana.com}"
+
+dump() {
+    solana account "$1" --url "$URL" --output-file "$2"
+}
+
+# Meteora dynamic AMM DONUT/WSOL pool (constant product) and its WSOL vault
+dump FrQ5KsAgjCe3FFg6ZENri8feDft54tgnATxyffcasuxU meteora_pool_constant_product.bin
+dump FERjPVNEa7Udq8CEv68h6tPL46Tq7ieE49HrE2wea3XT meteora_vault.bin
+dump BvoAjwEDhpLzs3jtu4H72j96ShKT5rvZE9RP1vgpfSM vault_lp_mint.bin
+dump HJNs8hPTzs9i6AVFkRDDMFVEkrrUoV7H7LDZHdCWvxn7 vault_lp_token_account.bin
+
+# SOL/USD price sources
+dump 99B2bTijsU6f1GCT73HmdR7HCFFjGMBcPZY6jZ96ynrR chainlink_sol_usd_feed.bin
+dump J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix pyth_sol_usd_price.bin
@@ -0,0 +1,166 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::build_protocol_config;
+use matrix_system::buyback::BurnMode;
+use matrix_system::config::ProtocolConfig;
+use matrix_system::dex::SwapVenue;
+use matrix_system::governance::{check_timelock, timelock_eta, ConfigChange, PendingConfigChange, GOVERNANCE_DELAY};
+use matrix_system::matrix::SlotAction;
+use matrix_system::oracle::{OracleConfig, OracleKind, OracleSource, StalePricePolicy};
+use matrix_system::pause::PauseFlags;
+use matrix_system::pending_burn::SwapFailurePolicy;
+use matrix_system::sink::TerminalSinkPolicy;
+use matrix_system::ProgramState;
+
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn timelock_expires_after_governance_delay() {
+    let eta = timelock_eta(NOW);
+
+    assert_eq!(eta, NOW + GOVERNANCE_DELAY);
+    assert!(check_timelock(eta, NOW).is_err());
+    assert!(check_timelock(eta, eta - 1).is_err());
+    assert!(check_timelock(eta, eta).is_ok());
+    assert!(check_timelock(eta, eta + 1).is_ok());
+}
+
+#[test]
+fn timelock_eta_saturates() {
+    assert_eq!(timelock_eta(i64::MAX - 1), i64::MAX);
+}
+
+fn program_state() -> ProgramState {
+    ProgramState {
+        owner: Pubkey::new_unique(),
+        multisig_treasury: Pubkey::new_unique(),
+        next_upline_id: 1,
+        next_chain_id: 1,
+        is_locked: false,
+        stale_price_policy: StalePricePolicy::SecondaryMedian,
+        admin_sol_price: 0,
+        admin_price_expires_at: 0,
+        pending_owner: None,
+        ownership_transfer_eta: 0,
+        pause_flags: PauseFlags::default(),
+        last_mint_amount: 0,
+    }
+}
+
+#[test]
+fn applies_queued_change_to_config() {
+    let mut queued = build_protocol_config();
+    queued.minimum_usd_deposit = 25_00000000;
+    queued.max_price_feed_age = 3600;
+    queued.max_upline_depth = 4;
+    queued.slot_actions = vec![SlotAction::Burn, SlotAction::PayTreasury];
+    queued.max_slippage_bps = 100;
+    queued.max_price_deviation_bps = 300;
+    queued.swap_venue = SwapVenue::ConstantProduct;
+    queued.burn_mode = BurnMode::Batched;
+    queued.swap_failure_policy = SwapFailurePolicy::Escrow;
+    queued.terminal_sink_policy = TerminalSinkPolicy::Reserve;
+    let treasury = Pubkey::new_unique();
+
+    let pending = PendingConfigChange {
+        change: ConfigChange::Protocol { config: Box::new(queued.clone()), multisig_treasury: treasury },
+        eta: timelock_eta(NOW),
+    };
+    assert!(pending.change.validate(pending.eta).is_ok());
+
+    let mut state = program_state();
+    let mut config: ProtocolConfig = build_protocol_config();
+    let mut oracles = OracleConfig { sources: vec![] };
+    pending.change.apply(&mut state, &mut config, &mut oracles, pending.eta).unwrap();
+
+    assert_eq!(config.addresses, queued.addresses);
+    assert_eq!(config.minimum_usd_deposit, 25_00000000);
+    assert_eq!(config.max_price_feed_age, 3600);
+    assert_eq!(config.max_upline_depth, 4);
+    assert_eq!(config.slot_actions, vec![SlotAction::Burn, SlotAction::PayTreasury]);
+    assert_eq!(config.max_slippage_bps, 100);
+    assert_eq!(config.max_price_deviation_bps, 300);
+    assert_eq!(config.swap_venue, SwapVenue::ConstantProduct);
+    assert_eq!(config.burn_mode, BurnMode::Batched);
+    assert_eq!(config.swap_failure_policy, SwapFailurePolicy::Escrow);
+    assert_eq!(config.terminal_sink_policy, TerminalSinkPolicy::Reserve);
+    assert_eq!(state.multisig_treasury, treasury);
+}
+
+#[test]
+fn rejects_invalid_protocol_config_at_queue_time() {
+    let mut config = build_protocol_config();
+    config.max_upline_depth = 0;
+
+    let change = ConfigChange::Protocol { config: Box::new(config), multisig_treasury: Pubkey::new_unique() };
+    assert!(change.validate(timelock_eta(NOW)).is_err());
+}
+
+#[test]
+fn oracle_and_price_policy_changes_go_through_the_queue() {
+    let eta = timelock_eta(NOW);
+    let mut state = program_state();
+    let mut config = build_protocol_config();
+    let mut oracles = OracleConfig { sources: vec![] };
+
+    let source = OracleSource {
+        kind: OracleKind::Pyth,
+        program_id: Pubkey::new_unique(),
+        account: Pubkey::new_unique(),
+        quote_decimals: 0,
+    };
+    let change = ConfigChange::Oracles { sources: vec![source] };
+    change.validate(eta).unwrap();
+    change.apply(&mut state, &mut config, &mut oracles, eta).unwrap();
+    assert_eq!(oracles.sources, vec![source]);
+    assert!(ConfigChange::Oracles { sources: vec![] }.validate(eta).is_err());
+
+    let change = ConfigChange::StalePricePolicy { policy: StalePricePolicy::AdminPrice };
+    change.apply(&mut state, &mut config, &mut oracles, eta).unwrap();
+    assert_eq!(state.stale_price_policy, StalePricePolicy::AdminPrice);
+}
+
+#[test]
+fn admin_price_must_outlive_the_timelock() {
+    let eta = timelock_eta(NOW);
+    let mut state = program_state();
+    let mut config = build_protocol_config();
+    let mut oracles = OracleConfig { sources: vec![] };
+
+    // Expires before the change could be executed
+    let expired = ConfigChange::AdminSolPrice { price: 150_00000000, expires_at: eta - 1 };
+    assert!(expired.validate(eta).is_err());
+    assert!(ConfigChange::AdminSolPrice { price: 0, expires_at: eta + 3600 }.validate(eta).is_err());
+
+    let change = ConfigChange::AdminSolPrice { price: 150_00000000, expires_at: eta + 3600 };
+    change.validate(eta).unwrap();
+
+    // Executed too late, the price has lapsed
+    assert!(change.apply(&mut state, &mut config, &mut oracles, eta + 3600).is_err());
+
+    change.apply(&mut state, &mut config, &mut oracles, eta).unwrap();
+    assert_eq!(state.admin_sol_price, 150_00000000);
+    assert_eq!(state.admin_price_expires_at, eta + 3600);
+}
+
+#[test]
+fn reserve_withdrawals_go_through_the_queue() {
+    let eta = timelock_eta(NOW);
+    let mut state = program_state();
+    let mut config = build_protocol_config();
+    let mut oracles = OracleConfig { sources: vec![] };
+
+    assert!(ConfigChange::ReserveWithdrawal { amount: 0 }.validate(eta).is_err());
+
+    let change = ConfigChange::ReserveWithdrawal { amount: 1_000_000_000 };
+    change.validate(eta).unwrap();
+    assert!(change.try_to_vec().unwrap().len() <= ConfigChange::SIZE);
+
+    // The lamports move in the instruction, the config accounts stay as they were
+    let treasury = state.multisig_treasury;
+    let before = config.try_to_vec().unwrap();
+    change.apply(&mut state, &mut config, &mut oracles, eta).unwrap();
+    assert_eq!(state.multisig_treasury, treasury);
+    assert_eq!(config.try_to_vec().unwrap(), before);
+}
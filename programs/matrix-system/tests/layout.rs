@@ -0,0 +1,242 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token;
+use matrix_system::get_vault_token_amount;
+use matrix_system::layout::{
+    read_mint_supply, read_token_amount, LockedProfitTracker, MintView, PoolView, TokenAccountView, VaultView,
+    LOCKED_PROFIT_DEGRADATION_DENOMINATOR, VAULT_LOCKED_PROFIT_TRACKER_OFFSET,
+};
+use matrix_system::quote::{CurveType, Depeg, DepegType, Pool, PoolFees, TokenMultiplier};
+
+// Account fixtures, see tests/fixtures/README.md
+const POOL_CONSTANT_PRODUCT: &[u8] = include_bytes!("fixtures/meteora_pool_constant_product.bin");
+const VAULT: &[u8] = include_bytes!("fixtures/meteora_vault.bin");
+const VAULT_LP_MINT: &[u8] = include_bytes!("fixtures/vault_lp_mint.bin");
+const VAULT_LP_TOKEN_ACCOUNT: &[u8] = include_bytes!("fixtures/vault_lp_token_account.bin");
+
+const NOW: i64 = 1_700_000_000;
+
+// Meteora's default: a harvest unlocks over six hours
+const SIX_HOURS: u64 = 6 * 60 * 60;
+const DEGRADATION: u64 = (LOCKED_PROFIT_DEGRADATION_DENOMINATOR / SIX_HOURS as u128) as u64;
+const HARVEST: u64 = 600_000_000_000;
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+const STABLE_CURVE: CurveType = CurveType::Stable {
+    amp: 100,
+    token_multiplier: TokenMultiplier { token_a_multiplier: 1_000, token_b_multiplier: 1, precision_factor: 9 },
+    depeg: Depeg { base_virtual_price: 0, base_cache_updated: 0, depeg_type: DepegType::None },
+    last_amp_updated_timestamp: 1_700_000_000,
+};
+
+// No DONUT pool runs the stable curve, so there is no stable pool to dump: the
+// constant-product pool fixture is re-encoded with a stable curve instead
+fn stable_pool_data() -> Vec<u8> {
+    let mut pool = Pool::from_account_data(POOL_CONSTANT_PRODUCT).unwrap();
+    pool.curve_type = STABLE_CURVE;
+
+    let mut data = POOL_CONSTANT_PRODUCT[..8].to_vec();
+    pool.serialize(&mut data).unwrap();
+    data.resize(data.len().max(POOL_CONSTANT_PRODUCT.len()), 0);
+    data
+}
+
+#[test]
+fn decodes_pool_fixture() {
+    let pool = PoolView::decode(POOL_CONSTANT_PRODUCT).unwrap();
+
+    assert_eq!(pool.lp_mint(), key(1));
+    assert_eq!(pool.token_a_mint(), key(2));
+    assert_eq!(pool.token_b_mint(), spl_token::native_mint::ID);
+    assert_eq!(pool.a_vault(), key(4));
+    assert_eq!(pool.b_vault(), key(5));
+    assert_eq!(pool.a_vault_lp(), key(6));
+    assert_eq!(pool.b_vault_lp(), key(7));
+    assert!(pool.enabled());
+    assert_eq!(
+        pool.fees(),
+        PoolFees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            protocol_trade_fee_numerator: 20,
+            protocol_trade_fee_denominator: 100,
+        }
+    );
+    assert_eq!(pool.curve_type().unwrap(), CurveType::ConstantProduct);
+}
+
+#[test]
+fn decodes_stable_curve_from_pool_data() {
+    let data = stable_pool_data();
+    let pool = PoolView::decode(&data).unwrap();
+
+    assert_eq!(pool.curve_type().unwrap(), STABLE_CURVE);
+}
+
+#[test]
+fn pool_view_agrees_with_the_full_pool_struct() {
+    let data = stable_pool_data();
+    let pool = Pool::from_account_data(&data).unwrap();
+    let view = PoolView::decode(&data).unwrap();
+
+    assert_eq!(pool.token_a_mint, view.token_a_mint());
+    assert_eq!(pool.b_vault_lp, view.b_vault_lp());
+    assert_eq!(pool.fees, view.fees());
+    assert_eq!(pool.curve_type, view.curve_type().unwrap());
+}
+
+#[test]
+fn decodes_vault_and_token_fixtures() {
+    let vault = VaultView::decode(VAULT).unwrap();
+    assert!(vault.enabled());
+    assert_eq!(vault.total_amount(), 5_000_000_000_000);
+    assert_eq!(vault.token_vault(), key(8));
+    assert_eq!(vault.token_mint(), key(2));
+    assert_eq!(vault.lp_mint(), key(9));
+
+    let mint = MintView::decode(VAULT_LP_MINT).unwrap();
+    assert_eq!(mint.supply(), 4_000_000_000_000);
+    assert_eq!(mint.decimals(), 9);
+
+    let token_account = TokenAccountView::decode(VAULT_LP_TOKEN_ACCOUNT).unwrap();
+    assert_eq!(token_account.mint(), key(9));
+    assert_eq!(token_account.owner(), key(19));
+    assert_eq!(token_account.amount(), 1_000_000_000_000);
+}
+
+#[test]
+fn rejects_wrong_discriminator_or_size() {
+    // A vault is not a pool and vice versa
+    assert!(PoolView::decode(VAULT).is_err());
+    assert!(VaultView::decode(POOL_CONSTANT_PRODUCT).is_err());
+
+    let mut data = POOL_CONSTANT_PRODUCT.to_vec();
+    data[0] ^= 0xff;
+    assert!(PoolView::decode(&data).is_err());
+
+    assert!(PoolView::decode(&POOL_CONSTANT_PRODUCT[..100]).is_err());
+    assert!(VaultView::decode(&VAULT[..200]).is_err());
+    assert!(MintView::decode(&VAULT_LP_MINT[..44]).is_err());
+    assert!(TokenAccountView::decode(&VAULT_LP_TOKEN_ACCOUNT[..72]).is_err());
+
+    // Uninitialized SPL accounts
+    let mut mint = VAULT_LP_MINT.to_vec();
+    mint[45] = 0;
+    assert!(MintView::decode(&mint).is_err());
+    let mut token_account = VAULT_LP_TOKEN_ACCOUNT.to_vec();
+    token_account[108] = 0;
+    assert!(TokenAccountView::decode(&token_account).is_err());
+}
+
+#[test]
+fn vault_token_amount_checks_owners() {
+    let vault_program = Pubkey::new_unique();
+    let (vault_key, lp_key, lp_mint_key) = (key(5), key(20), key(9));
+    let token_program = spl_token::ID;
+    let (mut vault_lamports, mut lp_lamports, mut mint_lamports) = (1, 1, 1);
+    let mut vault_data = VAULT.to_vec();
+    let mut lp_data = VAULT_LP_TOKEN_ACCOUNT.to_vec();
+    let mut mint_data = VAULT_LP_MINT.to_vec();
+
+    let vault = AccountInfo::new(&vault_key, false, false, &mut vault_lamports, &mut vault_data, &vault_program, false, 0);
+    let vault_lp = AccountInfo::new(&lp_key, false, false, &mut lp_lamports, &mut lp_data, &token_program, false, 0);
+    let vault_lp_mint = AccountInfo::new(&lp_mint_key, false, false, &mut mint_lamports, &mut mint_data, &token_program, false, 0);
+
+    // 1/4 of the LP supply of a vault holding 5_000_000_000_000
+    assert_eq!(get_vault_token_amount(&vault, &vault_lp, &vault_lp_mint, &vault_program, NOW).unwrap(), 1_250_000_000_000);
+    assert_eq!(read_token_amount(&vault_lp).unwrap(), 1_000_000_000_000);
+    assert_eq!(read_mint_supply(&vault_lp_mint).unwrap(), 4_000_000_000_000);
+
+    // Vault owned by another program
+    assert!(get_vault_token_amount(&vault, &vault_lp, &vault_lp_mint, &Pubkey::new_unique(), NOW).is_err());
+
+    // LP mint that is not the vault's
+    assert!(get_vault_token_amount(&vault, &vault_lp, &vault_lp, &vault_program, NOW).is_err());
+}
+
+#[test]
+fn spl_reads_reject_accounts_not_owned_by_the_token_program() {
+    let (lp_key, other_program) = (key(20), Pubkey::new_unique());
+    let mut lamports = 1;
+    let mut lp_data = VAULT_LP_TOKEN_ACCOUNT.to_vec();
+
+    let vault_lp = AccountInfo::new(&lp_key, false, false, &mut lamports, &mut lp_data, &other_program, false, 0);
+    assert!(read_token_amount(&vault_lp).is_err());
+}
+
+fn tracker() -> LockedProfitTracker {
+    LockedProfitTracker {
+        last_updated_locked_profit: HARVEST,
+        last_report: NOW as u64,
+        locked_profit_degradation: DEGRADATION,
+    }
+}
+
+// Vault fixture right after a harvest reported at NOW
+fn harvested_vault() -> Vec<u8> {
+    let mut data = VAULT.to_vec();
+    let offset = VAULT_LOCKED_PROFIT_TRACKER_OFFSET;
+    data[offset..offset + 8].copy_from_slice(&HARVEST.to_le_bytes());
+    data[offset + 8..offset + 16].copy_from_slice(&(NOW as u64).to_le_bytes());
+    data[offset + 16..offset + 24].copy_from_slice(&DEGRADATION.to_le_bytes());
+    data
+}
+
+#[test]
+fn locked_profit_degrades_linearly() {
+    let tracker = tracker();
+    let now = NOW as u64;
+
+    assert_eq!(tracker.calculate_locked_profit(now), Some(HARVEST));
+    assert_eq!(tracker.calculate_locked_profit(now + SIX_HOURS / 4), Some(450_000_000_960));
+    assert_eq!(tracker.calculate_locked_profit(now + SIX_HOURS / 2), Some(300_000_001_920));
+    assert_eq!(tracker.calculate_locked_profit(now + SIX_HOURS * 3 / 4), Some(150_000_002_880));
+    assert_eq!(tracker.calculate_locked_profit(now + SIX_HOURS), Some(3_840));
+    assert_eq!(tracker.calculate_locked_profit(now + SIX_HOURS + 1), Some(0));
+    assert_eq!(tracker.calculate_locked_profit(now + 30 * SIX_HOURS), Some(0));
+
+    // A clock behind the last report cannot be valued
+    assert_eq!(tracker.calculate_locked_profit(now - 1), None);
+}
+
+#[test]
+fn vault_reports_only_unlocked_amount() {
+    let data = harvested_vault();
+    let vault = VaultView::decode(&data).unwrap();
+    let now = NOW as u64;
+
+    assert_eq!(vault.locked_profit_tracker(), tracker());
+    assert_eq!(vault.total_amount(), 5_000_000_000_000);
+    assert_eq!(vault.get_unlocked_amount(now), Some(4_400_000_000_000));
+    assert_eq!(vault.get_unlocked_amount(now + SIX_HOURS / 2), Some(4_699_999_998_080));
+    assert_eq!(vault.get_unlocked_amount(now + SIX_HOURS + 1), Some(5_000_000_000_000));
+
+    assert_eq!(vault.get_amount_by_share(now, 1, 4), Some(1_100_000_000_000));
+    assert_eq!(vault.get_amount_by_share(now, 1, 0), Some(0));
+}
+
+#[test]
+fn lp_valuation_uses_the_clock() {
+    let vault_program = Pubkey::new_unique();
+    let (vault_key, lp_key, lp_mint_key) = (key(5), key(20), key(9));
+    let token_program = spl_token::ID;
+    let (mut vault_lamports, mut lp_lamports, mut mint_lamports) = (1, 1, 1);
+    let mut vault_data = harvested_vault();
+    let mut lp_data = VAULT_LP_TOKEN_ACCOUNT.to_vec();
+    let mut mint_data = VAULT_LP_MINT.to_vec();
+
+    let vault = AccountInfo::new(&vault_key, false, false, &mut vault_lamports, &mut vault_data, &vault_program, false, 0);
+    let vault_lp = AccountInfo::new(&lp_key, false, false, &mut lp_lamports, &mut lp_data, &token_program, false, 0);
+    let vault_lp_mint = AccountInfo::new(&lp_mint_key, false, false, &mut mint_lamports, &mut mint_data, &token_program, false, 0);
+
+    let value_at = |time: i64| get_vault_token_amount(&vault, &vault_lp, &vault_lp_mint, &vault_program, time);
+
+    // The pool holds a quarter of the LP supply
+    assert_eq!(value_at(NOW).unwrap(), 1_100_000_000_000);
+    assert_eq!(value_at(NOW + SIX_HOURS as i64 / 2).unwrap(), 1_174_999_999_520);
+    assert_eq!(value_at(NOW + SIX_HOURS as i64 + 1).unwrap(), 1_250_000_000_000);
+    assert!(value_at(NOW - 1).is_err());
+    assert!(value_at(-1).is_err());
+}
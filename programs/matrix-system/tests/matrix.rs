@@ -0,0 +1,148 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::AnchorSerialize;
+use common::build_protocol_config;
+use matrix_system::airdrop::{UserWeekData, AIRDROP_WEEKS};
+use matrix_system::config::ProtocolConfig;
+use matrix_system::matrix::{slot_action, validate_slot_actions, SlotAction, DEFAULT_SLOT_ACTIONS, MAX_MATRIX_SLOTS};
+use matrix_system::{ProgramState, ReferralChain, UserAccount};
+
+#[test]
+fn default_table_is_the_three_slot_matrix() {
+    assert!(validate_slot_actions(&DEFAULT_SLOT_ACTIONS).is_ok());
+    assert_eq!(build_protocol_config().matrix_width(), 3);
+}
+
+#[test]
+fn rejects_empty_and_oversized_tables() {
+    assert!(validate_slot_actions(&[]).is_err());
+    assert!(validate_slot_actions(&[SlotAction::Burn; MAX_MATRIX_SLOTS]).is_ok());
+    assert!(validate_slot_actions(&[SlotAction::Burn; MAX_MATRIX_SLOTS + 1]).is_err());
+}
+
+#[test]
+fn pay_reserved_must_complete_the_matrix() {
+    let table = [SlotAction::Reserve, SlotAction::PayReserved, SlotAction::Burn];
+    assert!(validate_slot_actions(&table).is_err());
+
+    let table = [SlotAction::Burn, SlotAction::Reserve, SlotAction::DepositToPool, SlotAction::PayReserved];
+    assert!(validate_slot_actions(&table).is_ok());
+}
+
+#[test]
+fn reserve_needs_a_payout_slot() {
+    assert!(validate_slot_actions(&[SlotAction::Burn, SlotAction::Reserve]).is_err());
+    assert!(validate_slot_actions(&[SlotAction::Reserve, SlotAction::PayTreasury]).is_err());
+}
+
+#[test]
+fn mint_needs_a_payout_slot() {
+    assert!(validate_slot_actions(&[SlotAction::Mint, SlotAction::Burn]).is_err());
+    assert!(validate_slot_actions(&[SlotAction::Mint, SlotAction::PayReserved]).is_ok());
+}
+
+#[test]
+fn only_pay_reserved_forwards_the_deposit() {
+    let actions = [
+        SlotAction::Burn,
+        SlotAction::Reserve,
+        SlotAction::PayReserved,
+        SlotAction::PayTreasury,
+        SlotAction::DepositToPool,
+        SlotAction::Mint,
+    ];
+
+    for action in actions {
+        assert_eq!(action.forwards_deposit(), action == SlotAction::PayReserved);
+    }
+}
+
+#[test]
+fn slot_index_past_the_table_uses_the_last_action() {
+    assert_eq!(slot_action(&DEFAULT_SLOT_ACTIONS, 0), SlotAction::Burn);
+    assert_eq!(slot_action(&DEFAULT_SLOT_ACTIONS, 2), SlotAction::PayReserved);
+    assert_eq!(slot_action(&DEFAULT_SLOT_ACTIONS, 3), SlotAction::PayReserved);
+}
+
+#[test]
+fn chain_completes_at_configured_width() {
+    for width in 1..=MAX_MATRIX_SLOTS {
+        let mut chain = ReferralChain::new(7);
+
+        for expected_idx in 0..width - 1 {
+            let (slot_idx, completed) = chain.fill_next_slot(Pubkey::new_unique(), width, 8).unwrap();
+            assert_eq!(slot_idx, expected_idx);
+            assert!(!completed);
+            assert_eq!(chain.id, 7);
+        }
+
+        let (slot_idx, completed) = chain.fill_next_slot(Pubkey::new_unique(), width, 8).unwrap();
+        assert_eq!(slot_idx, width - 1);
+        assert!(completed);
+        assert_eq!(chain.id, 8);
+        assert_eq!(chain.filled_slots, 0);
+        assert!(chain.slots.iter().all(Option::is_none));
+    }
+}
+
+#[test]
+fn chain_wider_than_a_shrunk_table_completes_on_next_fill() {
+    let mut chain = ReferralChain::new(1);
+    for _ in 0..3 {
+        chain.fill_next_slot(Pubkey::new_unique(), 4, 2).unwrap();
+    }
+
+    let (slot_idx, completed) = chain.fill_next_slot(Pubkey::new_unique(), 2, 2).unwrap();
+    assert_eq!(slot_idx, 3);
+    assert!(completed);
+}
+
+#[test]
+fn account_sizes_fit_the_widest_matrix() {
+    let mut config = build_protocol_config();
+    config.slot_actions = vec![SlotAction::Burn; MAX_MATRIX_SLOTS];
+    assert_eq!(config.try_to_vec().unwrap().len(), ProtocolConfig::SIZE);
+
+    let mut user = UserAccount {
+        upline: matrix_system::ReferralUpline {
+            id: 1,
+            depth: 1,
+            upline: vec![
+                matrix_system::UplineEntry { pda: Pubkey::new_unique(), wallet: Pubkey::new_unique() };
+                matrix_system::MAX_UPLINE_DEPTH
+            ],
+        },
+        referrer: Some(Pubkey::new_unique()),
+        cascade_lock: Some(Pubkey::new_unique()),
+        ..Default::default()
+    };
+    user.chain.slots = [Some(Pubkey::new_unique()); MAX_MATRIX_SLOTS];
+    user.weekly_matrices = (1..=AIRDROP_WEEKS as u8)
+        .map(|week_number| UserWeekData { week_number, matrices_completed: 1 })
+        .collect();
+    assert_eq!(user.try_to_vec().unwrap().len(), UserAccount::SIZE);
+}
+
+#[test]
+fn rejects_accounts_with_the_pre_upgrade_layout() {
+    let key = Pubkey::new_unique();
+    let owner = matrix_system::ID;
+    let mut lamports = 1;
+
+    // Pre-upgrade UserAccount: three matrix slots and no reserved_tokens / airdrop / claim fields
+    let legacy_size = 8 + 1 + 33 + 32 + (4 + 1 + 4 + matrix_system::MAX_UPLINE_DEPTH * 64) + (4 + 3 * 33 + 1) + 8;
+    let mut data = vec![0u8; legacy_size];
+    let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+    let err = UserAccount::check_layout(&info).unwrap_err();
+    assert!(err.to_string().contains("OutdatedAccountLayout"));
+
+    let mut data = vec![0u8; 8 + UserAccount::SIZE];
+    let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+    assert!(UserAccount::check_layout(&info).is_ok());
+
+    // Pre-upgrade ProgramState stopped after `is_locked` and no longer deserializes
+    let mut legacy_state = ProgramState::DISCRIMINATOR.to_vec();
+    legacy_state.extend_from_slice(&[0u8; 32 + 32 + 4 + 4 + 1]);
+    assert!(ProgramState::try_deserialize(&mut legacy_state.as_slice()).is_err());
+}
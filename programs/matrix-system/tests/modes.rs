@@ -0,0 +1,204 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::build_protocol_config;
+use matrix_system::airdrop::AirdropState;
+use matrix_system::matrix::{
+    slot_action, validate_slot_actions, SlotAction, POOL_DEPOSIT_AND_MINT_SLOT_ACTIONS, SWAP_AND_BURN_SLOT_ACTIONS,
+};
+use matrix_system::{limit_mint_amount, ReferralChain, UserAccount};
+
+const DEPOSIT: u64 = 100_000_000;
+const DONUT_PER_DEPOSIT: u64 = 5_000;
+const NOW: i64 = 1_700_000_000;
+
+// Deployment modes selected through the protocol config, each run through the same suite
+struct Mode {
+    name: &'static str,
+    slot_actions: &'static [SlotAction],
+    airdrop: bool,
+}
+
+const MODES: [Mode; 3] = [
+    Mode { name: "swap-and-burn", slot_actions: &SWAP_AND_BURN_SLOT_ACTIONS, airdrop: false },
+    Mode { name: "pool-deposit-and-mint", slot_actions: &POOL_DEPOSIT_AND_MINT_SLOT_ACTIONS, airdrop: false },
+    Mode { name: "swap-and-burn-with-airdrop", slot_actions: &SWAP_AND_BURN_SLOT_ACTIONS, airdrop: true },
+];
+
+// Where the deposits of one matrix ended up
+#[derive(Default)]
+struct Ledger {
+    burned: u64,
+    pooled: u64,
+    to_treasury: u64,
+    paid_to_owner: u64,
+    last_mint_amount: u64,
+    minted: u64,
+}
+
+// Fill the base user's matrix once, applying the slot actions the way registration does
+fn fill_matrix(mode: &Mode, owner: &mut UserAccount, airdrop: &mut AirdropState) -> Ledger {
+    let mut ledger = Ledger::default();
+    let width = mode.slot_actions.len();
+
+    for _ in 0..width {
+        let action = slot_action(mode.slot_actions, owner.chain.filled_slots as usize);
+        let forwarded = match action {
+            SlotAction::Burn => {
+                ledger.burned += DEPOSIT;
+                false
+            }
+            SlotAction::DepositToPool => {
+                ledger.pooled += DEPOSIT;
+                false
+            }
+            SlotAction::PayTreasury => {
+                ledger.to_treasury += DEPOSIT;
+                false
+            }
+            SlotAction::Reserve => {
+                owner.reserved_sol += DEPOSIT;
+                false
+            }
+            SlotAction::Mint => {
+                owner.reserved_sol += DEPOSIT;
+                let minted = limit_mint_amount(ledger.last_mint_amount, DONUT_PER_DEPOSIT);
+                ledger.last_mint_amount = minted;
+                ledger.minted += minted;
+                owner.reserved_tokens += minted;
+                false
+            }
+            SlotAction::PayReserved => {
+                ledger.paid_to_owner += owner.release_reserved_sol().unwrap();
+                owner.release_reserved_tokens().unwrap();
+                true
+            }
+        };
+
+        let (_, completed) = owner.chain.fill_next_slot(Pubkey::new_unique(), width, 2).unwrap();
+        if completed {
+            airdrop.record_matrix_completion(owner).unwrap();
+        }
+
+        // A base user has no upline, so a forwarded deposit is burned
+        if forwarded {
+            assert!(completed, "{}: deposit forwarded before the matrix completed", mode.name);
+            ledger.burned += DEPOSIT;
+        }
+    }
+
+    ledger
+}
+
+fn setup(mode: &Mode) -> (UserAccount, AirdropState) {
+    let owner = UserAccount {
+        is_registered: true,
+        chain: ReferralChain::new(1),
+        ..Default::default()
+    };
+
+    let mut airdrop = AirdropState::default();
+    if mode.airdrop {
+        airdrop.start(NOW);
+    }
+    (owner, airdrop)
+}
+
+#[test]
+fn every_mode_has_a_valid_slot_table() {
+    for mode in &MODES {
+        assert!(validate_slot_actions(mode.slot_actions).is_ok(), "{}", mode.name);
+
+        let mut config = build_protocol_config();
+        config.slot_actions = mode.slot_actions.to_vec();
+        assert_eq!(config.matrix_width(), mode.slot_actions.len(), "{}", mode.name);
+    }
+}
+
+#[test]
+fn every_mode_accounts_for_all_deposits() {
+    for mode in &MODES {
+        let (mut owner, mut airdrop) = setup(mode);
+        let ledger = fill_matrix(mode, &mut owner, &mut airdrop);
+
+        let total = DEPOSIT * mode.slot_actions.len() as u64;
+        assert_eq!(
+            ledger.burned + ledger.pooled + ledger.to_treasury + ledger.paid_to_owner,
+            total,
+            "{}",
+            mode.name
+        );
+        assert_eq!(owner.reserved_sol, 0, "{}", mode.name);
+        assert_eq!(owner.reserved_tokens, 0, "{}", mode.name);
+    }
+}
+
+#[test]
+fn every_mode_restarts_the_matrix() {
+    for mode in &MODES {
+        let (mut owner, mut airdrop) = setup(mode);
+        fill_matrix(mode, &mut owner, &mut airdrop);
+
+        assert_eq!(owner.chain.id, 2, "{}", mode.name);
+        assert_eq!(owner.chain.filled_slots, 0, "{}", mode.name);
+    }
+}
+
+#[test]
+fn slot_one_strategy_differs_per_mode() {
+    let (mut owner, mut airdrop) = setup(&MODES[0]);
+    let burn = fill_matrix(&MODES[0], &mut owner, &mut airdrop);
+    assert_eq!(burn.burned, 2 * DEPOSIT);
+    assert_eq!(burn.pooled, 0);
+    assert_eq!(burn.paid_to_owner, DEPOSIT);
+    assert_eq!(owner.claimable_donut(), 0);
+
+    let (mut owner, mut airdrop) = setup(&MODES[1]);
+    let pool = fill_matrix(&MODES[1], &mut owner, &mut airdrop);
+    assert_eq!(pool.burned, DEPOSIT);
+    assert_eq!(pool.pooled, DEPOSIT);
+    assert_eq!(pool.paid_to_owner, DEPOSIT);
+    assert_eq!(pool.minted, DONUT_PER_DEPOSIT);
+    assert_eq!(owner.claimable_donut(), DONUT_PER_DEPOSIT);
+}
+
+#[test]
+fn released_sol_waits_for_the_owner_to_claim_it() {
+    for mode in &MODES {
+        let (mut owner, mut airdrop) = setup(mode);
+        let ledger = fill_matrix(mode, &mut owner, &mut airdrop);
+        assert_eq!(owner.claimable_sol, ledger.paid_to_owner, "{}", mode.name);
+
+        // Credits from the next matrix add up until claimed
+        fill_matrix(mode, &mut owner, &mut airdrop);
+        assert_eq!(owner.claimable_sol, 2 * ledger.paid_to_owner, "{}", mode.name);
+
+        assert_eq!(owner.take_claimable_sol().unwrap(), 2 * ledger.paid_to_owner, "{}", mode.name);
+        assert_eq!(owner.claimable_sol, 0, "{}", mode.name);
+
+        let err = owner.take_claimable_sol().unwrap_err();
+        assert!(err.to_string().contains("NothingToClaim"), "{}", mode.name);
+    }
+}
+
+#[test]
+fn only_the_airdrop_mode_counts_completed_matrices() {
+    for mode in &MODES {
+        let (mut owner, mut airdrop) = setup(mode);
+        fill_matrix(mode, &mut owner, &mut airdrop);
+        fill_matrix(mode, &mut owner, &mut airdrop);
+
+        let expected = if mode.airdrop { 2 } else { 0 };
+        assert_eq!(owner.completed_matrices_total, expected, "{}", mode.name);
+        assert_eq!(airdrop.total_matrices_this_week, expected, "{}", mode.name);
+    }
+}
+
+#[test]
+fn mint_is_bounded_by_three_times_the_last_mint() {
+    assert_eq!(limit_mint_amount(0, 1_000), 1_000);
+    assert_eq!(limit_mint_amount(1_000, 3_000), 3_000);
+    assert_eq!(limit_mint_amount(1_000, 3_001), 1_000);
+    assert_eq!(limit_mint_amount(1_000, 10), 10);
+    assert_eq!(limit_mint_amount(u64::MAX, u64::MAX), u64::MAX);
+}
@@ -0,0 +1,268 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{build_feed, build_pyth_price, settled_tracker, CHAINLINK_PROGRAM, SOL_USD_FEED};
+use matrix_system::oracle::{
+    decode_pyth_price, pool_twap_price, read_prices, resolve_price, AdminPrice, OracleConfig,
+    OracleKind, OraclePrice, OracleSource, PriceResolution, StalePricePolicy, PRICE_EXPO,
+};
+use matrix_system::price_tracker::PriceTracker;
+
+const NOW: i64 = 1_700_000_000;
+const MAX_AGE: i64 = 86400;
+
+fn chainlink_source() -> OracleSource {
+    OracleSource {
+        kind: OracleKind::Chainlink,
+        program_id: CHAINLINK_PROGRAM,
+        account: SOL_USD_FEED,
+        quote_decimals: 0,
+    }
+}
+
+fn pyth_source(program_id: Pubkey, account: Pubkey) -> OracleSource {
+    OracleSource {
+        kind: OracleKind::Pyth,
+        program_id,
+        account,
+        quote_decimals: 0,
+    }
+}
+
+// USDC per SOL in a SOL/USDC pool tracker: 150 USDC (6 decimals) per 1e9 lamports
+const POOL_PRICE_150_USD: u128 = 150_000_000;
+
+fn pool_twap_source(account: Pubkey) -> OracleSource {
+    OracleSource {
+        kind: OracleKind::PoolTwap,
+        program_id: matrix_system::ID,
+        account,
+        quote_decimals: 6,
+    }
+}
+
+fn tracker_data(tracker: &PriceTracker) -> Vec<u8> {
+    let mut data = Vec::new();
+    tracker.try_serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn decodes_pyth_aggregate_price() {
+    let data = build_pyth_price(14_250_000_000, -8, NOW, 1);
+
+    let price = decode_pyth_price(&data).unwrap();
+    assert_eq!(price.price, 14_250_000_000);
+    assert_eq!(price.expo, -8);
+    assert_eq!(price.publish_time, NOW);
+}
+
+#[test]
+fn rejects_pyth_price_that_is_not_trading() {
+    let data = build_pyth_price(14_250_000_000, -8, NOW, 0);
+
+    assert!(decode_pyth_price(&data).is_err());
+}
+
+const NO_ADMIN_PRICE: AdminPrice = AdminPrice { price: 0, expires_at: 0 };
+
+fn price(price: i128, expo: i32, publish_time: i64) -> OraclePrice {
+    OraclePrice { price, expo, publish_time }
+}
+
+#[test]
+fn reads_every_configured_source_in_order() {
+    let pyth_program = Pubkey::new_unique();
+    let pyth_key = Pubkey::new_unique();
+    let config = OracleConfig {
+        sources: vec![chainlink_source(), pyth_source(pyth_program, pyth_key)],
+    };
+
+    let feed_key = SOL_USD_FEED;
+    let chainlink_program = CHAINLINK_PROGRAM;
+    let loader = Pubkey::default();
+    let (mut feed_lamports, mut program_lamports, mut pyth_lamports) = (1, 1, 1);
+    let mut feed_data = build_feed(8, 1, &[(NOW as u32 - 30, 150_00000000)]);
+    let mut program_data = vec![];
+    let mut pyth_data = build_pyth_price(14_000_000, -5, NOW, 1);
+
+    let accounts = vec![
+        AccountInfo::new(&feed_key, false, false, &mut feed_lamports, &mut feed_data, &chainlink_program, false, 0),
+        AccountInfo::new(&chainlink_program, false, false, &mut program_lamports, &mut program_data, &loader, true, 0),
+        AccountInfo::new(&pyth_key, false, false, &mut pyth_lamports, &mut pyth_data, &pyth_program, false, 0),
+    ];
+
+    let prices = read_prices(&config, &accounts, NOW).unwrap();
+    assert_eq!(
+        prices,
+        vec![Some(price(150_00000000, -8, NOW - 30)), Some(price(14_000_000, -5, NOW))]
+    );
+}
+
+#[test]
+fn unreadable_source_yields_none() {
+    let pyth_program = Pubkey::new_unique();
+    let pyth_key = Pubkey::new_unique();
+    let config = OracleConfig {
+        sources: vec![chainlink_source(), pyth_source(pyth_program, pyth_key)],
+    };
+
+    // Primary account is not the configured feed
+    let fake_feed = Pubkey::new_unique();
+    let chainlink_program = CHAINLINK_PROGRAM;
+    let loader = Pubkey::default();
+    let (mut feed_lamports, mut program_lamports, mut pyth_lamports) = (1, 1, 1);
+    let mut feed_data = build_feed(8, 1, &[(NOW as u32, 1)]);
+    let mut program_data = vec![];
+    let mut pyth_data = build_pyth_price(140_00000000, -8, NOW, 0);
+
+    let accounts = vec![
+        AccountInfo::new(&fake_feed, false, false, &mut feed_lamports, &mut feed_data, &chainlink_program, false, 0),
+        AccountInfo::new(&chainlink_program, false, false, &mut program_lamports, &mut program_data, &loader, true, 0),
+        AccountInfo::new(&pyth_key, false, false, &mut pyth_lamports, &mut pyth_data, &pyth_program, false, 0),
+    ];
+
+    assert_eq!(read_prices(&config, &accounts, NOW).unwrap(), vec![None, None]);
+    assert!(read_prices(&config, &accounts[..2], NOW).is_err());
+}
+
+#[test]
+fn uses_fresh_primary_regardless_of_policy() {
+    let prices = [Some(price(150_00000000, -8, NOW - 30)), Some(price(90_00000000, -8, NOW))];
+
+    for policy in [StalePricePolicy::Reject, StalePricePolicy::SecondaryMedian, StalePricePolicy::AdminPrice] {
+        let (resolved, resolution) = resolve_price(&prices, NOW, MAX_AGE, policy, NO_ADMIN_PRICE).unwrap();
+        assert_eq!(resolved.price, 150_00000000);
+        assert_eq!(resolution, PriceResolution::Primary);
+    }
+}
+
+#[test]
+fn reject_policy_refuses_stale_primary() {
+    let prices = [Some(price(150_00000000, -8, NOW - MAX_AGE - 1)), Some(price(90_00000000, -8, NOW))];
+
+    assert!(resolve_price(&prices, NOW, MAX_AGE, StalePricePolicy::Reject, NO_ADMIN_PRICE).is_err());
+}
+
+#[test]
+fn secondary_median_policy_uses_fresh_secondaries() {
+    let prices = [
+        None,
+        Some(price(1_400_000, -4, NOW - 10)),
+        Some(price(150_00000000, -8, NOW - MAX_AGE - 1)),
+    ];
+
+    let (resolved, resolution) =
+        resolve_price(&prices, NOW, MAX_AGE, StalePricePolicy::SecondaryMedian, NO_ADMIN_PRICE).unwrap();
+    assert_eq!(resolved.price, 140_00000000);
+    assert_eq!(resolved.expo, PRICE_EXPO);
+    assert_eq!(resolution, PriceResolution::SecondaryMedian { sources: 1 });
+
+    let prices = [None, Some(price(140_00000000, -8, NOW)), Some(price(150_00000000, -8, NOW))];
+    let (resolved, resolution) =
+        resolve_price(&prices, NOW, MAX_AGE, StalePricePolicy::SecondaryMedian, NO_ADMIN_PRICE).unwrap();
+    assert_eq!(resolved.price, 145_00000000);
+    assert_eq!(resolution, PriceResolution::SecondaryMedian { sources: 2 });
+}
+
+#[test]
+fn secondary_median_policy_rejects_without_fresh_secondaries() {
+    let prices = [None, Some(price(140_00000000, -8, NOW - MAX_AGE - 1))];
+
+    assert!(resolve_price(&prices, NOW, MAX_AGE, StalePricePolicy::SecondaryMedian, NO_ADMIN_PRICE).is_err());
+}
+
+#[test]
+fn admin_price_policy_uses_price_until_it_expires() {
+    let prices = [Some(price(150_00000000, -8, NOW - MAX_AGE - 1))];
+    let admin_price = AdminPrice { price: 120_00000000, expires_at: NOW + 60 };
+
+    let (resolved, resolution) =
+        resolve_price(&prices, NOW, MAX_AGE, StalePricePolicy::AdminPrice, admin_price).unwrap();
+    assert_eq!(resolved.price, 120_00000000);
+    assert_eq!(resolution, PriceResolution::AdminPrice);
+
+    assert!(resolve_price(&prices, NOW + 60, MAX_AGE, StalePricePolicy::AdminPrice, admin_price).is_err());
+    assert!(resolve_price(&prices, NOW, MAX_AGE, StalePricePolicy::AdminPrice, NO_ADMIN_PRICE).is_err());
+}
+
+#[test]
+fn pool_twap_converts_the_tracked_pool_price_to_usd() {
+    let tracker = settled_tracker(POOL_PRICE_150_USD, NOW);
+
+    let price = pool_twap_price(&tracker, 6, NOW).unwrap();
+    assert_eq!(price.price, 150_00000000);
+    assert_eq!(price.expo, PRICE_EXPO);
+    assert_eq!(price.publish_time, tracker.last_update);
+}
+
+#[test]
+fn pool_twap_needs_a_history_window() {
+    let mut tracker = PriceTracker::default();
+    assert!(pool_twap_price(&tracker, 6, NOW).is_err());
+
+    // A single fresh observation is one spot price, not an average
+    tracker.update(POOL_PRICE_150_USD, NOW);
+    assert!(pool_twap_price(&tracker, 6, NOW).is_err());
+}
+
+#[test]
+fn reads_a_pool_twap_source_from_its_tracker() {
+    let tracker_key = Pubkey::new_unique();
+    let config = OracleConfig { sources: vec![pool_twap_source(tracker_key)] };
+
+    let program_id = matrix_system::ID;
+    let mut lamports = 1;
+    let mut data = tracker_data(&settled_tracker(POOL_PRICE_150_USD, NOW));
+    let accounts = vec![AccountInfo::new(&tracker_key, false, false, &mut lamports, &mut data, &program_id, false, 0)];
+
+    let prices = read_prices(&config, &accounts, NOW).unwrap();
+    assert_eq!(prices, vec![Some(price(150_00000000, PRICE_EXPO, NOW - 3600))]);
+
+    // A tracker owned by another program is not trusted
+    let other_program = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = tracker_data(&settled_tracker(POOL_PRICE_150_USD, NOW));
+    let accounts = vec![AccountInfo::new(&tracker_key, false, false, &mut lamports, &mut data, &other_program, false, 0)];
+    assert_eq!(read_prices(&config, &accounts, NOW).unwrap(), vec![None]);
+}
+
+#[test]
+fn stale_pool_twap_is_refused_by_the_resolver() {
+    let fresh = pool_twap_price(&settled_tracker(POOL_PRICE_150_USD, NOW), 6, NOW).unwrap();
+    let (resolved, resolution) =
+        resolve_price(&[Some(fresh)], NOW, MAX_AGE, StalePricePolicy::Reject, NO_ADMIN_PRICE).unwrap();
+    assert_eq!(resolved.price, 150_00000000);
+    assert_eq!(resolution, PriceResolution::Primary);
+
+    // The crank stopped a day ago: the TWAP still has a window but its last update is too old
+    let stale = pool_twap_price(&settled_tracker(POOL_PRICE_150_USD, NOW - MAX_AGE), 6, NOW).unwrap();
+    assert!(resolve_price(&[Some(stale)], NOW, MAX_AGE, StalePricePolicy::Reject, NO_ADMIN_PRICE).is_err());
+}
+
+#[test]
+fn pool_twap_backs_up_a_stale_primary_as_a_secondary() {
+    let fresh = pool_twap_price(&settled_tracker(POOL_PRICE_150_USD, NOW), 6, NOW).unwrap();
+    let stale = pool_twap_price(&settled_tracker(POOL_PRICE_150_USD, NOW - MAX_AGE), 6, NOW).unwrap();
+    let stale_primary = Some(price(140_00000000, -8, NOW - MAX_AGE - 1));
+
+    let (resolved, resolution) = resolve_price(
+        &[stale_primary, Some(fresh)],
+        NOW,
+        MAX_AGE,
+        StalePricePolicy::SecondaryMedian,
+        NO_ADMIN_PRICE,
+    )
+    .unwrap();
+    assert_eq!(resolved.price, 150_00000000);
+    assert_eq!(resolution, PriceResolution::SecondaryMedian { sources: 1 });
+
+    assert!(resolve_price(
+        &[stale_primary, Some(stale)],
+        NOW,
+        MAX_AGE,
+        StalePricePolicy::SecondaryMedian,
+        NO_ADMIN_PRICE,
+    )
+    .is_err());
+}
@@ -0,0 +1,61 @@
+use anchor_lang::error::Error;
+use matrix_system::pause::{PauseFlag, PauseFlags};
+use matrix_system::ErrorCode;
+
+const ALL_FLAGS: [PauseFlag; 4] = [
+    PauseFlag::Registration,
+    PauseFlag::SwapAndBurn,
+    PauseFlag::ReservePayouts,
+    PauseFlag::AirdropClaims,
+];
+
+fn error_code(flag: PauseFlag) -> ErrorCode {
+    match flag {
+        PauseFlag::Registration => ErrorCode::RegistrationPaused,
+        PauseFlag::SwapAndBurn => ErrorCode::SwapAndBurnPaused,
+        PauseFlag::ReservePayouts => ErrorCode::ReservePayoutsPaused,
+        PauseFlag::AirdropClaims => ErrorCode::AirdropClaimsPaused,
+    }
+}
+
+#[test]
+fn nothing_is_paused_by_default() {
+    let flags = PauseFlags::default();
+
+    for flag in ALL_FLAGS {
+        assert!(!flags.is_paused(flag));
+        assert!(flags.ensure_not_paused(flag).is_ok());
+    }
+}
+
+#[test]
+fn flags_are_independent() {
+    for paused_flag in ALL_FLAGS {
+        let mut flags = PauseFlags::default();
+        flags.set(paused_flag, true);
+
+        for flag in ALL_FLAGS {
+            assert_eq!(flags.is_paused(flag), flag == paused_flag);
+        }
+    }
+}
+
+#[test]
+fn each_flag_has_its_own_error() {
+    for flag in ALL_FLAGS {
+        let mut flags = PauseFlags::default();
+        flags.set(flag, true);
+
+        let err = flags.ensure_not_paused(flag).unwrap_err();
+        assert_eq!(err, Error::from(error_code(flag)));
+    }
+}
+
+#[test]
+fn resuming_clears_the_flag() {
+    let mut flags = PauseFlags::default();
+    flags.set(PauseFlag::SwapAndBurn, true);
+    flags.set(PauseFlag::SwapAndBurn, false);
+
+    assert_eq!(flags, PauseFlags::default());
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{install_token_runtime, settled_tracker, vault_burn_accounts, MockDex, VAULT_RENT};
+use matrix_system::buyback::{queued_amount, BurnMode};
+use matrix_system::layout::read_token_amount;
+use matrix_system::pending_burn::SwapFailurePolicy;
+use matrix_system::price_guard::pool_price;
+use matrix_system::quote::TokenSwapFees;
+use matrix_system::slippage::DEFAULT_MAX_SLIPPAGE_BPS;
+use matrix_system::{burn_vault_lamports, record_pool_price, route_burn, BurnRoute};
+
+const NOW: i64 = 1_700_000_000;
+const DEPOSIT: u64 = 100_000_000;
+const MAX_DEVIATION_BPS: u16 = 500;
+const DONUT_RESERVE: u64 = 1_000_000_000_000;
+const SOL_RESERVE: u64 = 100_000_000_000;
+const FEES: TokenSwapFees = TokenSwapFees {
+    trade_fee_numerator: 25,
+    trade_fee_denominator: 10_000,
+    owner_trade_fee_numerator: 5,
+    owner_trade_fee_denominator: 10_000,
+};
+const SEEDS: &[&[&[u8]]] = &[&[b"pending_burn_vault", &[255]]];
+
+#[test]
+fn failed_swaps_revert_by_default() {
+    assert_eq!(SwapFailurePolicy::default(), SwapFailurePolicy::Revert);
+}
+
+#[test]
+fn policy_size_matches_serialization() {
+    assert_eq!(SwapFailurePolicy::Escrow.try_to_vec().unwrap().len(), SwapFailurePolicy::SIZE);
+    assert_eq!(SwapFailurePolicy::try_from_slice(&[1]).unwrap(), SwapFailurePolicy::Escrow);
+}
+
+#[test]
+fn unquotable_burns_escrow_only_under_the_escrow_policy() {
+    let tracker = settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW);
+
+    // A drained SOL side leaves no price to swap at
+    let dex = MockDex::new(DONUT_RESERVE, 0, FEES);
+
+    let route = route_burn(&dex, DEPOSIT, BurnMode::Immediate, SwapFailurePolicy::Escrow, &tracker, MAX_DEVIATION_BPS, NOW).unwrap();
+    assert_eq!(route, BurnRoute::Escrow);
+
+    let err = route_burn(&dex, DEPOSIT, BurnMode::Immediate, SwapFailurePolicy::Revert, &tracker, MAX_DEVIATION_BPS, NOW).unwrap_err();
+    assert!(err.to_string().contains("MeteoraCalculationOverflow"));
+
+    // A healthy pool swaps right away under either policy
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let route = route_burn(&dex, DEPOSIT, BurnMode::Immediate, SwapFailurePolicy::Escrow, &tracker, MAX_DEVIATION_BPS, NOW).unwrap();
+    assert!(matches!(route, BurnRoute::Swap { .. }));
+}
+
+#[test]
+fn empty_reserves_skip_the_price_update_and_leave_the_burn_to_its_policy() {
+    let settled = settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW);
+
+    for dex in [MockDex::new(DONUT_RESERVE, 0, FEES), MockDex::new(0, SOL_RESERVE, FEES)] {
+        // Registration records the price before any slot runs, and must not fail on it
+        let mut tracker = settled;
+        record_pool_price(&dex, &mut tracker, NOW);
+        assert_eq!(tracker.try_to_vec().unwrap(), settled.try_to_vec().unwrap());
+
+        let route = route_burn(&dex, DEPOSIT, BurnMode::Immediate, SwapFailurePolicy::Escrow, &tracker, MAX_DEVIATION_BPS, NOW).unwrap();
+        assert_eq!(route, BurnRoute::Escrow);
+        let route = route_burn(&dex, DEPOSIT, BurnMode::Batched, SwapFailurePolicy::Revert, &tracker, MAX_DEVIATION_BPS, NOW).unwrap();
+        assert_eq!(route, BurnRoute::Queue);
+        assert!(route_burn(&dex, DEPOSIT, BurnMode::Immediate, SwapFailurePolicy::Revert, &tracker, MAX_DEVIATION_BPS, NOW).is_err());
+    }
+
+    // A readable pool moves the history forward
+    let mut tracker = settled;
+    record_pool_price(&MockDex::new(DONUT_RESERVE * 2, SOL_RESERVE, FEES), &mut tracker, NOW);
+    assert_eq!(tracker.last_price, pool_price(DONUT_RESERVE * 2, SOL_RESERVE).unwrap());
+}
+
+#[test]
+fn retry_burns_the_escrow_and_empties_the_vault() {
+    install_token_runtime();
+    let mut tracker = settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW);
+
+    // Two registrations escrowed their burns
+    let accounts = vault_burn_accounts(2 * DEPOSIT);
+    let pending = queued_amount(accounts.vault.lamports(), VAULT_RENT);
+    assert_eq!(pending, 2 * DEPOSIT);
+
+    // Retrying fails like the registrations did while the pool is still drained
+    let dex = MockDex::new(DONUT_RESERVE, 0, FEES);
+    let err = burn_vault_lamports(&dex, &accounts, &mut tracker, pending, MAX_DEVIATION_BPS, DEFAULT_MAX_SLIPPAGE_BPS, SEEDS, NOW)
+        .unwrap_err();
+    assert!(err.to_string().contains("MeteoraCalculationOverflow"));
+    assert_eq!(accounts.vault.lamports(), VAULT_RENT + pending);
+
+    // Once the pool is back the whole escrow is swapped and burned
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let expected = dex.fees.quote(SOL_RESERVE, DONUT_RESERVE, pending).unwrap().amount_out;
+    let burned = burn_vault_lamports(&dex, &accounts, &mut tracker, pending, MAX_DEVIATION_BPS, DEFAULT_MAX_SLIPPAGE_BPS, SEEDS, NOW + 60)
+        .unwrap();
+
+    assert_eq!(burned, expected);
+    assert_eq!(read_token_amount(&accounts.burn_account).unwrap(), 0);
+    assert_eq!(read_token_amount(&accounts.vault_wsol_account).unwrap(), 0);
+
+    // Nothing is left for another retry
+    assert_eq!(queued_amount(accounts.vault.lamports(), VAULT_RENT), 0);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{install_token_runtime, settled_tracker, vault_burn_accounts, MockDex, VAULT_RENT};
+use matrix_system::buyback::BurnMode;
+use matrix_system::layout::read_token_amount;
+use matrix_system::pending_burn::SwapFailurePolicy;
+use matrix_system::price_guard::{
+    check_deviation, deviation_bps, pool_price, reserves_at_price, validate_max_price_deviation_bps, PRICE_SCALE,
+};
+use matrix_system::quote::TokenSwapFees;
+use matrix_system::slippage::DEFAULT_MAX_SLIPPAGE_BPS;
+use matrix_system::{burn_vault_lamports, route_burn, BurnRoute};
+
+const NOW: i64 = 1_700_000_000;
+const DEPOSIT: u64 = 100_000_000;
+const MAX_DEVIATION_BPS: u16 = 500;
+const DONUT_RESERVE: u64 = 1_000_000_000_000;
+const SOL_RESERVE: u64 = 100_000_000_000;
+const FEES: TokenSwapFees = TokenSwapFees {
+    trade_fee_numerator: 25,
+    trade_fee_denominator: 10_000,
+    owner_trade_fee_numerator: 5,
+    owner_trade_fee_denominator: 10_000,
+};
+
+#[test]
+fn pool_price_is_donut_per_sol() {
+    // 5_000 DONUT against 2 SOL
+    assert_eq!(pool_price(5_000_000_000_000, 2_000_000_000), Some(2_500 * PRICE_SCALE));
+    assert_eq!(pool_price(5_000_000_000_000, 0), None);
+}
+
+#[test]
+fn deviation_is_measured_against_the_reference() {
+    assert_eq!(deviation_bps(1_100, 1_000), Some(1_000));
+    assert_eq!(deviation_bps(900, 1_000), Some(1_000));
+    assert_eq!(deviation_bps(1_000, 1_000), Some(0));
+    assert_eq!(deviation_bps(5_000, 1_000), Some(40_000));
+    assert_eq!(deviation_bps(1_000, 0), None);
+}
+
+#[test]
+fn guard_trips_only_beyond_the_configured_bound() {
+    let reference = Some(1_000_000);
+
+    assert_eq!(check_deviation(1_050_000, reference, 500), None);
+    assert_eq!(check_deviation(1_050_100, reference, 500), Some(501));
+    assert_eq!(check_deviation(800_000, reference, 500), Some(2_000));
+
+    // 0 turns the guard off
+    assert_eq!(check_deviation(10_000_000, reference, 0), None);
+
+    // Nothing to compare against without a reference price
+    assert_eq!(check_deviation(10_000_000, None, 500), None);
+}
+
+#[test]
+fn max_deviation_is_at_most_one_hundred_percent() {
+    assert!(validate_max_price_deviation_bps(0).is_ok());
+    assert!(validate_max_price_deviation_bps(10_000).is_ok());
+    assert!(validate_max_price_deviation_bps(10_001).is_err());
+}
+
+#[test]
+fn held_deposits_are_burned_once_the_price_is_back_in_the_band() {
+    install_token_runtime();
+    let dex = MockDex::new(DONUT_RESERVE, SOL_RESERVE, FEES);
+    let mut tracker = settled_tracker(pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap(), NOW);
+
+    // SOL pushed into the pool moves the price 20% off its EMA, so the burn is held
+    dex.sol_reserve.set(SOL_RESERVE * 5 / 4);
+    let route = route_burn(&dex, DEPOSIT, BurnMode::Immediate, SwapFailurePolicy::Revert, &tracker, MAX_DEVIATION_BPS, NOW).unwrap();
+    assert!(matches!(route, BurnRoute::Hold { deviation_bps: 2_000, .. }));
+
+    // The registration parked the deposit in the holding vault
+    let accounts = vault_burn_accounts(DEPOSIT);
+    let seeds: &[&[&[u8]]] = &[&[b"holding_vault".as_ref(), &[255]]];
+
+    // Releasing is refused while the price stays outside the band
+    let err = burn_vault_lamports(&dex, &accounts, &mut tracker, DEPOSIT, MAX_DEVIATION_BPS, DEFAULT_MAX_SLIPPAGE_BPS, seeds, NOW + 60)
+        .unwrap_err();
+    assert!(err.to_string().contains("PriceDeviationTooHigh"));
+    assert_eq!(accounts.vault.lamports(), VAULT_RENT + DEPOSIT);
+
+    // Once the pool is back, anyone can swap and burn what was held
+    dex.sol_reserve.set(SOL_RESERVE);
+    let expected = dex.fees.quote(SOL_RESERVE, DONUT_RESERVE, DEPOSIT).unwrap().amount_out;
+    let burned = burn_vault_lamports(&dex, &accounts, &mut tracker, DEPOSIT, MAX_DEVIATION_BPS, DEFAULT_MAX_SLIPPAGE_BPS, seeds, NOW + 120)
+        .unwrap();
+
+    assert_eq!(burned, expected);
+    assert_eq!(accounts.vault.lamports(), VAULT_RENT);
+    assert_eq!(read_token_amount(&accounts.vault_wsol_account).unwrap(), 0);
+    assert_eq!(read_token_amount(&accounts.burn_account).unwrap(), 0);
+    assert_eq!(dex.sol_reserve.get(), SOL_RESERVE + DEPOSIT);
+}
+
+#[test]
+fn reserves_at_price_keep_the_pool_liquidity() {
+    // 10 DONUT per SOL moved to 40 DONUT per SOL
+    let (donut, sol) = reserves_at_price(DONUT_RESERVE, SOL_RESERVE, 40 * PRICE_SCALE).unwrap();
+    assert_eq!((donut, sol), (2_000_000_000_000, 50_000_000_000));
+
+    let price = pool_price(DONUT_RESERVE, SOL_RESERVE).unwrap();
+    assert_eq!(reserves_at_price(DONUT_RESERVE, SOL_RESERVE, price), Some((DONUT_RESERVE, SOL_RESERVE)));
+    assert_eq!(reserves_at_price(DONUT_RESERVE, SOL_RESERVE, 0), None);
+    assert_eq!(reserves_at_price(0, SOL_RESERVE, price), None);
+
+    // Deep reserves are scaled without overflowing
+    let (donut, sol) = reserves_at_price(u64::MAX, u64::MAX / 2, 2 * PRICE_SCALE).unwrap();
+    assert!(donut.abs_diff(u64::MAX) <= 1 && sol.abs_diff(u64::MAX / 2) <= 1);
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use matrix_system::price_guard::PRICE_SCALE;
+use matrix_system::price_tracker::{PriceTracker, EMA_PERIOD, OBSERVATION_INTERVAL, PRICE_OBSERVATIONS};
+
+const NOW: i64 = 1_700_000_000;
+
+fn seeded(price: u128) -> PriceTracker {
+    let mut tracker = PriceTracker::default();
+    tracker.update(price, NOW);
+    tracker
+}
+
+#[test]
+fn first_update_seeds_every_average() {
+    assert!(PriceTracker::default().ema().is_none());
+    assert!(PriceTracker::default().twap(NOW).is_none());
+
+    let tracker = seeded(1_000_000);
+    assert_eq!(tracker.ema(), Some(1_000_000));
+    assert_eq!(tracker.last_price, 1_000_000);
+    assert_eq!(tracker.last_update, NOW);
+
+    // No time has passed yet, so there is no window to average over
+    assert_eq!(tracker.twap(NOW), None);
+    assert_eq!(tracker.twap(NOW + 10), Some(1_000_000));
+}
+
+#[test]
+fn ema_moves_with_the_time_the_previous_price_held() {
+    let mut tracker = seeded(1_000_000);
+
+    // The new spot price only takes effect for the next interval
+    tracker.update(2_000_000, NOW + 60);
+    assert_eq!(tracker.ema_price, 1_000_000);
+
+    // A quarter of the period at 2_000_000 moves the EMA a quarter of the way
+    tracker.update(2_000_000, NOW + 60 + EMA_PERIOD / 4);
+    assert_eq!(tracker.ema_price, 1_250_000);
+
+    // A full period or more lands on the price
+    tracker.update(2_000_000, NOW + 60 + 2 * EMA_PERIOD);
+    assert_eq!(tracker.ema_price, 2_000_000);
+}
+
+#[test]
+fn same_second_updates_cannot_move_the_averages() {
+    let mut tracker = seeded(1_000_000);
+    tracker.update(1_000_000, NOW + 600);
+
+    // A manipulated print and its reversal within one second
+    tracker.update(50_000_000, NOW + 600);
+    tracker.update(1_000_000, NOW + 600);
+
+    assert_eq!(tracker.ema_price, 1_000_000);
+    assert_eq!(tracker.twap(NOW + 900), Some(1_000_000));
+}
+
+#[test]
+fn twap_weights_prices_by_how_long_they_held() {
+    let mut tracker = seeded(1_000_000);
+    tracker.update(3_000_000, NOW + 300);
+
+    // 300s at 1_000_000 and 100s at 3_000_000
+    assert_eq!(tracker.twap(NOW + 400), Some(1_500_000));
+}
+
+#[test]
+fn twap_window_rolls_over_the_oldest_observations() {
+    let mut tracker = seeded(1_000_000);
+    let mut now = NOW;
+    for _ in 0..PRICE_OBSERVATIONS {
+        now += OBSERVATION_INTERVAL;
+        tracker.update(2_000_000, now);
+    }
+    assert_eq!(tracker.observation_count as usize, PRICE_OBSERVATIONS);
+
+    // The observation at the 1_000_000 price has been overwritten
+    assert_eq!(tracker.twap(now), Some(2_000_000));
+
+    let snapshot = tracker.snapshot(now);
+    assert_eq!(snapshot.twap_window, OBSERVATION_INTERVAL * (PRICE_OBSERVATIONS as i64 - 1));
+    assert_eq!(snapshot.spot_price, 2_000_000);
+}
+
+#[test]
+fn observations_are_spaced_by_the_interval() {
+    let mut tracker = seeded(1_000_000);
+    tracker.update(1_000_000, NOW + OBSERVATION_INTERVAL - 1);
+    assert_eq!(tracker.observation_count, 1);
+
+    tracker.update(1_000_000, NOW + OBSERVATION_INTERVAL);
+    assert_eq!(tracker.observation_count, 2);
+}
+
+#[test]
+fn guard_checks_against_the_ema() {
+    let mut tracker = seeded(1_000_000);
+    assert_eq!(tracker.check(1_050_000, 500), None);
+    assert_eq!(tracker.check(800_000, 500), Some(2_000));
+    assert_eq!(PriceTracker::default().check(800_000, 500), None);
+
+    // A 20% print trips a 10% bound and barely moves the EMA once recorded
+    assert!(tracker.check(1_200_000, 1_000).is_some());
+    tracker.update(1_200_000, NOW + 1);
+    tracker.update(1_000_000, NOW + 2);
+    assert!(tracker.check(1_200_000, 1_000).is_some());
+}
+
+#[test]
+fn donut_value_uses_the_twap() {
+    // 2_500 DONUT per SOL
+    let mut tracker = seeded(2_500 * PRICE_SCALE);
+    tracker.update(2_500 * PRICE_SCALE, NOW + 60);
+
+    assert_eq!(tracker.donut_value(2_000_000_000, NOW + 60), Some(5_000_000_000_000));
+    assert_eq!(PriceTracker::default().donut_value(2_000_000_000, NOW), None);
+}
+
+#[test]
+fn price_tracker_size_matches_serialization() {
+    let tracker = seeded(u128::MAX);
+    assert_eq!(tracker.try_to_vec().unwrap().len(), PriceTracker::SIZE);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::build_protocol_config;
+use matrix_system::config::ProtocolConfig;
+use matrix_system::{validate_all_remaining_accounts, verify_all_fixed_addresses, verify_pool_and_vault_a_addresses, MAX_UPLINE_DEPTH};
+
+#[test]
+fn accepts_valid_params() {
+    assert!(ProtocolConfig::validate_params(10_00000000, 86400, MAX_UPLINE_DEPTH as u8).is_ok());
+    assert!(ProtocolConfig::validate_params(1, 1, 1).is_ok());
+}
+
+#[test]
+fn rejects_invalid_params() {
+    assert!(ProtocolConfig::validate_params(0, 86400, 6).is_err());
+    assert!(ProtocolConfig::validate_params(10_00000000, 0, 6).is_err());
+    assert!(ProtocolConfig::validate_params(10_00000000, -1, 6).is_err());
+    assert!(ProtocolConfig::validate_params(10_00000000, 86400, 0).is_err());
+    assert!(ProtocolConfig::validate_params(10_00000000, 86400, MAX_UPLINE_DEPTH as u8 + 1).is_err());
+}
+
+#[test]
+fn verifies_fixed_addresses_against_config() {
+    let config = build_protocol_config();
+    let a = config.addresses;
+
+    assert!(verify_all_fixed_addresses(
+        &config, &a.pool, &a.b_vault, &a.b_token_vault, &a.b_vault_lp_mint, &a.b_vault_lp, &a.token_mint, &a.wsol_mint,
+    )
+    .is_ok());
+    assert!(verify_all_fixed_addresses(
+        &config, &a.pool, &a.b_vault, &a.b_token_vault, &a.b_vault_lp_mint, &a.b_vault_lp, &Pubkey::new_unique(), &a.wsol_mint,
+    )
+    .is_err());
+
+    assert!(verify_pool_and_vault_a_addresses(&config, &a.pool, &a.a_vault, &a.a_vault_lp, &a.a_vault_lp_mint).is_ok());
+    assert!(verify_pool_and_vault_a_addresses(&config, &a.pool, &a.a_vault_lp, &a.a_vault, &a.a_vault_lp_mint).is_err());
+}
+
+#[test]
+fn rejects_addresses_from_another_config() {
+    let config = build_protocol_config();
+    let other = build_protocol_config().addresses;
+
+    assert!(verify_all_fixed_addresses(
+        &config, &other.pool, &other.b_vault, &other.b_token_vault, &other.b_vault_lp_mint, &other.b_vault_lp,
+        &other.token_mint, &other.wsol_mint,
+    )
+    .is_err());
+}
+
+#[test]
+fn validates_remaining_accounts_against_config() {
+    let config = build_protocol_config();
+    let a = config.addresses;
+    let keys = [a.pool, a.a_vault, a.a_vault_lp, a.a_vault_lp_mint, a.a_token_vault];
+    let owner = Pubkey::new_unique();
+    let mut lamports = [1u64; 5];
+    let mut data: [Vec<u8>; 5] = Default::default();
+
+    let accounts: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| AccountInfo::new(key, false, true, lamports, data, &owner, false, 0))
+        .collect();
+
+    assert!(validate_all_remaining_accounts(&config, &accounts, 5).is_ok());
+    assert!(validate_all_remaining_accounts(&config, &accounts[..4], 5).is_err());
+
+    let other = build_protocol_config();
+    assert!(validate_all_remaining_accounts(&other, &accounts, 5).is_err());
+}
+
+#[test]
+fn validates_upline_pdas_after_base_accounts() {
+    let config = build_protocol_config();
+    let a = config.addresses;
+    let keys = [a.pool, a.a_vault, a.a_vault_lp, a.a_vault_lp_mint, a.a_token_vault, Pubkey::new_unique(), Pubkey::new_unique()];
+    let owners = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        matrix_system::ID,
+        System::id(),
+    ];
+    let mut lamports = [1u64; 7];
+    let mut data: [Vec<u8>; 7] = Default::default();
+
+    let accounts: Vec<AccountInfo> = keys
+        .iter()
+        .zip(owners.iter())
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|(((key, owner), lamports), data)| AccountInfo::new(key, false, true, lamports, data, owner, false, 0))
+        .collect();
+
+    assert!(validate_all_remaining_accounts(&config, &accounts[..6], 5).is_ok());
+    // Uplines are passed as user account PDAs only, so a wallet is rejected
+    let err = validate_all_remaining_accounts(&config, &accounts, 5).unwrap_err();
+    assert!(err.to_string().contains("InvalidSlotOwner"));
+}
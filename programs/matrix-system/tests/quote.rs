@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use matrix_system::layout::account_discriminator;
+use matrix_system::quote::{
+    quote_swap, CurveType, Depeg, DepegType, Pool, PoolFees, SwapQuote, TokenMultiplier, TradeDirection,
+    DEPEG_PRECISION,
+};
+
+const NO_FEES: PoolFees = PoolFees {
+    trade_fee_numerator: 0,
+    trade_fee_denominator: 10_000,
+    protocol_trade_fee_numerator: 0,
+    protocol_trade_fee_denominator: 100,
+};
+
+// 0.25% trade fee, a fifth of it to the protocol
+const FEES: PoolFees = PoolFees {
+    trade_fee_numerator: 25,
+    trade_fee_denominator: 10_000,
+    protocol_trade_fee_numerator: 20,
+    protocol_trade_fee_denominator: 100,
+};
+
+fn stable(amp: u64, token_a_multiplier: u64, token_b_multiplier: u64, depeg: Depeg) -> CurveType {
+    CurveType::Stable {
+        amp,
+        token_multiplier: TokenMultiplier { token_a_multiplier, token_b_multiplier, precision_factor: 9 },
+        depeg,
+        last_amp_updated_timestamp: 0,
+    }
+}
+
+fn quote_out(fees: &PoolFees, curve: &CurveType, in_reserve: u64, out_reserve: u64, amount_in: u64) -> u64 {
+    quote_swap(fees, curve, in_reserve, out_reserve, amount_in, TradeDirection::AtoB)
+        .unwrap()
+        .amount_out
+}
+
+#[test]
+fn constant_product_matches_the_invariant() {
+    let curve = CurveType::ConstantProduct;
+    assert_eq!(quote_out(&NO_FEES, &curve, 1_000_000, 2_000_000, 10_000), 19_801);
+
+    // Price impact: twice the input returns less than twice the output
+    let out = quote_out(&NO_FEES, &curve, 1_000_000, 2_000_000, 20_000);
+    assert!(out < 2 * 19_801);
+}
+
+#[test]
+fn trade_fee_is_taken_from_the_input() {
+    let quote = quote_swap(&FEES, &CurveType::ConstantProduct, 1_000_000, 2_000_000, 10_000, TradeDirection::AtoB).unwrap();
+    assert_eq!(quote, SwapQuote { amount_out: 19_752, trade_fee: 25, protocol_fee: 5 });
+
+    // A non-zero fee rate always charges at least one unit
+    let quote = quote_swap(&FEES, &CurveType::ConstantProduct, 1_000_000, 2_000_000, 10, TradeDirection::AtoB).unwrap();
+    assert_eq!(quote.trade_fee, 1);
+    assert_eq!(quote.protocol_fee, 1);
+}
+
+#[test]
+fn empty_reserves_cannot_be_quoted() {
+    assert!(quote_swap(&NO_FEES, &CurveType::ConstantProduct, 0, 2_000_000, 10, TradeDirection::AtoB).is_none());
+    assert!(quote_swap(&NO_FEES, &stable(100, 1, 1, Depeg::default()), 1_000, 0, 10, TradeDirection::AtoB).is_none());
+}
+
+#[test]
+fn stable_curve_stays_near_parity_on_a_balanced_pool() {
+    let curve = stable(100, 1, 1, Depeg::default());
+    let out = quote_out(&NO_FEES, &curve, 1_000_000_000_000, 1_000_000_000_000, 1_000_000);
+    assert!((999_990..=1_000_000).contains(&out), "{}", out);
+
+    // Much better than the constant-product price for the same large trade
+    let large = 100_000_000_000;
+    let stable_out = quote_out(&NO_FEES, &curve, 1_000_000_000_000, 1_000_000_000_000, large);
+    let cp_out = quote_out(&NO_FEES, &CurveType::ConstantProduct, 1_000_000_000_000, 1_000_000_000_000, large);
+    assert!(stable_out > cp_out);
+    assert!(stable_out < large);
+}
+
+#[test]
+fn stable_curve_upscales_tokens_with_different_decimals() {
+    // Token A has 6 decimals, token B has 9
+    let curve = stable(100, 1_000, 1, Depeg::default());
+    let out = quote_swap(&NO_FEES, &curve, 1_000_000_000_000, 1_000_000_000, 1_000_000_000, TradeDirection::BtoA)
+        .unwrap()
+        .amount_out;
+    assert!((999_990..=1_000_000).contains(&out), "{}", out);
+}
+
+#[test]
+fn stable_curve_prices_the_depeg_token_at_its_virtual_price() {
+    let depeg = Depeg {
+        base_virtual_price: (DEPEG_PRECISION * 11 / 10) as u64,
+        base_cache_updated: 0,
+        depeg_type: DepegType::Marinade,
+    };
+    let curve = stable(100, 1, 1, depeg);
+
+    // Token B is worth 1.1 token A
+    let out = quote_swap(&NO_FEES, &curve, 1_000_000_000_000, 1_100_000_000_000, 1_000_000, TradeDirection::BtoA)
+        .unwrap()
+        .amount_out;
+    assert!((1_099_980..=1_100_000).contains(&out), "{}", out);
+}
+
+fn pool_account_data(pool: &Pool) -> Vec<u8> {
+    let mut data = account_discriminator("Pool").to_vec();
+    pool.serialize(&mut data).unwrap();
+    // Real accounts are allocated larger than the serialized struct
+    data.extend_from_slice(&[0u8; 64]);
+    data
+}
+
+#[test]
+fn quotes_from_pool_account_data() {
+    let pool = Pool {
+        enabled: true,
+        fees: FEES,
+        curve_type: stable(100, 1, 1, Depeg::default()),
+        ..Default::default()
+    };
+
+    let decoded = Pool::from_account_data(&pool_account_data(&pool)).unwrap();
+    assert_eq!(decoded.fees, FEES);
+    assert_eq!(decoded.curve_type, pool.curve_type);
+
+    let quote = decoded.quote(2_000_000, 1_000_000, 10_000, TradeDirection::BtoA).unwrap();
+    let expected = quote_swap(&FEES, &pool.curve_type, 1_000_000, 2_000_000, 10_000, TradeDirection::BtoA).unwrap();
+    assert_eq!(quote, expected);
+}
+
+#[test]
+fn rejects_disabled_or_truncated_pools() {
+    let pool = Pool::default();
+    let data = pool_account_data(&pool);
+
+    let decoded = Pool::from_account_data(&data).unwrap();
+    assert!(decoded.quote(1_000, 1_000, 10, TradeDirection::BtoA).is_err());
+
+    assert!(Pool::from_account_data(&data[..100]).is_err());
+    assert!(Pool::from_account_data(&[0u8; 4]).is_err());
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use common::{anchor_account_data, build_protocol_config, build_token_account, executable_account, program_account, run_instruction};
+use matrix_system::airdrop::AirdropState;
+use matrix_system::config::ProtocolConfig;
+use matrix_system::oracle::StalePricePolicy;
+use matrix_system::pause::PauseFlags;
+use matrix_system::reentrancy::{ReentrancyGuard, IS_LOCKED_OFFSET};
+use matrix_system::{instruction, ErrorCode, ProgramState, UserAccount};
+
+fn state_data(is_locked: bool) -> Vec<u8> {
+    let state = ProgramState {
+        owner: Pubkey::new_unique(),
+        multisig_treasury: Pubkey::new_unique(),
+        next_upline_id: 7,
+        next_chain_id: 9,
+        is_locked,
+        stale_price_policy: StalePricePolicy::Reject,
+        admin_sol_price: 0,
+        admin_price_expires_at: 0,
+        pending_owner: None,
+        ownership_transfer_eta: 0,
+        pause_flags: PauseFlags::default(),
+        last_mint_amount: 0,
+    };
+
+    let mut data = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn is_locked(state: &AccountInfo) -> bool {
+    state.try_borrow_data().unwrap()[IS_LOCKED_OFFSET] != 0
+}
+
+// Instruction body that may fail after taking the lock
+fn locked_operation(state: &AccountInfo, fail: bool) -> Result<()> {
+    let _guard = ReentrancyGuard::acquire(state)?;
+    assert!(is_locked(state));
+
+    if fail {
+        return Err(error!(ErrorCode::SwapFailed));
+    }
+    Ok(())
+}
+
+#[test]
+fn lock_offset_matches_program_state_layout() {
+    assert_eq!(state_data(false)[IS_LOCKED_OFFSET], 0);
+    assert_eq!(state_data(true)[IS_LOCKED_OFFSET], 1);
+
+    let data = state_data(true);
+    let state = ProgramState::try_deserialize(&mut data.as_slice()).unwrap();
+    assert!(state.is_locked);
+}
+
+#[test]
+fn guard_holds_lock_until_dropped() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = state_data(false);
+    let state = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &matrix_system::ID, false, 0);
+
+    let guard = ReentrancyGuard::acquire(&state).unwrap();
+    assert!(is_locked(&state));
+
+    drop(guard);
+    assert!(!is_locked(&state));
+}
+
+#[test]
+fn rejects_nested_acquire() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = state_data(false);
+    let state = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &matrix_system::ID, false, 0);
+
+    let _guard = ReentrancyGuard::acquire(&state).unwrap();
+    let err = ReentrancyGuard::acquire(&state).err().unwrap();
+
+    assert_eq!(err, Error::from(ErrorCode::ReentrancyLock));
+    // The failed attempt leaves the outer lock in place
+    assert!(is_locked(&state));
+}
+
+#[test]
+fn rejects_state_that_is_already_locked() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = state_data(true);
+    let state = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &matrix_system::ID, false, 0);
+
+    let err = ReentrancyGuard::acquire(&state).err().unwrap();
+    assert_eq!(err, Error::from(ErrorCode::ReentrancyLock));
+}
+
+#[test]
+fn releases_lock_on_success_and_on_error() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = state_data(false);
+    let state = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &matrix_system::ID, false, 0);
+
+    assert!(locked_operation(&state, false).is_ok());
+    assert!(!is_locked(&state));
+
+    assert_eq!(locked_operation(&state, true).unwrap_err(), Error::from(ErrorCode::SwapFailed));
+    assert!(!is_locked(&state));
+
+    // Lock can be taken again after either exit path
+    assert!(locked_operation(&state, false).is_ok());
+}
+
+#[test]
+fn rejects_truncated_state_account() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = vec![0u8; IS_LOCKED_OFFSET];
+    let state = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &matrix_system::ID, false, 0);
+
+    assert!(ReentrancyGuard::acquire(&state).is_err());
+}
+
+#[test]
+fn rejects_state_account_that_is_borrowed() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = state_data(false);
+    let state = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &matrix_system::ID, false, 0);
+
+    let borrowed = state.try_borrow_data().unwrap();
+    assert!(ReentrancyGuard::acquire(&state).is_err());
+    drop(borrowed);
+
+    assert!(!is_locked(&state));
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &matrix_system::ID).0
+}
+
+fn user_data(wallet: Pubkey, claimable_sol: u64, total_donut_earned: u64) -> Vec<u8> {
+    let user = UserAccount {
+        is_registered: true,
+        owner_wallet: wallet,
+        claimable_sol,
+        total_donut_earned,
+        ..Default::default()
+    };
+    anchor_account_data(&user, 8 + UserAccount::SIZE)
+}
+
+fn claim_rewards_accounts(is_locked: bool, wallet: Pubkey, claimable_sol: u64) -> Vec<AccountInfo<'static>> {
+    vec![
+        program_account(Pubkey::new_unique(), matrix_system::ID, false, true, state_data(is_locked)),
+        program_account(wallet, System::id(), true, true, vec![]),
+        program_account(pda(&[b"user_account", wallet.as_ref()]), matrix_system::ID, false, true, user_data(wallet, claimable_sol, 0)),
+        program_account(pda(&[b"program_sol_vault"]), System::id(), false, true, vec![]),
+        executable_account(System::id()),
+    ]
+}
+
+fn claim_donut_accounts(is_locked: bool, wallet: Pubkey) -> Vec<AccountInfo<'static>> {
+    let config = build_protocol_config();
+    let mint = config.addresses.token_mint;
+    let vault_authority = pda(&[b"token_vault_authority"]);
+
+    vec![
+        program_account(Pubkey::new_unique(), matrix_system::ID, false, true, state_data(is_locked)),
+        program_account(pda(&[b"protocol_config"]), matrix_system::ID, false, false, anchor_account_data(&config, 8 + ProtocolConfig::SIZE)),
+        program_account(pda(&[b"airdrop_state"]), matrix_system::ID, false, true, anchor_account_data(&AirdropState::default(), 8 + AirdropState::SIZE)),
+        program_account(wallet, System::id(), true, false, vec![]),
+        program_account(pda(&[b"user_account", wallet.as_ref()]), matrix_system::ID, false, true, user_data(wallet, 0, 1_000)),
+        program_account(get_associated_token_address(&wallet, &mint), spl_token::ID, false, true, build_token_account(&mint, &wallet, 0)),
+        program_account(get_associated_token_address(&vault_authority, &mint), spl_token::ID, false, true, build_token_account(&mint, &vault_authority, 1_000)),
+        program_account(vault_authority, System::id(), false, false, vec![]),
+        program_account(mint, spl_token::ID, false, false, vec![]),
+        executable_account(spl_token::ID),
+    ]
+}
+
+fn reentrancy_error() -> ProgramError {
+    ProgramError::Custom(u32::from(ErrorCode::ReentrancyLock))
+}
+
+#[test]
+fn claim_rewards_is_rejected_while_the_lock_is_held() {
+    let accounts = claim_rewards_accounts(true, Pubkey::new_unique(), 5_000);
+    let state = accounts[0].clone();
+    let user = accounts[2].clone();
+
+    assert_eq!(run_instruction(accounts, instruction::ClaimRewards {}), Err(reentrancy_error()));
+
+    // Nothing was paid out
+    let user = UserAccount::try_deserialize(&mut &user.try_borrow_data().unwrap()[..]).unwrap();
+    assert_eq!(user.claimable_sol, 5_000);
+    assert!(is_locked(&state));
+}
+
+#[test]
+fn claim_rewards_takes_and_releases_the_lock() {
+    let accounts = claim_rewards_accounts(false, Pubkey::new_unique(), 5_000);
+    let state = accounts[0].clone();
+    let user = accounts[2].clone();
+
+    run_instruction(accounts, instruction::ClaimRewards {}).unwrap();
+
+    let user = UserAccount::try_deserialize(&mut &user.try_borrow_data().unwrap()[..]).unwrap();
+    assert_eq!(user.claimable_sol, 0);
+    assert!(!is_locked(&state));
+}
+
+#[test]
+fn claim_donut_is_rejected_while_the_lock_is_held() {
+    let accounts = claim_donut_accounts(true, Pubkey::new_unique());
+
+    assert_eq!(run_instruction(accounts, instruction::ClaimDonut {}), Err(reentrancy_error()));
+}
+
+#[test]
+fn claim_donut_gets_past_the_lock_when_it_is_free() {
+    let accounts = claim_donut_accounts(false, Pubkey::new_unique());
+    let state = accounts[0].clone();
+
+    // The lock is taken and released; the claim then needs the clock, which tests do not have
+    let err = run_instruction(accounts, instruction::ClaimDonut {}).unwrap_err();
+    assert_ne!(err, reentrancy_error());
+    assert!(!is_locked(&state));
+}
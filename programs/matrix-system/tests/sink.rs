@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use matrix_system::sink::{check_reserve_withdrawal, DepositLedger, TerminalSinkPolicy};
+
+const DEPOSIT: u64 = 100_000_000;
+const WALLET: u64 = 5_000_000_000;
+const VAULT_RENT: u64 = 890_880;
+
+#[test]
+fn unused_deposits_are_burned_by_default() {
+    assert_eq!(TerminalSinkPolicy::default(), TerminalSinkPolicy::Burn);
+}
+
+#[test]
+fn policy_size_matches_serialization() {
+    assert_eq!(TerminalSinkPolicy::Reserve.try_to_vec().unwrap().len(), TerminalSinkPolicy::SIZE);
+    assert_eq!(TerminalSinkPolicy::try_from_slice(&[1]).unwrap(), TerminalSinkPolicy::Treasury);
+}
+
+#[test]
+fn ledger_accepts_a_fully_spent_deposit() {
+    let ledger = DepositLedger::open(DEPOSIT, WALLET);
+    ledger.check(WALLET - DEPOSIT).unwrap();
+}
+
+#[test]
+fn ledger_rejects_deposit_lamports_left_behind() {
+    let ledger = DepositLedger::open(DEPOSIT, WALLET);
+
+    let err = ledger.check(WALLET).unwrap_err();
+    assert!(err.to_string().contains("DepositUnaccounted"));
+
+    let err = ledger.check(WALLET - DEPOSIT / 2).unwrap_err();
+    assert!(err.to_string().contains("DepositUnaccounted"));
+}
+
+#[test]
+fn ledger_rejects_spending_more_than_the_deposit() {
+    let ledger = DepositLedger::open(DEPOSIT, WALLET);
+
+    let err = ledger.check(WALLET - DEPOSIT - 1).unwrap_err();
+    assert!(err.to_string().contains("DepositUnaccounted"));
+}
+
+#[test]
+fn ledger_counts_overhead_and_kept_lamports() {
+    // A registration that parks its deposit in a new cascade vault also pays the vault's rent
+    let mut ledger = DepositLedger::open(DEPOSIT, WALLET);
+    ledger.add_overhead(VAULT_RENT).unwrap();
+    ledger.check(WALLET - DEPOSIT - VAULT_RENT).unwrap();
+
+    // A cascade step that forwards the deposit leaves it in the vault
+    let vault = VAULT_RENT + DEPOSIT;
+    let mut ledger = DepositLedger::open(DEPOSIT, vault);
+    ledger.keep(DEPOSIT).unwrap();
+    ledger.check(vault).unwrap();
+    assert!(ledger.check(VAULT_RENT).unwrap_err().to_string().contains("DepositUnaccounted"));
+}
+
+#[test]
+fn reserve_withdrawals_leave_the_reserve_rent_exempt() {
+    let reserve = VAULT_RENT + DEPOSIT;
+    check_reserve_withdrawal(DEPOSIT, reserve, VAULT_RENT).unwrap();
+    check_reserve_withdrawal(1, reserve, VAULT_RENT).unwrap();
+
+    let err = check_reserve_withdrawal(DEPOSIT + 1, reserve, VAULT_RENT).unwrap_err();
+    assert!(err.to_string().contains("InvalidReserveWithdrawal"));
+    let err = check_reserve_withdrawal(0, reserve, VAULT_RENT).unwrap_err();
+    assert!(err.to_string().contains("InvalidReserveWithdrawal"));
+    assert!(check_reserve_withdrawal(1, VAULT_RENT - 1, VAULT_RENT).is_err());
+}
@@ -0,0 +1,71 @@
+use matrix_system::price_guard::PRICE_SCALE;
+use matrix_system::price_tracker::PriceTracker;
+use matrix_system::slippage::{
+    bounded_minimum_amount_out, check_amount_out, check_slippage_bps, clamp_slippage_bps, minimum_amount_out,
+    validate_max_slippage_bps, BPS_DENOMINATOR, DEFAULT_MAX_SLIPPAGE_BPS,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn ceiling_must_be_below_one_hundred_percent() {
+    assert!(validate_max_slippage_bps(0).is_ok());
+    assert!(validate_max_slippage_bps(DEFAULT_MAX_SLIPPAGE_BPS).is_ok());
+    assert!(validate_max_slippage_bps(BPS_DENOMINATOR as u16 - 1).is_ok());
+    assert!(validate_max_slippage_bps(BPS_DENOMINATOR as u16).is_err());
+}
+
+#[test]
+fn requested_slippage_is_capped_by_the_config() {
+    assert!(check_slippage_bps(100, 500).is_ok());
+    assert!(check_slippage_bps(500, 500).is_ok());
+
+    let err = check_slippage_bps(501, 500).unwrap_err();
+    assert!(err.to_string().contains("SlippageAboveMaximum"));
+}
+
+#[test]
+fn crank_slippage_is_clamped_to_the_config() {
+    assert_eq!(clamp_slippage_bps(100, 500), 100);
+    assert_eq!(clamp_slippage_bps(500, 500), 500);
+    assert_eq!(clamp_slippage_bps(9_000, 500), 500);
+}
+
+#[test]
+fn minimum_out_rounds_up_and_is_never_zero() {
+    assert_eq!(minimum_amount_out(1_000_000, 0), 1_000_000);
+    assert_eq!(minimum_amount_out(1_000_000, 500), 950_000);
+    assert_eq!(minimum_amount_out(999, 100), 990);
+    assert_eq!(minimum_amount_out(0, 100), 1);
+    assert_eq!(minimum_amount_out(u64::MAX, 1), (u64::MAX as u128 * 9_999 / 10_000 + 1) as u64);
+}
+
+#[test]
+fn short_swap_fails_with_a_slippage_error() {
+    assert!(check_amount_out(950_000, 950_000).is_ok());
+
+    let err = check_amount_out(949_999, 950_000).unwrap_err();
+    assert!(err.to_string().contains("SlippageExceeded"));
+}
+
+#[test]
+fn sandwiched_quote_cannot_lower_the_minimum_below_the_twap() {
+    // 2_500 DONUT per SOL held for an hour
+    let mut tracker = PriceTracker::default();
+    tracker.update(2_500 * PRICE_SCALE, NOW - 3_600);
+    tracker.update(2_500 * PRICE_SCALE, NOW - 1_800);
+
+    // Reserves pushed earlier in the transaction make 1 SOL quote only 2_000 DONUT
+    let fair_out = tracker.donut_value(1_000_000_000, NOW);
+    assert_eq!(fair_out, Some(2_500_000_000_000));
+
+    let minimum = bounded_minimum_amount_out(2_000_000_000_000, fair_out, 500);
+    assert_eq!(minimum, minimum_amount_out(2_500_000_000_000, 500));
+    assert!(check_amount_out(2_000_000_000_000, minimum).is_err());
+}
+
+#[test]
+fn better_quote_or_missing_twap_keeps_the_quoted_minimum() {
+    assert_eq!(bounded_minimum_amount_out(3_000, Some(2_500), 100), minimum_amount_out(3_000, 100));
+    assert_eq!(bounded_minimum_amount_out(3_000, None, 100), minimum_amount_out(3_000, 100));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{SimulatedWsolAccount, TOKEN_ACCOUNT_RENT};
+use matrix_system::wsol::WsolSession;
+
+const SOL: u64 = 1_000_000_000;
+
+#[test]
+fn open_counts_unsynced_lamports_as_held() {
+    let account = SimulatedWsolAccount::new(10 * SOL, 2 * SOL);
+    account.airdrop(SOL / 2);
+
+    let session = WsolSession::open(account.clone()).unwrap();
+
+    assert_eq!(session.held_before(), 2 * SOL + SOL / 2);
+    assert_eq!(session.available().unwrap(), 0);
+    assert_eq!(account.state().syncs, 1);
+}
+
+#[test]
+fn wraps_only_the_missing_deposit() {
+    let account = SimulatedWsolAccount::new(10 * SOL, 0);
+    let mut session = WsolSession::open(account.clone()).unwrap();
+
+    session.ensure_wrapped(SOL).unwrap();
+    assert_eq!(session.available().unwrap(), SOL);
+    assert_eq!(session.wrapped(), SOL);
+
+    // Asking again for the same deposit wraps nothing
+    session.ensure_wrapped(SOL).unwrap();
+    assert_eq!(account.state().transfers, 1);
+
+    // A larger request tops the account up by the difference
+    session.ensure_wrapped(SOL + SOL / 4).unwrap();
+    assert_eq!(account.state().transfers, 2);
+    assert_eq!(session.wrapped(), SOL + SOL / 4);
+    assert_eq!(account.state().wallet_lamports, 10 * SOL - SOL - SOL / 4);
+}
+
+#[test]
+fn cascade_wraps_each_spent_deposit_once() {
+    let account = SimulatedWsolAccount::new(10 * SOL, 3 * SOL);
+    let mut session = WsolSession::open(account.clone()).unwrap();
+
+    // Slots that pass the deposit on never touch the WSOL account; each spending slot wraps once
+    for _ in 0..3 {
+        session.ensure_wrapped(SOL / 10).unwrap();
+        account.spend(SOL / 10).unwrap();
+    }
+
+    let state = account.state();
+    assert_eq!(state.transfers, 3);
+    assert_eq!(state.amount, 3 * SOL);
+    assert_eq!(state.lamports, TOKEN_ACCOUNT_RENT + 3 * SOL);
+    assert_eq!(session.settle().unwrap(), 0);
+}
+
+#[test]
+fn settle_returns_the_unspent_deposit() {
+    let account = SimulatedWsolAccount::new(10 * SOL, SOL);
+    let mut session = WsolSession::open(account.clone()).unwrap();
+
+    session.ensure_wrapped(SOL / 2).unwrap();
+    account.spend(SOL / 5).unwrap();
+
+    assert_eq!(session.settle().unwrap(), SOL / 2 - SOL / 5);
+}
+
+#[test]
+fn held_wsol_is_never_spent() {
+    let account = SimulatedWsolAccount::new(10 * SOL, SOL);
+    let mut session = WsolSession::open(account.clone()).unwrap();
+
+    // Spending beyond the wrapped deposit eats into the WSOL the user held
+    session.ensure_wrapped(SOL / 2).unwrap();
+    account.spend(SOL).unwrap();
+
+    let err = session.settle().unwrap_err();
+    assert!(err.to_string().contains("UserWsolBalanceChanged"));
+    let err = session.ensure_wrapped(SOL / 2).unwrap_err();
+    assert!(err.to_string().contains("UserWsolBalanceChanged"));
+}
+
+#[test]
+fn wrap_fails_when_the_wallet_is_short() {
+    let account = SimulatedWsolAccount::new(SOL / 2, 0);
+    let mut session = WsolSession::open(account.clone()).unwrap();
+
+    let err = session.ensure_wrapped(SOL).unwrap_err();
+    assert!(err.to_string().contains("WrapSolFailed"));
+    assert_eq!(session.wrapped(), 0);
+    assert_eq!(account.state().lamports, TOKEN_ACCOUNT_RENT);
+}